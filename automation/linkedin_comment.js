@@ -1,40 +1,39 @@
-import { chromium } from "playwright";
 import path from "path";
 import { fileURLToPath } from "url";
+import { contextOptions, dryRun, dryRunStop, ensureSession, launch, record, requireText, result, run, step } from "./protocol.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
 
-const authPath = path.join(__dirname, "auth.json");
-
-// usage: node linkedin_comment.js "your comment text"
-const commentText = process.argv.slice(2).join(" ").trim();
-if (!commentText) {
-  console.error("❌ No comment provided. Usage: linkedin_comment.js <text>");
-  process.exit(1);
-}
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
 
 async function safeClick(locator, timeout = 15000) {
   await locator.first().waitFor({ state: "visible", timeout });
   await locator.first().click({ timeout });
 }
 
-(async () => {
-  const browser = await chromium.launch({ headless: false });
-  const context = await browser.newContext({ storageState: authPath });
+// usage: node linkedin_comment.js "your comment text"
+run(async (res) => {
+  const commentText = requireText(
+    process.argv.slice(2).join(" ").trim(),
+    "No comment provided. Usage: linkedin_comment.js <text>"
+  );
+
+  const browser = await launch();
+  res.browser = browser;
+  const context = await browser.newContext(contextOptions({ storageState: authPath }));
   const page = await context.newPage();
+  await record(res, context, page);
 
   // 1) Go to LinkedIn content search for #openclaw
+  await step(page, "Searching #openclaw posts", "search");
   const url =
     "https://www.linkedin.com/search/results/content/?keywords=%23openclaw&origin=SWITCH_SEARCH_VERTICAL";
   await page.goto(url, { waitUntil: "domcontentloaded" });
 
   // If session invalid
-  if (page.url().includes("/login")) {
-    console.error("❌ Session not valid. Run `linkedin login` again.");
-    await browser.close();
-    process.exit(1);
-  }
+  ensureSession(page);
 
   // 2) Wait for results to render
   await page.waitForTimeout(2000);
@@ -45,9 +44,11 @@ async function safeClick(locator, timeout = 15000) {
   const firstPost = page.locator('[data-urn^="urn:li:activity"]').first();
 
   await firstPost.waitFor({ state: "visible", timeout: 20000 });
+  const postUrn = await firstPost.getAttribute("data-urn");
 
   // 4) Click the Comment button/icon in that post
   // Common labels: "Comment", "Comment on", "Comment button"
+  await step(page, "Opening comment box", "open_comment");
   const commentBtn = firstPost
     .getByRole("button", { name: /comment/i })
     .or(firstPost.locator('button[aria-label*="Comment"]'));
@@ -63,6 +64,7 @@ async function safeClick(locator, timeout = 15000) {
   await editor.waitFor({ state: "visible", timeout: 20000 });
 
   // Focus + type
+  await step(page, "Typing comment", "type");
   await editor.click();
   // Fill sometimes fails on contenteditable depending on LinkedIn
   // so we use type
@@ -86,11 +88,13 @@ async function safeClick(locator, timeout = 15000) {
     await page.waitForTimeout(500);
   }
 
+  if (dryRun) return dryRunStop(page, "comment");
+
+  await step(page, "Publishing", "submit");
   await submitBtn.first().click({ timeout: 20000 });
 
   // 7) Small wait so you can see it happened
   await page.waitForTimeout(2000);
 
-  console.log("✅ Comment posted successfully on #openclaw content.");
-  await browser.close();
-})();
+  result("✅ Comment posted successfully on #openclaw content.", { post: postUrn });
+});
@@ -1,42 +1,38 @@
-import { chromium } from "playwright";
 import path from "path";
 import { fileURLToPath } from "url";
+import { contextOptions, dryRun, dryRunStop, ensureSession, launch, record, requireText, result, run, step } from "./protocol.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
 
-// session file next to scripts
-const authPath = path.join(__dirname, "auth.json");
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
 
-const commentText = process.argv.slice(2).join(" ").trim();
-if (!commentText) {
-  console.error("❌ No comment text provided.");
-  process.exit(1);
-}
+run(async (res) => {
+  const commentText = requireText(process.argv.slice(2).join(" ").trim(), "No comment text provided.");
 
-(async () => {
-  const browser = await chromium.launch({ headless: false });
-  const context = await browser.newContext({ storageState: authPath });
+  const browser = await launch();
+  res.browser = browser;
+  const context = await browser.newContext(contextOptions({ storageState: authPath }));
   const page = await context.newPage();
+  await record(res, context, page);
 
   // Go directly to hashtag results
+  await step(page, "Searching #openclaw posts", "search");
   await page.goto(
     "https://www.linkedin.com/search/results/content/?keywords=%23openclaw&origin=GLOBAL_SEARCH_HEADER",
     { waitUntil: "domcontentloaded" }
   );
 
   // If session is invalid, LinkedIn will redirect to login
-  if (page.url().includes("/login")) {
-    console.error("❌ Session not valid. Run `linkedin login` again.");
-    await browser.close();
-    process.exit(1);
-  }
+  ensureSession(page);
 
   // Open first result and comment (simple + demo-friendly)
   // Click first result card (LinkedIn UI changes often, so we keep it robust)
   await page.waitForTimeout(2000);
 
   // Try to click the first visible "Comment" button
+  await step(page, "Opening comment box", "open_comment");
   const commentBtn = page.getByRole("button", { name: /comment/i }).first();
   await commentBtn.click();
 
@@ -44,15 +40,18 @@ if (!commentText) {
   await page.waitForTimeout(1200);
 
   // This grabs the active textbox after opening comment
+  await step(page, "Typing comment", "type");
   const box = page.getByRole("textbox").last();
   await box.click();
   await box.fill(commentText);
 
+  if (dryRun) return dryRunStop(page, "comment");
+
   // Click "Post" button for comment
+  await step(page, "Publishing", "submit");
   const postBtn = page.getByRole("button", { name: /^post$/i }).first();
   await postBtn.click();
 
-  console.log("✅ Comment posted on #openclaw content.");
   await page.waitForTimeout(1500);
-  await browser.close();
-})();
+  result("✅ Comment posted on #openclaw content.");
+});
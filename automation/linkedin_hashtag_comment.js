@@ -1,31 +1,27 @@
-import { chromium } from "playwright";
 import path from "path";
 import { fileURLToPath } from "url";
+import { contextOptions, dryRun, dryRunStop, ensureSession, launch, record, requireText, result, run, step, warning } from "./protocol.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
-const authPath = path.join(__dirname, "auth.json");
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
 
-const commentText = process.argv.slice(2).join(" ").trim();
-if (!commentText) {
-  console.error("❌ No comment text provided.");
-  process.exit(1);
-}
+run(async (res) => {
+  const commentText = requireText(process.argv.slice(2).join(" ").trim(), "No comment text provided.");
 
-(async () => {
-  const browser = await chromium.launch({ headless: false });
+  const browser = await launch();
+  res.browser = browser;
 
-  const context = await browser.newContext({ storageState: authPath });
+  const context = await browser.newContext(contextOptions({ storageState: authPath }));
   const page = await context.newPage();
+  await record(res, context, page);
 
+  await step(page, "Searching #openclaw posts", "search");
   await page.goto("https://www.linkedin.com/search/results/content/?keywords=%23openclaw");
   await page.waitForLoadState("domcontentloaded");
 
-  if (page.url().includes("/login")) {
-    console.error("❌ Session invalid. Run `linkedin login` again.");
-    await browser.close();
-    process.exit(1);
-  }
+  ensureSession(page);
 
   // Scroll a bit so posts load
   await page.mouse.wheel(0, 1200);
@@ -36,13 +32,14 @@ if (!commentText) {
   const count = await commentButtons.count();
 
   if (count === 0) {
-    console.log("⚠️ No comment buttons found.");
-    await browser.close();
+    warning("No comment buttons found.");
+    result("⚠️ Nothing to comment on.", { commented: 0 });
     return;
   }
 
   const max = Math.min(2, count);
   for (let i = 0; i < max; i++) {
+    await step(page, `Commenting on post ${i + 1}/${max}`, "comment");
     await commentButtons.nth(i).click();
     await page.waitForTimeout(800);
 
@@ -53,11 +50,11 @@ if (!commentText) {
 
     // Click "Post" for comment
     const postBtn = page.getByRole("button", { name: /^post$/i }).last();
+    if (dryRun) return dryRunStop(page, "comment");
     await postBtn.click();
 
     await page.waitForTimeout(2000);
   }
 
-  console.log(`✅ Commented on ${max} post(s) under #openclaw`);
-  await browser.close();
-})();
+  result(`✅ Commented on ${max} post(s) under #openclaw`, { commented: max });
+});
@@ -1,30 +1,36 @@
-import { chromium } from "playwright";
 import path from "path";
 import fs from "fs";
 import { fileURLToPath } from "url";
+import { ErrorCode, ScriptError, contextOptions, launch, progress, result, run } from "./protocol.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
 
-const authPath = path.join(__dirname, "auth.json");
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
+// no record(): a trace of the login would contain the fresh session
 
-(async () => {
-  const browser = await chromium.launch({ headless: false });
-  const context = await browser.newContext();
+run(async (res) => {
+  const browser = await launch();
+  res.browser = browser;
+  const context = await browser.newContext(contextOptions());
 
   const page = await context.newPage();
   await page.goto("https://www.linkedin.com/login");
-  console.log("✅ Please login in the opened browser window...");
+  progress("✅ Please login in the opened browser window...", "wait_login");
 
   // Wait until feed loads after login
-  await page.waitForURL(/linkedin\.com\/feed/, { timeout: 180000 });
+  await page.waitForURL(/linkedin\.com\/feed/, { timeout: 180000 }).catch((e) => {
+    throw e?.name === "TimeoutError"
+      ? new ScriptError(ErrorCode.TIMEOUT, "Login was not completed within 3 minutes.")
+      : e;
+  });
 
   // ✅ ensure folder exists
-  fs.mkdirSync(__dirname, { recursive: true });
+  fs.mkdirSync(path.dirname(authPath), { recursive: true });
 
   // ✅ save session
   await context.storageState({ path: authPath });
 
-  console.log("✅ LinkedIn session saved to auth.json");
-  await browser.close();
-})();
+  result("✅ LinkedIn session saved.");
+});
@@ -1,38 +1,33 @@
-import { chromium } from "playwright";
 import path from "path";
 import { fileURLToPath } from "url";
+import { contextOptions, dryRun, dryRunStop, ensureSession, launch, record, requireText, result, run, step } from "./protocol.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
 
-// auth.json saved next to this file
-const authPath = path.join(__dirname, "auth.json");
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
 
-const text = process.argv.slice(2).join(" ").trim();
-if (!text) {
-  console.error("❌ No post text provided. Usage: linkedin post: <text>");
-  process.exit(1);
-}
+run(async (res) => {
+  const text = requireText(process.argv.slice(2).join(" ").trim(), "No post text provided. Usage: linkedin post: <text>");
 
-(async () => {
-  const browser = await chromium.launch({ headless: false });
+  const browser = await launch();
+  res.browser = browser;
 
-  const context = await browser.newContext({
-    storageState: authPath,
-  });
+  const context = await browser.newContext(contextOptions({ storageState: authPath }));
 
   const page = await context.newPage();
 
+  await record(res, context, page);
+
+  await step(page, "Opening LinkedIn feed", "open_feed");
   await page.goto("https://www.linkedin.com/feed/", { waitUntil: "domcontentloaded" });
 
   // if session is invalid
-  if (page.url().includes("/login")) {
-    console.error("❌ Session not valid. Run `linkedin login` again.");
-    await browser.close();
-    process.exit(1);
-  }
+  ensureSession(page);
 
   // ✅ Open post composer (LinkedIn labels vary)
+  await step(page, "Opening post composer", "open_composer");
   const startBtn = page.getByRole("button", { name: /start a post/i });
   await startBtn.waitFor({ state: "visible", timeout: 20000 });
   await startBtn.click();
@@ -53,6 +48,7 @@ if (!text) {
   await editor.waitFor({ state: "visible", timeout: 20000 });
 
   // ✅ Focus editor + type (type is more reliable than fill on LinkedIn)
+  await step(page, "Typing post", "type");
   await editor.click({ timeout: 10000 });
   await page.keyboard.down("Control");
   await page.keyboard.press("A");
@@ -74,11 +70,13 @@ if (!text) {
     { timeout: 20000 }
   );
 
+  if (dryRun) return dryRunStop(page, "post");
+
+  await step(page, "Publishing", "submit");
   await postBtn.click();
 
   // ✅ optional: wait so you can see it posted
   await page.waitForTimeout(3000);
 
-  console.log("✅ Posted to LinkedIn successfully.");
-  await browser.close();
-})();
+  result("✅ Posted to LinkedIn successfully.", { chars: text.length });
+});
@@ -0,0 +1,23 @@
+import path from "path";
+import { fileURLToPath } from "url";
+import { contextOptions, ensureSession, launch, progress, result, run } from "./protocol.js";
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+// decrypted session handed over by the app (falls back to auth.json next to this file)
+const authPath = process.env.PERSONALIZ_AUTH_PATH || path.join(__dirname, "auth.json");
+
+// Opens the feed with the saved session; LinkedIn redirects to /login when it expired.
+run(async (res) => {
+  const browser = await launch();
+  res.browser = browser;
+  const context = await browser.newContext(contextOptions({ storageState: authPath }));
+  const page = await context.newPage();
+
+  progress("Opening LinkedIn feed", "open_feed");
+  await page.goto("https://www.linkedin.com/feed/", { waitUntil: "domcontentloaded" });
+  ensureSession(page);
+
+  result("✅ LinkedIn session is valid.", { url: page.url() });
+});
@@ -0,0 +1,147 @@
+// Line protocol between the app (src-tauri/src/protocol.rs) and the scripts.
+// One JSON object per stdout line:
+//   {"type":"progress","message":"Opening composer","step":"open_composer"}
+//   {"type":"warning","message":"..."}
+//   {"type":"result","message":"Posted to LinkedIn","data":{...}}
+//   {"type":"error","code":"SESSION_EXPIRED","message":"..."}
+// Anything else a script prints is shown as plain log output.
+//
+// Artifacts: the runner passes a per-run folder in PERSONALIZ_ARTIFACTS_DIR.
+// step() screenshots the page at each step; a run that fails also leaves a
+// failure screenshot and a Playwright trace (trace.zip) there. Traces include
+// cookies of the session: the app encrypts trace.zip as soon as the run ends
+// and deletes artifacts after a retention period.
+
+import path from "path";
+import { chromium, firefox, webkit } from "playwright";
+
+export const ErrorCode = {
+  SESSION_EXPIRED: "SESSION_EXPIRED",
+  SELECTOR_NOT_FOUND: "SELECTOR_NOT_FOUND",
+  TIMEOUT: "TIMEOUT",
+  INVALID_ARGS: "INVALID_ARGS",
+  FAILED: "FAILED",
+};
+
+export class ScriptError extends Error {
+  constructor(code, message) {
+    super(message);
+    this.code = code;
+  }
+}
+
+function send(msg) {
+  process.stdout.write(JSON.stringify(msg) + "\n");
+}
+
+export function progress(message, step) {
+  send({ type: "progress", message, ...(step ? { step } : {}) });
+}
+
+export function warning(message) {
+  send({ type: "warning", message });
+}
+
+export function result(message, data = {}) {
+  send({ type: "result", message, data });
+}
+
+// Browser chosen by the app (src-tauri/src/browser.rs). Run by hand without
+// these variables, scripts use a visible Chromium window as before.
+const engines = { chromium, firefox, webkit };
+
+export function launch() {
+  const engine = engines[process.env.PERSONALIZ_BROWSER] || chromium;
+  return engine.launch({ headless: process.env.PERSONALIZ_HEADLESS === "1" });
+}
+
+// newContext() options + the configured viewport
+export function contextOptions(options = {}) {
+  const m = /^(\d+)x(\d+)$/.exec(process.env.PERSONALIZ_VIEWPORT || "");
+  return m ? { ...options, viewport: { width: Number(m[1]), height: Number(m[2]) } } : options;
+}
+
+export const artifactsDir = process.env.PERSONALIZ_ARTIFACTS_DIR || null;
+
+let shots = 0;
+
+async function screenshot(page, name) {
+  if (!artifactsDir || !page) return null;
+  shots += 1;
+  const file = path.join(artifactsDir, `${String(shots).padStart(2, "0")}-${name}.png`);
+  return page.screenshot({ path: file }).then(() => file, () => null);
+}
+
+// progress + a screenshot of the page as the step starts
+export async function step(page, message, name) {
+  progress(message, name);
+  await screenshot(page, name || "step");
+}
+
+// Call right after creating the context: the trace is kept only if the run fails.
+export async function record(resources, context, page) {
+  resources.context = context;
+  resources.page = page;
+  if (!artifactsDir) return;
+  await context.tracing.start({ screenshots: true, snapshots: true }).then(
+    () => (resources.tracing = true),
+    (e) => warning(`Trace not recorded: ${e.message}`)
+  );
+}
+
+// Set by the runner: do everything up to the final submit, then stop.
+export const dryRun = process.env.PERSONALIZ_DRY_RUN === "1";
+
+// Ends a dry run right before the submit click, with a screenshot of the ready
+// page (only with an artifacts folder, so nothing is left where the app can't clean up).
+export async function dryRunStop(page, what) {
+  const file = await screenshot(page, `dry-run-${what}`);
+  result(`🧪 Dry run: ${what} ready but not submitted.`, { dry_run: true, ...(file ? { screenshot: file } : {}) });
+}
+
+// LinkedIn redirects to /login (or the checkpoint page) when the session is gone
+export function ensureSession(page) {
+  const url = page.url();
+  if (url.includes("/login") || url.includes("/checkpoint") || url.includes("/authwall")) {
+    throw new ScriptError(ErrorCode.SESSION_EXPIRED, "LinkedIn session is not valid anymore.");
+  }
+}
+
+export function requireText(text, usage) {
+  if (!text) {
+    throw new ScriptError(ErrorCode.INVALID_ARGS, usage);
+  }
+  return text;
+}
+
+function toError(e) {
+  if (e instanceof ScriptError) return { code: e.code, message: e.message };
+  // Playwright: locator.waitFor / click timeouts => the page changed under us
+  if (e?.name === "TimeoutError") {
+    const target = String(e.message || "").split("\n")[0];
+    return { code: ErrorCode.SELECTOR_NOT_FOUND, message: target || "Element not found in time." };
+  }
+  return { code: ErrorCode.FAILED, message: String(e?.message || e) };
+}
+
+// Runs the script body; an exception becomes one error line + exit code 1.
+// On failure the page is screenshotted and the trace saved (see record()).
+// A browser stored in resources.browser is closed either way.
+export async function run(main) {
+  const resources = { browser: null, context: null, page: null, tracing: false };
+  let failed = false;
+  try {
+    await main(resources);
+  } catch (e) {
+    failed = true;
+    await screenshot(resources.page, "failure");
+    send({ type: "error", ...toError(e) });
+    process.exitCode = 1;
+  } finally {
+    if (resources.tracing) {
+      const trace = failed ? { path: path.join(artifactsDir, "trace.zip") } : {};
+      await resources.context.tracing.stop(trace).catch(() => {});
+    }
+    await resources.browser?.close().catch(() => {});
+  }
+}
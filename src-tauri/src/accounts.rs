@@ -0,0 +1,372 @@
+// -------------------------
+// ✅ Social accounts
+// One row per identity on a platform (platform + label), e.g. linkedin/default
+// and linkedin/company-page, each with its own encrypted session file
+// (secrets.rs). Agents are bound to an account with agents.account_id
+// (NULL => the platform's default account); the runner decrypts that
+// account's session for the script. Session health is tracked per account
+// (session_health.rs).
+// -------------------------
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection};
+
+use crate::{automations, ensure_agents_table, ensure_column, open_db, secrets, write_log, write_log_agent};
+
+pub const DEFAULT_LABEL: &str = "default";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub id: String,
+    pub platform: String,
+    pub label: String,
+    pub session_path: PathBuf,
+}
+
+impl Account {
+    pub fn is_default(&self) -> bool {
+        self.label == DEFAULT_LABEL
+    }
+
+    pub fn has_session(&self) -> bool {
+        self.session_path.exists()
+    }
+
+    // "linkedin/company-page"
+    pub fn display(&self) -> String {
+        format!("{}/{}", self.platform, self.label)
+    }
+}
+
+pub fn ensure_accounts_table(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS accounts (
+            id TEXT PRIMARY KEY,
+            platform TEXT NOT NULL,
+            label TEXT NOT NULL,
+            session_path TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE (platform, label)
+        )",
+        [],
+    );
+    ensure_agents_table(conn);
+    ensure_column(conn, "agents", "account_id TEXT NULL");
+
+    // the single session from before accounts existed becomes linkedin/default
+    let _ = conn.execute(
+        "INSERT OR IGNORE INTO accounts (id, platform, label, session_path, created_at)
+         VALUES ('linkedin-default', 'linkedin', ?1, ?2, datetime('now'))",
+        params![DEFAULT_LABEL, secrets::default_session_path().to_string_lossy().to_string()],
+    );
+}
+
+fn from_row(r: &rusqlite::Row) -> rusqlite::Result<Account> {
+    Ok(Account {
+        id: r.get(0)?,
+        platform: r.get(1)?,
+        label: r.get(2)?,
+        session_path: PathBuf::from(r.get::<_, String>(3)?),
+    })
+}
+
+pub fn get(conn: &Connection, id: &str) -> Option<Account> {
+    ensure_accounts_table(conn);
+    conn.query_row(
+        "SELECT id, platform, label, session_path FROM accounts WHERE id=?1",
+        [id],
+        from_row,
+    )
+    .ok()
+}
+
+pub fn find(conn: &Connection, platform: &str, label: &str) -> Option<Account> {
+    ensure_accounts_table(conn);
+    conn.query_row(
+        "SELECT id, platform, label, session_path FROM accounts WHERE platform=?1 AND label=?2 COLLATE NOCASE",
+        params![platform, label.trim()],
+        from_row,
+    )
+    .ok()
+}
+
+// None / "" => the platform's default account; labels match the way add_account stores them
+pub fn find_or_default(conn: &Connection, platform: &str, label: Option<&str>) -> Result<Account, String> {
+    let platform = platform.trim().to_lowercase();
+    let label = match label.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(l) => normalize_label(l)?,
+        None => DEFAULT_LABEL.to_string(),
+    };
+    find(conn, &platform, &label).ok_or_else(|| {
+        format!(
+            "❌ No {} account '{}'. Add it with: account add {} {}",
+            platform, label, platform, label
+        )
+    })
+}
+
+// Account whose session a run uses: explicit account > the agent's account > default
+pub fn for_run(
+    conn: &Connection,
+    platform: &str,
+    account_id: Option<&str>,
+    agent_id: Option<&str>,
+) -> Result<Account, String> {
+    ensure_accounts_table(conn);
+
+    let bound: Option<String> = agent_id.and_then(|id| {
+        conn.query_row("SELECT account_id FROM agents WHERE id=?1", [id], |r| r.get(0))
+            .ok()
+            .flatten()
+    });
+
+    match account_id.map(str::to_string).or(bound) {
+        Some(id) => {
+            let account = get(conn, &id).ok_or_else(|| format!("❌ Account {} no longer exists.", id))?;
+            if account.platform != platform {
+                return Err(format!(
+                    "❌ Account {} is a {} account, but this automation needs {}.",
+                    account.display(),
+                    account.platform,
+                    platform
+                ));
+            }
+            Ok(account)
+        }
+        None => find_or_default(conn, platform, None),
+    }
+}
+
+// Agents that run with this account (bound to it, or unbound on the default account)
+pub fn agents_using(conn: &Connection, account: &Account) -> Vec<(String, String, String)> {
+    ensure_accounts_table(conn);
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT id, name, tools_json FROM agents WHERE account_id=?1 OR (account_id IS NULL AND ?2)",
+    ) else {
+        return vec![];
+    };
+    stmt.query_map(params![account.id, account.is_default()], |r| {
+        Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+    })
+    .map(|rows| rows.flatten().collect())
+    .unwrap_or_default()
+}
+
+// platforms some registered automation has a session for
+fn known_platforms() -> Vec<String> {
+    let mut platforms: Vec<String> = automations::load_registry()
+        .manifests
+        .into_iter()
+        .filter_map(|m| m.session)
+        .collect();
+    platforms.sort();
+    platforms.dedup();
+    platforms
+}
+
+fn normalize_label(label: &str) -> Result<String, String> {
+    let label = label.trim().to_lowercase().replace(' ', "-");
+    if label.is_empty()
+        || label.len() > 40
+        || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("❌ Account label: 1-40 letters, digits, '-' or '_'.".to_string());
+    }
+    Ok(label)
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+redacted! {
+    #[tauri::command]
+    pub fn list_accounts() -> Result<String, String> {
+        let conn = open_db()?;
+        crate::session_health::ensure_session_tables(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, platform, label, session_path, session_status, last_verified_at FROM accounts
+             ORDER BY platform, label <> 'default', label",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows: Vec<(Account, Option<String>, Option<String>)> = stmt
+            .query_map([], |r| Ok((from_row(r)?, r.get(4)?, r.get(5)?)))
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+
+        let mut out = String::from("👤 Accounts:\n");
+        for (a, status, verified) in rows {
+            let agents: Vec<String> = agents_using(&conn, &a).into_iter().map(|(_, name, _)| name).collect();
+            out.push_str(&format!(
+                "\n• {}\n   session: {}{}\n   last verified: {}\n   agents: {}\n",
+                a.display(),
+                if a.has_session() { "✅ saved" } else { "❌ not logged in" },
+                status.map(|s| format!(" ({})", s)).unwrap_or_default(),
+                verified.unwrap_or_else(|| "never".to_string()),
+                if agents.is_empty() { "-".to_string() } else { agents.join(", ") }
+            ));
+        }
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn add_account(platform: String, label: String) -> Result<String, String> {
+        let platform = platform.trim().to_lowercase();
+        let known = known_platforms();
+        if !known.contains(&platform) {
+            return Err(format!("❌ Unknown platform '{}'. Known: {}", platform, known.join(", ")));
+        }
+        let label = normalize_label(&label)?;
+
+        let conn = open_db()?;
+        ensure_accounts_table(&conn);
+        if find(&conn, &platform, &label).is_some() {
+            return Err(format!("❌ Account {}/{} already exists.", platform, label));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let session_path = secrets::sessions_dir().join(format!("{}-{}.json.enc", platform, id));
+        conn.execute(
+            "INSERT INTO accounts (id, platform, label, session_path, created_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![id, platform, label, session_path.to_string_lossy().to_string()],
+        )
+        .map_err(|e| format!("DB insert failed: {}", e))?;
+
+        write_log("INFO", &format!("Account added: {}/{}", platform, label));
+        Ok(format!(
+            "✅ Account {}/{} added.\nLog in with: {} login {}\nBind an agent: agent account <agent name>: {}",
+            platform, label, platform, label, label
+        ))
+    }
+}
+
+// Deletes the account and its session; agents still bound to it must be moved first
+redacted! {
+    #[tauri::command]
+    pub fn remove_account(platform: String, label: String) -> Result<String, String> {
+        let conn = open_db()?;
+        let account = find_or_default(&conn, &platform, Some(&label))?;
+        if account.is_default() {
+            return Err("❌ The default account can't be removed (log in again to replace its session).".to_string());
+        }
+
+        let bound: Vec<String> = agents_using(&conn, &account).into_iter().map(|(_, name, _)| name).collect();
+        if !bound.is_empty() {
+            return Err(format!(
+                "❌ {} is used by: {}. Bind them to another account first.",
+                account.display(),
+                bound.join(", ")
+            ));
+        }
+
+        conn.execute("DELETE FROM accounts WHERE id=?1", [&account.id])
+            .map_err(|e| format!("DB delete failed: {}", e))?;
+        let _ = std::fs::remove_file(&account.session_path);
+
+        write_log("INFO", &format!("Account removed: {}", account.display()));
+        Ok(format!("🗑 Account {} removed (session deleted).", account.display()))
+    }
+}
+
+// label None / "default" => back to the platform's default account
+redacted! {
+    #[tauri::command]
+    pub fn set_agent_account(agent_name: String, platform: String, label: Option<String>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_accounts_table(&conn);
+
+        let agent_id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.clone()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+
+        let account = find_or_default(&conn, &platform, label.as_deref())?;
+        let bound = if account.is_default() { None } else { Some(account.id.clone()) };
+        conn.execute(
+            "UPDATE agents SET account_id=?1 WHERE id=?2",
+            params![bound, agent_id],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log_agent("INFO", &agent_id, &format!("Agent account: {}", account.display()));
+        Ok(format!("✅ Agent '{}' now uses {}.", agent_name, account.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_with_agent() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_accounts_table(&conn);
+        conn.execute(
+            "INSERT INTO agents (id, name, role, goal, tools_json, created_at)
+             VALUES ('a1', 'Company Agent', 'r', 'g', '[\"linkedin_post\"]', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO accounts (id, platform, label, session_path, created_at)
+             VALUES ('acc-company', 'linkedin', 'company', '/tmp/company.json.enc', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn runs_use_the_agents_account_or_the_default() {
+        let conn = db_with_agent();
+
+        assert_eq!(for_run(&conn, "linkedin", None, Some("a1")).unwrap().label, "default");
+        assert_eq!(for_run(&conn, "linkedin", None, None).unwrap().id, "linkedin-default");
+
+        conn.execute("UPDATE agents SET account_id='acc-company' WHERE id='a1'", []).unwrap();
+        assert_eq!(for_run(&conn, "linkedin", None, Some("a1")).unwrap().label, "company");
+        // an explicit account wins over the agent's
+        assert_eq!(
+            for_run(&conn, "linkedin", Some("linkedin-default"), Some("a1")).unwrap().label,
+            "default"
+        );
+        assert!(for_run(&conn, "x", None, Some("a1")).is_err());
+
+        let company = find(&conn, "linkedin", "Company").unwrap();
+        assert_eq!(agents_using(&conn, &company).len(), 1);
+        let default = find_or_default(&conn, "linkedin", None).unwrap();
+        assert!(agents_using(&conn, &default).is_empty());
+    }
+
+    #[test]
+    fn lookups_match_the_stored_label() {
+        let conn = db_with_agent();
+        conn.execute(
+            "INSERT INTO accounts (id, platform, label, session_path, created_at)
+             VALUES ('acc-page', 'linkedin', ?1, '/tmp/page.json.enc', datetime('now'))",
+            [normalize_label("Company Page").unwrap()],
+        )
+        .unwrap();
+
+        assert_eq!(find_or_default(&conn, "LinkedIn", Some(" Company Page ")).unwrap().id, "acc-page");
+        assert_eq!(find_or_default(&conn, "linkedin", Some("company-page")).unwrap().id, "acc-page");
+        assert!(find_or_default(&conn, "linkedin", Some("a/b")).is_err());
+        assert!(find_or_default(&conn, "linkedin", Some("missing")).is_err());
+    }
+
+    #[test]
+    fn labels_are_normalized() {
+        assert_eq!(normalize_label(" Company Page ").unwrap(), "company-page");
+        assert!(normalize_label("").is_err());
+        assert!(normalize_label("a/b").is_err());
+    }
+}
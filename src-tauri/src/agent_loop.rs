@@ -0,0 +1,552 @@
+// -------------------------
+// ✅ Agent loop (tool calling)
+// The model is offered the registered tools and may call them step by step.
+// Read-only tools run directly. Side-effecting tools never run here: the call
+// becomes a pending approval, the same flow as scheduled drafts.
+// Every step is stored in agent_run_steps so a run can be reviewed later.
+// -------------------------
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+
+use crate::drafts::{load_agent, style_guide, AgentProfile};
+use crate::llm::{ChatMessage, LlmProvider, LlmRequest, ToolCall, ToolSpec};
+use crate::prompts::{render_template, PromptVars};
+use crate::{
+    create_approval_with_prompt, ensure_agents_table, ensure_approvals_table, http, open_db, redact,
+    structured, write_log_agent, RoutedProvider,
+};
+
+pub const DEFAULT_MAX_STEPS: u32 = 6;
+const MAX_STEPS_LIMIT: u32 = 20;
+// tool output sent back to the model (the transcript keeps all of it)
+const MAX_TOOL_RESULT_CHARS: usize = 4000;
+
+struct AgentTool {
+    name: &'static str,
+    description: &'static str,
+    parameters: fn() -> Value,
+    // Some(kind) => side-effecting: queued as an approval of this kind instead of run
+    approval_kind: Option<&'static str>,
+}
+
+fn text_parameters(max_len: u64) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "text": { "type": "string", "minLength": 1, "maxLength": max_len }
+        },
+        "required": ["text"]
+    })
+}
+
+fn registered_tools() -> Vec<AgentTool> {
+    vec![
+        AgentTool {
+            name: "get_trending_topics",
+            description: "Get today's trending topics (OpenClaw, with a fallback list).",
+            parameters: || json!({ "type": "object", "properties": {} }),
+            approval_kind: None,
+        },
+        AgentTool {
+            name: "list_pending_approvals",
+            description: "List this agent's drafts that are still waiting for the user's approval.",
+            parameters: || json!({ "type": "object", "properties": {} }),
+            approval_kind: None,
+        },
+        AgentTool {
+            name: "publish_post",
+            description: "Publish a LinkedIn post. Queued for the user's approval before anything is posted.",
+            parameters: || text_parameters(3000),
+            approval_kind: Some("linkedin_post"),
+        },
+        AgentTool {
+            name: "post_comment",
+            description: "Comment on LinkedIn posts under #openclaw. Queued for the user's approval before anything is posted.",
+            parameters: || text_parameters(1250),
+            approval_kind: Some("linkedin_comment"),
+        },
+    ]
+}
+
+pub fn ensure_agent_run_tables(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_runs (
+            id TEXT PRIMARY KEY,
+            agent_id TEXT NOT NULL,
+            task TEXT NOT NULL,
+            status TEXT NOT NULL,
+            steps INTEGER NOT NULL DEFAULT 0,
+            final_text TEXT NULL,
+            error TEXT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NULL
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_run_steps (
+            id TEXT PRIMARY KEY,
+            run_id TEXT NOT NULL,
+            step INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            name TEXT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_agent_run_steps_run ON agent_run_steps (run_id, step)",
+        [],
+    );
+}
+
+// kind: task | assistant | tool_call | tool_result
+fn record_step(run_id: &str, step: u32, kind: &str, name: Option<&str>, content: &str) -> Result<(), String> {
+    let conn = open_db()?;
+    ensure_agent_run_tables(&conn);
+
+    conn.execute(
+        "INSERT INTO agent_run_steps (id, run_id, step, kind, name, content, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, strftime('%Y-%m-%d %H:%M:%f', 'now'))",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            run_id,
+            step,
+            kind,
+            name,
+            redact::redact(content)
+        ],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+
+    Ok(())
+}
+
+fn start_run(agent_id: &str, task: &str) -> Result<String, String> {
+    let conn = open_db()?;
+    ensure_agent_run_tables(&conn);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO agent_runs (id, agent_id, task, status, started_at)
+         VALUES (?1, ?2, ?3, 'running', datetime('now'))",
+        params![id, agent_id, task],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+
+    Ok(id)
+}
+
+fn finish_run(run_id: &str, status: &str, steps: u32, final_text: Option<&str>, error: Option<&str>) {
+    if let Ok(conn) = open_db() {
+        let _ = conn.execute(
+            "UPDATE agent_runs
+             SET status=?1, steps=?2, final_text=?3, error=?4, finished_at=datetime('now')
+             WHERE id=?5",
+            params![
+                status,
+                steps,
+                final_text.map(redact::redact),
+                error.map(redact::redact),
+                run_id
+            ],
+        );
+    }
+}
+
+async fn run_tool(agent: &AgentProfile, name: &str) -> Result<String, String> {
+    match name {
+        "get_trending_topics" => {
+            let topics = tokio::task::spawn_blocking(crate::get_trending_topics)
+                .await
+                .map_err(|e| format!("Join error: {}", e))?;
+            Ok(topics
+                .iter()
+                .enumerate()
+                .map(|(i, t)| format!("{}. {}", i + 1, t))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "list_pending_approvals" => {
+            let conn = open_db()?;
+            ensure_approvals_table(&conn);
+
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, kind, draft_text FROM approvals
+                     WHERE agent_id=?1 AND status='pending'
+                     ORDER BY created_at DESC LIMIT 10",
+                )
+                .map_err(|e| format!("Query failed: {}", e))?;
+
+            let rows: Vec<String> = stmt
+                .query_map([&agent.id], |r| {
+                    Ok(format!(
+                        "- {} ({}): {}",
+                        r.get::<_, String>(0)?,
+                        r.get::<_, String>(1)?,
+                        r.get::<_, String>(2)?
+                    ))
+                })
+                .map_err(|e| format!("Query map failed: {}", e))?
+                .flatten()
+                .collect();
+
+            Ok(if rows.is_empty() {
+                "No pending approvals.".to_string()
+            } else {
+                rows.join("\n")
+            })
+        }
+        other => Err(format!("tool '{}' has no runner", other)),
+    }
+}
+
+// Errors go back to the model as the tool result so it can correct itself
+async fn handle_call(
+    agent: &AgentProfile,
+    run_id: &str,
+    label: &str,
+    call: &ToolCall,
+    approvals: &mut Vec<String>,
+) -> String {
+    let tools = registered_tools();
+    let tool = match tools.iter().find(|t| t.name == call.name) {
+        Some(t) => t,
+        None => return format!("Error: unknown tool '{}'", call.name),
+    };
+
+    if let Err(e) = structured::validate(&call.arguments, &(tool.parameters)()) {
+        return format!("Error: invalid arguments:\n{}", e);
+    }
+
+    match tool.approval_kind {
+        Some(kind) => {
+            let text = call.arguments["text"].as_str().unwrap_or_default().trim();
+            let audit = format!("Agent run {} (tool {})", run_id, call.name);
+            match create_approval_with_prompt(&agent.id, kind, text, Some(&audit), Some(label)) {
+                Ok(id) => {
+                    write_log_agent(
+                        "INFO",
+                        &agent.id,
+                        &format!("Agent run {} queued approval id={} ({})", run_id, id, kind),
+                    );
+                    approvals.push(id.clone());
+                    format!(
+                        "Queued for the user's approval (approval id: {}). Nothing is published until they approve.",
+                        id
+                    )
+                }
+                Err(e) => format!("Error: {}", e),
+            }
+        }
+        None => run_tool(agent, tool.name)
+            .await
+            .unwrap_or_else(|e| format!("Error: {}", e)),
+    }
+}
+
+pub struct AgentRunSummary {
+    pub run_id: String,
+    pub status: String,
+    pub steps: u32,
+    pub final_text: String,
+    pub approvals: Vec<String>,
+}
+
+pub async fn run_agent_loop(agent_id: &str, task: &str, max_steps: u32) -> Result<AgentRunSummary, String> {
+    run_agent_loop_with(&RoutedProvider::new(Some(agent_id)), agent_id, task, max_steps).await
+}
+
+async fn run_agent_loop_with(
+    provider: &dyn LlmProvider,
+    agent_id: &str,
+    task: &str,
+    max_steps: u32,
+) -> Result<AgentRunSummary, String> {
+    let agent = load_agent(agent_id)?;
+    let max_steps = max_steps.clamp(1, MAX_STEPS_LIMIT);
+
+    let system = render_template(
+        "agent_system",
+        &PromptVars::new().agent(&agent).text("style_guide", style_guide()),
+    )?;
+
+    let run_id = start_run(&agent.id, task)?;
+    write_log_agent("INFO", &agent.id, &format!("Agent run {} started: {}", run_id, task));
+    record_step(&run_id, 0, "task", None, task)?;
+
+    // cancel_llm(<run id>) aborts the current LLM call and ends the run
+    let call = http::track(Some(&run_id));
+
+    let mut req = LlmRequest {
+        system: Some(system),
+        tools: registered_tools()
+            .iter()
+            .map(|t| ToolSpec {
+                name: t.name.to_string(),
+                description: t.description.to_string(),
+                parameters: (t.parameters)(),
+            })
+            .collect(),
+        cancel: Some(call.token()),
+        ..LlmRequest::prompt(task)
+    };
+
+    let mut approvals = vec![];
+    let mut steps = 0;
+
+    let outcome: Result<(&str, String), String> = async {
+        for step in 1..=max_steps {
+            steps = step;
+            let resp = provider.generate(&req).await?;
+            let label = provider.label();
+            record_step(&run_id, step, "assistant", Some(&label), &resp.text)?;
+
+            if resp.tool_calls.is_empty() {
+                return Ok(("completed", resp.text));
+            }
+
+            req.messages
+                .push(ChatMessage::assistant_tool_calls(&resp.text, resp.tool_calls.clone()));
+
+            for call in &resp.tool_calls {
+                record_step(&run_id, step, "tool_call", Some(&call.name), &call.arguments.to_string())?;
+                let result = handle_call(&agent, &run_id, &label, call, &mut approvals).await;
+                record_step(&run_id, step, "tool_result", Some(&call.name), &result)?;
+
+                let short: String = result.chars().take(MAX_TOOL_RESULT_CHARS).collect();
+                req.messages.push(ChatMessage::tool_result(call, &short));
+            }
+        }
+
+        Ok(("max_steps", format!("Stopped after the {}-step limit.", max_steps)))
+    }
+    .await;
+
+    match outcome {
+        Ok((status, final_text)) => {
+            finish_run(&run_id, status, steps, Some(&final_text), None);
+            write_log_agent(
+                "INFO",
+                &agent.id,
+                &format!("Agent run {} {} after {} step(s)", run_id, status, steps),
+            );
+            Ok(AgentRunSummary {
+                run_id,
+                status: status.to_string(),
+                steps,
+                final_text,
+                approvals,
+            })
+        }
+        Err(e) => {
+            let status = if call.token().is_cancelled() { "cancelled" } else { "failed" };
+            finish_run(&run_id, status, steps, None, Some(&e));
+            write_log_agent("ERROR", &agent.id, &format!("Agent run {} failed: {}", run_id, e));
+            Err(format!("❌ Agent run {} failed: {}", run_id, e))
+        }
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub async fn run_agent(agent_name: String, task: Option<String>, max_steps: Option<u32>) -> Result<String, String> {
+        let agent_id: String = {
+            let conn = open_db()?;
+            ensure_agents_table(&conn);
+            conn.query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.trim()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?
+        };
+
+        let task = task
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| "Work toward your goal for today.".to_string());
+
+        run_agent_loop(&agent_id, &task, max_steps.unwrap_or(DEFAULT_MAX_STEPS))
+            .await
+            .map(|s| {
+                let queued = if s.approvals.is_empty() {
+                    String::new()
+                } else {
+                    format!("\n\nQueued for approval:\n{}", s.approvals.join("\n"))
+                };
+                format!(
+                    "🤖 Agent run {} — {} after {} step(s)\n\n{}{}\n\nTranscript: agent run {}",
+                    s.run_id, s.status, s.steps, s.final_text, queued, s.run_id
+                )
+            })
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn list_agent_runs() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_agent_run_tables(&conn);
+        ensure_agents_table(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT r.id, COALESCE(a.name, r.agent_id), r.status, r.steps, r.started_at
+             FROM agent_runs r
+             LEFT JOIN agents a ON a.id = r.agent_id
+             ORDER BY r.started_at DESC
+             LIMIT 20",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                    r.get::<_, i64>(3)?,
+                    r.get::<_, String>(4)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("🤖 Agent runs:\n\n");
+        let mut count = 0;
+        for (id, agent, status, steps, started_at) in rows.flatten() {
+            count += 1;
+            out.push_str(&format!(
+                "{}. {} — {} ({} steps)\n   id: {}\n   started: {}\n\n",
+                count, agent, status, steps, id, started_at
+            ));
+        }
+
+        if count == 0 {
+            Ok("ℹ️ No agent runs yet.".to_string())
+        } else {
+            Ok(out)
+        }
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn show_agent_run(id: String) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_agent_run_tables(&conn);
+
+        let (task, status, final_text, error): (String, String, Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT task, status, final_text, error FROM agent_runs WHERE id=?1",
+                [&id],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .map_err(|_| "❌ Agent run not found.".to_string())?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT step, kind, name, content FROM agent_run_steps
+             WHERE run_id=?1 ORDER BY step ASC, created_at ASC",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let steps = stmt
+            .query_map([&id], |r| {
+                Ok((
+                    r.get::<_, i64>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, Option<String>>(2)?,
+                    r.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = format!("🤖 Agent run {} ({})\nTask: {}\n\n", id, status, task);
+        for (step, kind, name, content) in steps.flatten() {
+            let name = name.map(|n| format!(" {}", n)).unwrap_or_default();
+            out.push_str(&format!("[{}] {}{}:\n{}\n\n", step, kind, name, content));
+        }
+        if let Some(f) = final_text {
+            out.push_str(&format!("Result: {}\n", f));
+        }
+        if let Some(e) = error {
+            out.push_str(&format!("Error: {}\n", e));
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::{reply, MockProvider};
+
+    // tests run against a throwaway DB (see app_data_dir)
+    fn test_agent() -> String {
+        let conn = open_db().unwrap();
+        ensure_agents_table(&conn);
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO agents (id, name, role, goal, tools_json, created_at)
+             VALUES (?1, 'Tester', 'Writer', 'Post about local LLMs', '[]', datetime('now'))",
+            [&id],
+        )
+        .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_step_limit() {
+        let agent_id = test_agent();
+        let provider = MockProvider::scripted(vec![reply(
+            "Checking drafts",
+            vec![("list_pending_approvals", json!({}))],
+        )]);
+
+        let run = run_agent_loop_with(&provider, &agent_id, "Keep going", 2).await.unwrap();
+        assert_eq!(run.status, "max_steps");
+        assert_eq!(run.steps, 2);
+        assert_eq!(provider.requests().len(), 2);
+        assert!(run.approvals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn side_effecting_tools_become_approvals() {
+        let agent_id = test_agent();
+        let provider = MockProvider::scripted(vec![
+            reply("Posting", vec![("publish_post", json!({ "text": "Local LLMs are here." }))]),
+            reply("Queued the post.", vec![]),
+        ]);
+
+        let run = run_agent_loop_with(&provider, &agent_id, "Write a post", 5).await.unwrap();
+        assert_eq!(run.status, "completed");
+        assert_eq!(run.final_text, "Queued the post.");
+        assert_eq!(run.approvals.len(), 1);
+
+        let conn = open_db().unwrap();
+        let (kind, status, text): (String, String, String) = conn
+            .query_row(
+                "SELECT kind, status, draft_text FROM approvals WHERE id=?1",
+                [&run.approvals[0]],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(kind, "linkedin_post");
+        assert_eq!(status, "pending");
+        assert_eq!(text, "Local LLMs are here.");
+
+        // the model is told the call was queued, not published
+        let requests = provider.requests();
+        let result = requests[1].messages.last().unwrap();
+        assert!(result.content.contains("Queued for the user's approval"));
+    }
+}
@@ -0,0 +1,337 @@
+// -------------------------
+// ✅ Run artifacts
+// Every automation run gets <app data>/artifacts/<run id>, passed to the
+// script as PERSONALIZ_ARTIFACTS_DIR (see automation/protocol.js): step
+// screenshots, and on failure a failure screenshot + Playwright trace.
+// The folder and the files found when the run ends are stored on the `runs` row.
+// Traces hold session cookies: the runner encrypts trace.zip with the vault
+// (trace.zip.enc) when the run ends, open_artifact decrypts a temporary copy.
+// Folders older than the retention period (user_settings.artifact_retention_days,
+// default 7) are deleted at startup and hourly by the scheduler.
+// -------------------------
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+
+use crate::runner::ensure_runs_table;
+use crate::{app_data_dir, ensure_column, ensure_user_settings_table, open_db, secrets, write_log};
+
+const DEFAULT_RETENTION_DAYS: i64 = 7;
+const TRACE: &str = "trace.zip";
+// decrypted trace copies (open_artifact) are removed by the next cleanup after this
+const DECRYPTED_TTL: Duration = Duration::from_secs(15 * 60);
+
+pub fn root() -> PathBuf {
+    app_data_dir().join("artifacts")
+}
+
+pub fn run_dir(run_id: &str) -> PathBuf {
+    root().join(run_id)
+}
+
+// None => the run goes ahead without artifacts
+pub fn create_run_dir(run_id: &str) -> Option<PathBuf> {
+    let dir = run_dir(run_id);
+    match std::fs::create_dir_all(&dir) {
+        Ok(_) => Some(dir),
+        Err(e) => {
+            write_log("WARN", &format!("Artifacts dir not created for run {}: {}", run_id, e));
+            None
+        }
+    }
+}
+
+// files in a run folder, by name (screenshots are numbered per step)
+pub fn files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+// trace.zip => trace.zip.enc; without an unlocked vault the trace is dropped, never kept in plaintext
+pub fn seal_trace(dir: &Path) {
+    let trace = dir.join(TRACE);
+    if !trace.is_file() {
+        return;
+    }
+    if let Err(e) = secrets::seal_file(&trace) {
+        let _ = std::fs::remove_file(&trace);
+        write_log("WARN", &format!("Trace deleted, not encrypted ({}): {}", dir.display(), e));
+    }
+}
+
+pub fn ensure_artifact_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "artifact_retention_days INTEGER NULL");
+}
+
+fn retention_days(conn: &Connection) -> i64 {
+    ensure_artifact_settings(conn);
+    conn.query_row("SELECT artifact_retention_days FROM user_settings WHERE id=1", [], |r| {
+        r.get::<_, Option<i64>>(0)
+    })
+    .ok()
+    .flatten()
+    .filter(|d| *d > 0)
+    .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+// Deletes artifacts of runs that ended before the retention period; returns the number of runs cleaned
+pub fn cleanup(conn: &Connection) -> usize {
+    let cutoff = format!("-{} days", retention_days(conn));
+    let (cleaned, failed) = remove_expired(conn, &root(), &cutoff);
+    remove_decrypted_traces(&root());
+
+    for (id, e) in &failed {
+        write_log("WARN", &format!("Artifacts of run {} not deleted: {}", id, e));
+    }
+    if cleaned > 0 {
+        write_log("INFO", &format!("Deleted artifacts of {} old run(s) ({})", cleaned, cutoff));
+    }
+    cleaned
+}
+
+// (runs cleaned, (run id, error) for folders that could not be deleted)
+fn remove_expired(conn: &Connection, root: &Path, cutoff: &str) -> (usize, Vec<(String, String)>) {
+    ensure_runs_table(conn);
+    let expired: Vec<(String, String)> = match conn.prepare(
+        "SELECT id, artifacts_dir FROM runs
+         WHERE artifacts_dir IS NOT NULL AND ended_at IS NOT NULL AND ended_at < datetime('now', ?1)",
+    ) {
+        Ok(mut stmt) => stmt
+            .query_map([cutoff], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map(|rows| rows.flatten().collect())
+            .unwrap_or_default(),
+        Err(_) => vec![],
+    };
+
+    let mut cleaned = 0;
+    let mut failed = vec![];
+    for (id, dir) in expired {
+        let dir = Path::new(&dir);
+        // only ever delete inside our own artifacts folder
+        if dir.starts_with(root) && dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                failed.push((id, e.to_string()));
+                continue;
+            }
+        }
+        let _ = conn.execute(
+            "UPDATE runs SET artifacts_dir=NULL, artifacts_json=NULL WHERE id=?1",
+            params![id],
+        );
+        cleaned += 1;
+    }
+    (cleaned, failed)
+}
+
+// plaintext trace.zip next to its .enc = a copy decrypted for viewing
+fn remove_decrypted_traces(root: &Path) {
+    let Ok(runs) = std::fs::read_dir(root) else { return };
+    for run in runs.flatten() {
+        let trace = run.path().join(TRACE);
+        let old = std::fs::metadata(&trace)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > DECRYPTED_TTL);
+        if old && run.path().join(format!("{}.enc", TRACE)).exists() {
+            let _ = std::fs::remove_file(&trace);
+        }
+    }
+}
+
+fn run_artifacts_dir(conn: &Connection, run_id: &str) -> Result<PathBuf, String> {
+    ensure_runs_table(conn);
+    let dir: Option<String> = conn
+        .query_row("SELECT artifacts_dir FROM runs WHERE id=?1", [run_id], |r| r.get(0))
+        .map_err(|_| format!("❌ Run '{}' not found.", run_id))?;
+    dir.map(PathBuf::from)
+        .filter(|d| d.is_dir())
+        .ok_or_else(|| format!("ℹ️ Run {} has no artifacts (none recorded, or already cleaned up).", run_id))
+}
+
+// explorer / open / xdg-open; the app does not wait for it
+fn open_path(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let mut cmd = std::process::Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut cmd = std::process::Command::new("open");
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut cmd = std::process::Command::new("xdg-open");
+
+    cmd.arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("❌ Could not open {}: {}", path.display(), e))
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+redacted! {
+    #[tauri::command]
+    pub fn list_artifacts(run_id: String) -> Result<String, String> {
+        let run_id = run_id.trim();
+        let conn = open_db()?;
+        let dir = run_artifacts_dir(&conn, run_id)?;
+
+        let files = files(&dir);
+        let mut out = format!("🗂 Artifacts of run {} ({} files)\n{}\n", run_id, files.len(), dir.display());
+        for f in &files {
+            let name = f.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let kb = std::fs::metadata(f).map(|m| m.len() / 1024).unwrap_or(0);
+            let icon = if name.starts_with(TRACE) { "🧭" } else { "📸" };
+            out.push_str(&format!("\n{} {} ({} KB)", icon, name, kb));
+        }
+        if files.iter().any(|f| f.ends_with(format!("{}.enc", TRACE))) {
+            out.push_str(&format!(
+                "\n\nThe trace is encrypted. Decrypt and view it with: open artifacts {} {}.enc\nthen: npx playwright show-trace <path to trace.zip>",
+                run_id, TRACE
+            ));
+        }
+        Ok(out)
+    }
+}
+
+// file = None opens the run's folder
+redacted! {
+    #[tauri::command]
+    pub fn open_artifact(run_id: String, file: Option<String>) -> Result<String, String> {
+        let run_id = run_id.trim();
+        let conn = open_db()?;
+        let dir = run_artifacts_dir(&conn, run_id)?;
+
+        let target = match file.map(|f| f.trim().to_string()).filter(|f| !f.is_empty()) {
+            None => dir,
+            Some(name) => {
+                // a bare file name from list_artifacts, never a path
+                if Path::new(&name).file_name().map(|n| n.to_string_lossy() != name.as_str()).unwrap_or(true) {
+                    return Err(format!("❌ Not an artifact name: {}", name));
+                }
+                let path = dir.join(&name);
+                if !path.is_file() {
+                    return Err(format!("❌ No artifact '{}' in run {}.", name, run_id));
+                }
+                // encrypted trace => a short-lived decrypted copy next to it
+                match name.strip_suffix(".enc") {
+                    Some(plain) => {
+                        let copy = dir.join(plain);
+                        secrets::unseal_file(&path, &copy)?;
+                        write_log("INFO", &format!("Decrypted {} of run {} for viewing", plain, run_id));
+                        return Ok(format!(
+                            "🔓 Decrypted to {}\nView it with: npx playwright show-trace \"{}\"\n(the copy is deleted within the hour)",
+                            copy.display(),
+                            copy.display()
+                        ));
+                    }
+                    None => path,
+                }
+            }
+        };
+
+        open_path(&target)?;
+        Ok(format!("📂 Opened {}", target.display()))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn set_artifact_retention(days: i64) -> Result<String, String> {
+        if !(1..=365).contains(&days) {
+            return Err("❌ Retention must be between 1 and 365 days.".to_string());
+        }
+        let conn = open_db()?;
+        ensure_artifact_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET artifact_retention_days=?1, updated_at=datetime('now') WHERE id=1",
+            params![days],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        let cleaned = cleanup(&conn);
+        write_log("INFO", &format!("Artifact retention set to {} days", days));
+        Ok(format!(
+            "✅ Run artifacts are kept for {} days ({} older run(s) cleaned up).",
+            days, cleaned
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_listed_in_step_order() {
+        let dir = std::env::temp_dir().join(format!("artifacts-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        for name in ["02-type.png", "trace.zip", "01-open_feed.png"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+
+        let names: Vec<String> = files(&dir)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["01-open_feed.png", "02-type.png", "trace.zip"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(files(&dir).is_empty());
+    }
+
+    fn insert_run(conn: &Connection, id: &str, dir: &Path, ended: &str) {
+        conn.execute(
+            "INSERT INTO runs (id, script, status, started_at, ended_at, artifacts_dir, artifacts_json)
+             VALUES (?1, 'linkedin_post', 'failed', datetime('now', ?2), datetime('now', ?2), ?3, '[\"x\"]')",
+            params![id, ended, dir.to_string_lossy().to_string()],
+        )
+        .unwrap();
+    }
+
+    fn columns(conn: &Connection, id: &str) -> (Option<String>, Option<String>) {
+        conn.query_row("SELECT artifacts_dir, artifacts_json FROM runs WHERE id=?1", [id], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn cleanup_removes_only_expired_runs_inside_the_root() {
+        let base = std::env::temp_dir().join(format!("artifacts-cleanup-{}", uuid::Uuid::new_v4()));
+        let root = base.join("artifacts");
+        let outside = base.join("elsewhere");
+        for dir in [root.join("old"), root.join("new"), outside.clone()] {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("01-step.png"), b"x").unwrap();
+        }
+
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_runs_table(&conn);
+        insert_run(&conn, "old", &root.join("old"), "-10 days");
+        insert_run(&conn, "new", &root.join("new"), "-1 days");
+        insert_run(&conn, "outside", &outside, "-10 days");
+
+        let (cleaned, failed) = remove_expired(&conn, &root, "-7 days");
+        assert_eq!(cleaned, 2);
+        assert!(failed.is_empty());
+
+        // expired inside the root: folder deleted, columns nulled
+        assert!(!root.join("old").exists());
+        assert_eq!(columns(&conn, "old"), (None, None));
+        // recent: untouched
+        assert!(root.join("new/01-step.png").exists());
+        assert!(columns(&conn, "new").0.is_some());
+        // a path outside the root is never deleted, only forgotten
+        assert!(outside.join("01-step.png").exists());
+        assert_eq!(columns(&conn, "outside"), (None, None));
+
+        // nothing left to clean
+        assert_eq!(remove_expired(&conn, &root, "-7 days").0, 0);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
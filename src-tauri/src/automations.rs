@@ -0,0 +1,372 @@
+// -------------------------
+// ✅ Automation registry
+// Every script in the scripts dir has a `<name>.manifest.json` next to it:
+//   { "name": "linkedin_post", "entry": "linkedin_post.js",
+//     "description": "...", "session": "linkedin", "timeout_secs": 180, "dry_run": true,
+//     "args": [{ "name": "text", "type": "string", "required": true, "max_length": 3000 }] }
+// The runner launches automations by name and checks the arguments first.
+// Scripts dir (first that applies): user setting -> PERSONALIZ_SCRIPTS_DIR
+// -> bundled resources -> next to the executable -> dev checkout (debug builds)
+// -> <app data>/automation.
+// -------------------------
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+
+use crate::{app_data_dir, ensure_column, ensure_user_settings_table, open_db, write_log};
+
+const MANIFEST_SUFFIX: &str = ".manifest.json";
+const DEFAULT_TIMEOUT_SECS: u64 = 180;
+
+// Tauri resource dir, set once in main's setup
+static RESOURCE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgType {
+    String,
+    Integer,
+    Url,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArgSpec {
+    pub name: String,
+    #[serde(rename = "type", default = "default_arg_type")]
+    pub kind: ArgType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+fn default_arg_type() -> ArgType {
+    ArgType::String
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    // script file, relative to the scripts dir
+    pub entry: String,
+    #[serde(default)]
+    pub description: String,
+    // positional, in this order
+    #[serde(default)]
+    pub args: Vec<ArgSpec>,
+    // platform whose decrypted session is passed in PERSONALIZ_AUTH_PATH
+    #[serde(default)]
+    pub session: Option<String>,
+    // the script writes a fresh session (login) => encrypt it afterwards
+    #[serde(default)]
+    pub captures_session: bool,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    // publishes / changes something; false => dry run does not apply (login)
+    #[serde(default = "default_true")]
+    pub side_effects: bool,
+    // honours PERSONALIZ_DRY_RUN (stops before submit)
+    #[serde(default)]
+    pub dry_run: bool,
+    // needs a visible browser window (login) => never headless
+    #[serde(default)]
+    pub interactive: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Manifest {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.filter(|t| *t > 0).unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+
+    pub fn usage(&self) -> String {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| if a.required { format!("<{}>", a.name) } else { format!("[{}]", a.name) })
+            .collect();
+        format!("{} {}", self.name, args.join(" ")).trim_end().to_string()
+    }
+
+    pub fn validate_args(&self, args: &[String]) -> Result<(), String> {
+        if args.len() > self.args.len() {
+            return Err(format!(
+                "{} takes {} argument(s), got {}. Usage: {}",
+                self.name,
+                self.args.len(),
+                args.len(),
+                self.usage()
+            ));
+        }
+
+        for (i, spec) in self.args.iter().enumerate() {
+            let value = args.get(i).map(|s| s.trim()).unwrap_or("");
+            if value.is_empty() {
+                if spec.required {
+                    return Err(format!("{}: '{}' is required. Usage: {}", self.name, spec.name, self.usage()));
+                }
+                continue;
+            }
+
+            if let Some(max) = spec.max_length {
+                let n = value.chars().count();
+                if n > max {
+                    return Err(format!("{}: '{}' is {} characters (max {}).", self.name, spec.name, n, max));
+                }
+            }
+
+            let valid = match spec.kind {
+                ArgType::String => true,
+                ArgType::Integer => value.parse::<i64>().is_ok(),
+                ArgType::Url => reqwest::Url::parse(value)
+                    .map(|u| matches!(u.scheme(), "http" | "https"))
+                    .unwrap_or(false),
+            };
+            if !valid {
+                return Err(format!("{}: '{}' must be a {:?}, got '{}'.", self.name, spec.name, spec.kind, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Registry {
+    pub dir: PathBuf,
+    pub manifests: Vec<Manifest>,
+    // unreadable manifests, duplicate names, missing entry files
+    pub problems: Vec<String>,
+}
+
+impl Registry {
+    pub fn scan(dir: &Path) -> Registry {
+        let mut manifests: Vec<Manifest> = vec![];
+        let mut problems = vec![];
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|rd| rd.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        files.retain(|p| p.to_string_lossy().ends_with(MANIFEST_SUFFIX));
+        files.sort();
+
+        for path in files {
+            let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str::<Manifest>(&s).map_err(|e| e.to_string()));
+
+            match parsed {
+                Ok(m) if manifests.iter().any(|o| o.name == m.name) => {
+                    problems.push(format!("{}: duplicate automation name '{}'", file, m.name));
+                }
+                Ok(m) => {
+                    if !dir.join(&m.entry).exists() {
+                        problems.push(format!("{}: entry file '{}' not found", file, m.entry));
+                    }
+                    manifests.push(m);
+                }
+                Err(e) => problems.push(format!("{}: {}", file, e)),
+            }
+        }
+
+        Registry {
+            dir: dir.to_path_buf(),
+            manifests,
+            problems,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Manifest, String> {
+        self.manifests.iter().find(|m| m.name == name).ok_or_else(|| {
+            format!(
+                "❌ Automation '{}' not found in {} (see: automations)",
+                name,
+                self.dir.display()
+            )
+        })
+    }
+
+    pub fn entry_path(&self, m: &Manifest) -> PathBuf {
+        self.dir.join(&m.entry)
+    }
+}
+
+pub fn load_registry() -> Registry {
+    Registry::scan(&scripts_dir().0)
+}
+
+pub fn set_resource_dir(dir: Option<PathBuf>) {
+    if let Some(d) = dir {
+        let _ = RESOURCE_DIR.set(d);
+    }
+}
+
+pub fn ensure_automation_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "scripts_dir TEXT NULL");
+}
+
+fn saved_scripts_dir() -> Option<PathBuf> {
+    let conn = open_db().ok()?;
+    ensure_automation_settings(&conn);
+    conn.query_row("SELECT scripts_dir FROM user_settings WHERE id=1", [], |r| {
+        r.get::<_, Option<String>>(0)
+    })
+    .ok()
+    .flatten()
+    .filter(|s| !s.trim().is_empty())
+    .map(PathBuf::from)
+}
+
+// (dir, where it came from)
+pub fn scripts_dir() -> (PathBuf, &'static str) {
+    if let Some(d) = saved_scripts_dir() {
+        return (d, "setting");
+    }
+    if let Some(d) = std::env::var_os("PERSONALIZ_SCRIPTS_DIR").filter(|v| !v.is_empty()) {
+        return (PathBuf::from(d), "PERSONALIZ_SCRIPTS_DIR");
+    }
+    if let Some(d) = RESOURCE_DIR.get().map(|r| r.join("automation")).filter(|d| d.is_dir()) {
+        return (d, "bundled");
+    }
+    if let Some(d) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.join("automation")))
+        .filter(|d| d.is_dir())
+    {
+        return (d, "next to the app");
+    }
+    #[cfg(debug_assertions)]
+    {
+        // dev checkout: <repo>/src-tauri -> <repo>/automation
+        let dev = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("automation");
+        if dev.is_dir() {
+            return (dev, "dev checkout");
+        }
+    }
+    (app_data_dir().join("automation"), "app data")
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn list_automations() -> Result<String, String> {
+        let (dir, source) = scripts_dir();
+        let registry = Registry::scan(&dir);
+
+        let mut out = format!("🧰 Automations in {} ({}):\n", dir.display(), source);
+        if registry.manifests.is_empty() {
+            out.push_str("\n(none found — add <name>.manifest.json files or: scripts dir <path>)\n");
+        }
+        for m in &registry.manifests {
+            out.push_str(&format!("\n• {}", m.usage()));
+            if !m.description.is_empty() {
+                out.push_str(&format!(" — {}", m.description));
+            }
+            out.push_str(&format!(
+                "\n   {} | timeout {}s{}{}{}\n",
+                m.entry,
+                m.timeout().as_secs(),
+                m.session.as_deref().map(|s| format!(" | session: {}", s)).unwrap_or_default(),
+                if m.dry_run { " | dry-run ✓" } else { "" },
+                if m.interactive { " | interactive" } else { "" }
+            ));
+        }
+        if !registry.problems.is_empty() {
+            out.push_str("\n⚠️ Problems:\n");
+            for p in &registry.problems {
+                out.push_str(&format!("- {}\n", p));
+            }
+        }
+        Ok(out)
+    }
+}
+
+// "" / None => back to automatic lookup
+redacted! {
+    #[tauri::command]
+    pub fn set_scripts_dir(path: Option<String>) -> Result<String, String> {
+        let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        if let Some(p) = &path {
+            if !Path::new(p).is_dir() {
+                return Err(format!("❌ Not a directory: {}", p));
+            }
+        }
+
+        let conn = open_db()?;
+        ensure_automation_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET scripts_dir=?1, updated_at=datetime('now') WHERE id=1",
+            params![path],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        let (dir, source) = scripts_dir();
+        write_log("INFO", &format!("Scripts dir: {} ({})", dir.display(), source));
+        list_automations()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(json: &str) -> Manifest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn validates_arguments_against_the_schema() {
+        let m = manifest(
+            r#"{ "name": "demo", "entry": "demo.js", "args": [
+                { "name": "text", "required": true, "max_length": 5 },
+                { "name": "count", "type": "integer" },
+                { "name": "repo", "type": "url" }
+            ] }"#,
+        );
+
+        assert!(m.validate_args(&["hello".into()]).is_ok());
+        assert!(m.validate_args(&["hi".into(), "3".into(), "https://github.com/x/y".into()]).is_ok());
+
+        assert!(m.validate_args(&[]).unwrap_err().contains("'text' is required"));
+        assert!(m.validate_args(&["too long".into()]).unwrap_err().contains("max 5"));
+        assert!(m.validate_args(&["hi".into(), "three".into()]).unwrap_err().contains("Integer"));
+        assert!(m.validate_args(&["hi".into(), "".into(), "ftp://x".into()]).unwrap_err().contains("Url"));
+        assert!(m
+            .validate_args(&["a".into(), "1".into(), "https://x.y".into(), "extra".into()])
+            .unwrap_err()
+            .contains("takes 3 argument(s)"));
+    }
+
+    #[test]
+    fn timeout_defaults_when_missing_or_zero() {
+        let m = manifest(r#"{ "name": "a", "entry": "a.js", "timeout_secs": 0 }"#);
+        assert_eq!(m.timeout().as_secs(), DEFAULT_TIMEOUT_SECS);
+        let m = manifest(r#"{ "name": "a", "entry": "a.js", "timeout_secs": 600 }"#);
+        assert_eq!(m.timeout().as_secs(), 600);
+    }
+
+    #[test]
+    fn scan_finds_manifests_and_reports_problems() {
+        let dir = std::env::temp_dir().join(format!("personaliz-registry-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("post.js"), "").unwrap();
+        std::fs::write(dir.join("post.manifest.json"), r#"{ "name": "post", "entry": "post.js" }"#).unwrap();
+        std::fs::write(dir.join("gone.manifest.json"), r#"{ "name": "gone", "entry": "gone.js" }"#).unwrap();
+        std::fs::write(dir.join("broken.manifest.json"), "{").unwrap();
+
+        let registry = Registry::scan(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(registry.get("post").is_ok());
+        assert!(registry.get("nope").is_err());
+        assert_eq!(registry.problems.len(), 2, "{:?}", registry.problems);
+        assert!(registry.problems.iter().any(|p| p.contains("'gone.js' not found")));
+    }
+}
@@ -0,0 +1,264 @@
+// -------------------------
+// ✅ Browser settings for automations
+// Engine (chromium | firefox | webkit), window mode and viewport live in
+// user_settings; the runner passes them to scripts as PERSONALIZ_BROWSER,
+// PERSONALIZ_HEADLESS and PERSONALIZ_VIEWPORT (see launch() in automation/protocol.js).
+// Mode "auto" (default): headless for scheduler-triggered runs, a visible window
+// for runs started by the user. Without a display (Linux build box) runs are
+// headless; interactive scripts (login) always need a window.
+// -------------------------
+use rusqlite::{params, Connection};
+
+use crate::{ensure_column, ensure_user_settings_table, open_db, write_log};
+
+pub const ENGINES: [&str; 3] = ["chromium", "firefox", "webkit"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Auto,
+    Headless,
+    Headed,
+}
+
+impl Mode {
+    pub fn parse(s: &str) -> Option<Mode> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Some(Mode::Auto),
+            "headless" => Some(Mode::Headless),
+            "headed" | "window" => Some(Mode::Headed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Auto => "auto",
+            Mode::Headless => "headless",
+            Mode::Headed => "headed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserSettings {
+    pub engine: String,
+    pub mode: Mode,
+    // None => the engine's default (1280x720)
+    pub viewport: Option<(u32, u32)>,
+}
+
+impl Default for BrowserSettings {
+    fn default() -> Self {
+        BrowserSettings {
+            engine: "chromium".to_string(),
+            mode: Mode::Auto,
+            viewport: None,
+        }
+    }
+}
+
+impl BrowserSettings {
+    // Err => the run can't work here (interactive script without a display)
+    pub fn headless(&self, interactive: bool, scheduled: bool, display: bool) -> Result<bool, String> {
+        if interactive {
+            return if display {
+                Ok(false)
+            } else {
+                Err("❌ This automation needs a browser window, but no display is available.".to_string())
+            };
+        }
+        Ok(match self.mode {
+            Mode::Headless => true,
+            Mode::Headed => !display,
+            Mode::Auto => scheduled || !display,
+        })
+    }
+
+    pub fn env(&self, headless: bool) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("PERSONALIZ_BROWSER", self.engine.clone()),
+            ("PERSONALIZ_HEADLESS", if headless { "1" } else { "0" }.to_string()),
+        ];
+        if let Some((w, h)) = self.viewport {
+            env.push(("PERSONALIZ_VIEWPORT", format!("{}x{}", w, h)));
+        }
+        env
+    }
+
+    pub fn describe(&self, headless: bool) -> String {
+        format!(
+            "{}, {}{}",
+            self.engine,
+            if headless { "headless" } else { "headed" },
+            self.viewport.map(|(w, h)| format!(", {}x{}", w, h)).unwrap_or_default()
+        )
+    }
+}
+
+// "1280x800" (also "1280*800" / "1280 x 800")
+pub fn parse_viewport(s: &str) -> Result<(u32, u32), String> {
+    let cleaned = s.trim().to_lowercase().replace(' ', "");
+    let (w, h) = cleaned
+        .split_once(['x', '*'])
+        .ok_or_else(|| format!("❌ Viewport must look like 1280x800, got '{}'.", s.trim()))?;
+    let w: u32 = w.parse().map_err(|_| format!("❌ Bad viewport width '{}'.", w))?;
+    let h: u32 = h.parse().map_err(|_| format!("❌ Bad viewport height '{}'.", h))?;
+    if !(320..=3840).contains(&w) || !(240..=2160).contains(&h) {
+        return Err("❌ Viewport must be between 320x240 and 3840x2160.".to_string());
+    }
+    Ok((w, h))
+}
+
+// Linux without X11/Wayland can only run headless
+pub fn has_display() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|v| std::env::var(v).map(|s| !s.is_empty()).unwrap_or(false))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        true
+    }
+}
+
+pub fn ensure_browser_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "browser_engine TEXT NULL");
+    ensure_column(conn, "user_settings", "browser_mode TEXT NULL");
+    ensure_column(conn, "user_settings", "browser_viewport TEXT NULL");
+}
+
+pub fn load_settings() -> BrowserSettings {
+    let Ok(conn) = open_db() else {
+        return BrowserSettings::default();
+    };
+    ensure_browser_settings(&conn);
+
+    let row: Option<(Option<String>, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT browser_engine, browser_mode, browser_viewport FROM user_settings WHERE id=1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .ok();
+    let Some((engine, mode, viewport)) = row else {
+        return BrowserSettings::default();
+    };
+
+    let defaults = BrowserSettings::default();
+    BrowserSettings {
+        engine: engine.filter(|e| ENGINES.contains(&e.as_str())).unwrap_or(defaults.engine),
+        mode: mode.as_deref().and_then(Mode::parse).unwrap_or(defaults.mode),
+        viewport: viewport.as_deref().and_then(|v| parse_viewport(v).ok()),
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+redacted! {
+    #[tauri::command]
+    pub fn browser_settings() -> Result<String, String> {
+        let s = load_settings();
+        Ok(format!(
+            "🌐 Browser for automations:\n- engine: {}\n- mode: {}{}\n- viewport: {}\n- display: {}",
+            s.engine,
+            s.mode.as_str(),
+            if s.mode == Mode::Auto { " (headless when scheduled, window when you start it)" } else { "" },
+            s.viewport
+                .map(|(w, h)| format!("{}x{}", w, h))
+                .unwrap_or_else(|| "default".to_string()),
+            if has_display() { "yes" } else { "none (headless only)" }
+        ))
+    }
+}
+
+// None leaves a setting as it is; viewport "default" resets it
+redacted! {
+    #[tauri::command]
+    pub fn set_browser_settings(
+        engine: Option<String>,
+        mode: Option<String>,
+        viewport: Option<String>,
+    ) -> Result<String, String> {
+        let engine = match engine.map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()) {
+            Some(e) if !ENGINES.contains(&e.as_str()) => {
+                return Err(format!("❌ Unknown browser '{}'. Use: {}", e, ENGINES.join(" | ")));
+            }
+            other => other,
+        };
+        let mode = match mode.filter(|m| !m.trim().is_empty()) {
+            Some(m) => Some(Mode::parse(&m).ok_or_else(|| format!("❌ Unknown mode '{}'. Use: auto | headless | headed", m.trim()))?),
+            None => None,
+        };
+
+        let conn = open_db()?;
+        ensure_browser_settings(&conn);
+
+        if let Some(e) = &engine {
+            conn.execute("UPDATE user_settings SET browser_engine=?1 WHERE id=1", params![e])
+                .map_err(|e| format!("DB update failed: {}", e))?;
+        }
+        if let Some(m) = mode {
+            conn.execute("UPDATE user_settings SET browser_mode=?1 WHERE id=1", params![m.as_str()])
+                .map_err(|e| format!("DB update failed: {}", e))?;
+        }
+        if let Some(v) = viewport.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            let value = if v.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                let (w, h) = parse_viewport(&v)?;
+                Some(format!("{}x{}", w, h))
+            };
+            conn.execute("UPDATE user_settings SET browser_viewport=?1 WHERE id=1", params![value])
+                .map_err(|e| format!("DB update failed: {}", e))?;
+        }
+        let _ = conn.execute("UPDATE user_settings SET updated_at=datetime('now') WHERE id=1", []);
+
+        let s = load_settings();
+        write_log(
+            "INFO",
+            &format!("Browser settings: {} / {} / {:?}", s.engine, s.mode.as_str(), s.viewport),
+        );
+        browser_settings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_viewports() {
+        assert_eq!(parse_viewport("1280x800"), Ok((1280, 800)));
+        assert_eq!(parse_viewport(" 1920 X 1080 "), Ok((1920, 1080)));
+        assert_eq!(parse_viewport("1366*768"), Ok((1366, 768)));
+        assert!(parse_viewport("big").is_err());
+        assert!(parse_viewport("100x100").is_err());
+    }
+
+    #[test]
+    fn auto_mode_is_headless_for_scheduled_runs_and_without_display() {
+        let s = BrowserSettings::default();
+        assert_eq!(s.headless(false, true, true), Ok(true));
+        assert_eq!(s.headless(false, false, true), Ok(false));
+        assert_eq!(s.headless(false, false, false), Ok(true));
+
+        let headed = BrowserSettings {
+            mode: Mode::Headed,
+            ..BrowserSettings::default()
+        };
+        assert_eq!(headed.headless(false, true, true), Ok(false));
+
+        // login needs a window, whatever the mode
+        let headless = BrowserSettings {
+            mode: Mode::Headless,
+            ..BrowserSettings::default()
+        };
+        assert_eq!(headless.headless(true, true, true), Ok(false));
+        assert!(headless.headless(true, false, false).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+// -------------------------
+// ✅ LLM response cache (opt-in)
+// Key = sha256 of provider, model, normalized prompt (system + messages)
+// and request parameters. Entries expire after the configured TTL.
+// Off until `set_llm_cache` gives a TTL; any request can bypass it.
+// Requests that offer tools are never cached (tool results change), nor
+// structured ones: their reply is only known good after generate_structured
+// validated it, and a cached invalid reply would be replayed on every retry.
+// -------------------------
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::llm::{LlmRequest, LlmResponse, Usage};
+use crate::{ensure_column, ensure_user_settings_table, open_db, usage, write_log};
+
+pub fn ensure_cache_tables(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_cache (
+            key TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            response_text TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    // daily counters for the usage report
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_cache_stats (
+            day TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0,
+            misses INTEGER NOT NULL DEFAULT 0,
+            saved_usd REAL NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, provider)
+        )",
+        [],
+    );
+
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "llm_cache_ttl_secs INTEGER NULL");
+}
+
+fn ttl_secs(conn: &Connection) -> Option<i64> {
+    conn.query_row("SELECT llm_cache_ttl_secs FROM user_settings WHERE id=1", [], |r| {
+        r.get::<_, Option<i64>>(0)
+    })
+    .ok()
+    .flatten()
+    .filter(|t| *t > 0)
+}
+
+// Whitespace differences (indentation, trailing newlines) should not miss the cache
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn cache_key(provider: &str, model: &str, req: &LlmRequest) -> String {
+    let material = json!({
+        "provider": provider,
+        "model": model,
+        "system": req.system.as_deref().map(normalize),
+        "messages": req
+            .messages
+            .iter()
+            .map(|m| json!([m.role, normalize(&m.content)]))
+            .collect::<Vec<_>>(),
+        "max_tokens": req.max_tokens,
+    });
+
+    hex::encode(Sha256::digest(material.to_string().as_bytes()))
+}
+
+fn bump_stats(conn: &Connection, provider: &str, hit: bool, saved_usd: f64) {
+    let _ = conn.execute(
+        "INSERT INTO llm_cache_stats (day, provider, hits, misses, saved_usd)
+         VALUES (date('now'), ?1, ?2, ?3, ?4)
+         ON CONFLICT(day, provider) DO UPDATE SET
+            hits = hits + excluded.hits,
+            misses = misses + excluded.misses,
+            saved_usd = saved_usd + excluded.saved_usd",
+        params![provider, hit as i64, (!hit) as i64, saved_usd],
+    );
+}
+
+fn cacheable(req: &LlmRequest) -> bool {
+    !req.bypass_cache && req.tools.is_empty() && req.response_schema.is_none()
+}
+
+// Some(key) when this request should go through the cache
+pub fn key_for(provider: &str, model: &str, req: &LlmRequest) -> Option<String> {
+    if !cacheable(req) {
+        return None;
+    }
+    let conn = open_db().ok()?;
+    ensure_cache_tables(&conn);
+    ttl_secs(&conn)?;
+    Some(cache_key(provider, model, req))
+}
+
+// Counts a hit or a miss. Hits are returned without usage (nothing was billed).
+pub fn get(key: &str, provider: &str) -> Option<LlmResponse> {
+    let conn = open_db().ok()?;
+    ensure_cache_tables(&conn);
+    lookup(&conn, key, provider)
+}
+
+fn lookup(conn: &Connection, key: &str, provider: &str) -> Option<LlmResponse> {
+    let _ = conn.execute("DELETE FROM llm_cache WHERE expires_at <= datetime('now')", []);
+
+    let row: Option<(String, String, i64, i64)> = conn
+        .query_row(
+            "SELECT model, response_text, input_tokens, output_tokens FROM llm_cache WHERE key=?1",
+            [key],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional()
+        .ok()
+        .flatten();
+
+    match row {
+        Some((model, text, input_tokens, output_tokens)) => {
+            let _ = conn.execute("UPDATE llm_cache SET hits = hits + 1 WHERE key=?1", [key]);
+            let saved = usage::cost_usd(
+                conn,
+                provider,
+                &model,
+                &Usage {
+                    input_tokens,
+                    output_tokens,
+                },
+            );
+            bump_stats(conn, provider, true, saved);
+            Some(LlmResponse {
+                text,
+                model,
+                usage: None,
+                tool_calls: vec![],
+            })
+        }
+        None => {
+            bump_stats(conn, provider, false, 0.0);
+            None
+        }
+    }
+}
+
+pub fn put(key: &str, provider: &str, resp: &LlmResponse) {
+    let Ok(conn) = open_db() else { return };
+    ensure_cache_tables(&conn);
+    store(&conn, key, provider, resp);
+}
+
+fn store(conn: &Connection, key: &str, provider: &str, resp: &LlmResponse) {
+    // a reply that asks for tools is not an answer
+    if !resp.tool_calls.is_empty() || resp.text.trim().is_empty() {
+        return;
+    }
+    let Some(ttl) = ttl_secs(conn) else { return };
+
+    let usage = resp.usage.unwrap_or_default();
+    let _ = conn.execute(
+        "INSERT OR REPLACE INTO llm_cache
+            (key, provider, model, response_text, input_tokens, output_tokens, hits, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, datetime('now'), datetime('now', ?7))",
+        params![
+            key,
+            provider,
+            resp.model,
+            resp.text,
+            usage.input_tokens,
+            usage.output_tokens,
+            format!("+{} seconds", ttl)
+        ],
+    );
+}
+
+// Section for usage_report
+pub fn report(conn: &Connection, days: i64) -> String {
+    ensure_cache_tables(conn);
+
+    let status = match ttl_secs(conn) {
+        Some(ttl) => format!("on, TTL {} min", ttl / 60),
+        None => "off".to_string(),
+    };
+    let mut out = format!("\n🗄️ Response cache ({}):\n", status);
+
+    let mut stmt = match conn.prepare(
+        "SELECT provider, SUM(hits), SUM(misses), SUM(saved_usd) FROM llm_cache_stats
+         WHERE day >= date('now', ?1)
+         GROUP BY provider ORDER BY provider",
+    ) {
+        Ok(s) => s,
+        Err(_) => return out,
+    };
+
+    let rows: Vec<(String, i64, i64, f64)> = stmt
+        .query_map([format!("-{} days", days)], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+        })
+        .map(|rows| rows.flatten().collect())
+        .unwrap_or_default();
+
+    if rows.is_empty() {
+        out.push_str("ℹ️ No cache lookups in this period.\n");
+    }
+    for (provider, hits, misses, saved) in rows {
+        let total = hits + misses;
+        out.push_str(&format!(
+            "{} | hits {} | misses {} | hit rate {}% | saved ~${:.4}\n",
+            provider,
+            hits,
+            misses,
+            if total > 0 { hits * 100 / total } else { 0 },
+            saved
+        ));
+    }
+
+    out
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// ttl_minutes = None or 0 turns the cache off (entries are kept until they expire)
+redacted! {
+    #[tauri::command]
+    pub fn set_llm_cache(ttl_minutes: Option<i64>) -> Result<String, String> {
+        let ttl = ttl_minutes.filter(|m| *m > 0).map(|m| m * 60);
+
+        let conn = open_db()?;
+        ensure_cache_tables(&conn);
+        conn.execute(
+            "UPDATE user_settings SET llm_cache_ttl_secs=?1, updated_at=datetime('now') WHERE id=1",
+            params![ttl],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        match ttl {
+            Some(secs) => {
+                write_log("INFO", &format!("LLM cache enabled (TTL {}s)", secs));
+                Ok(format!("✅ LLM response cache on (TTL {} min).", secs / 60))
+            }
+            None => {
+                write_log("INFO", "LLM cache disabled");
+                Ok("✅ LLM response cache off.".to_string())
+            }
+        }
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn clear_llm_cache() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_cache_tables(&conn);
+        let n = conn
+            .execute("DELETE FROM llm_cache", [])
+            .map_err(|e| format!("DB delete failed: {}", e))?;
+        write_log("INFO", &format!("LLM cache cleared ({} entries)", n));
+        Ok(format!("✅ Cleared {} cached response(s).", n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ChatMessage, JsonSchema, ToolSpec};
+
+    fn req(system: &str, prompt: &str) -> LlmRequest {
+        LlmRequest {
+            system: Some(system.to_string()),
+            ..LlmRequest::prompt(prompt)
+        }
+    }
+
+    fn resp(text: &str) -> LlmResponse {
+        LlmResponse {
+            text: text.to_string(),
+            model: "gpt-4o-mini".to_string(),
+            usage: Some(Usage {
+                input_tokens: 100,
+                output_tokens: 50,
+            }),
+            tool_calls: vec![],
+        }
+    }
+
+    fn db(ttl_secs: Option<i64>) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_cache_tables(&conn);
+        usage::ensure_usage_tables(&conn);
+        conn.execute("UPDATE user_settings SET llm_cache_ttl_secs=?1 WHERE id=1", params![ttl_secs])
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn key_ignores_whitespace_only() {
+        let a = cache_key("openai", "gpt-4o-mini", &req("Be brief.", "Write a post\nabout Rust"));
+        let b = cache_key("openai", "gpt-4o-mini", &req("  Be   brief. ", "Write a post about\tRust\n\n"));
+        assert_eq!(a, b);
+
+        assert_ne!(a, cache_key("openai", "gpt-4o", &req("Be brief.", "Write a post about Rust")));
+        assert_ne!(a, cache_key("anthropic", "gpt-4o-mini", &req("Be brief.", "Write a post about Rust")));
+        assert_ne!(a, cache_key("openai", "gpt-4o-mini", &req("Be brief.", "Write a post about Go")));
+
+        let mut longer = req("Be brief.", "Write a post about Rust");
+        longer.max_tokens = 2000;
+        assert_ne!(a, cache_key("openai", "gpt-4o-mini", &longer));
+
+        let mut turns = req("Be brief.", "Write a post about Rust");
+        turns.messages.push(ChatMessage::assistant("Sure"));
+        assert_ne!(a, cache_key("openai", "gpt-4o-mini", &turns));
+    }
+
+    #[test]
+    fn bypass_tools_and_structured_requests_skip_the_cache() {
+        assert!(cacheable(&LlmRequest::prompt("hi")));
+
+        let mut bypass = LlmRequest::prompt("hi");
+        bypass.bypass_cache = true;
+        assert!(!cacheable(&bypass));
+
+        let mut tools = LlmRequest::prompt("hi");
+        tools.tools.push(ToolSpec {
+            name: "web_search".to_string(),
+            description: "search".to_string(),
+            parameters: serde_json::json!({"type": "object"}),
+        });
+        assert!(!cacheable(&tools));
+
+        let mut structured = LlmRequest::prompt("hi");
+        structured.response_schema = Some(JsonSchema::new("tone", serde_json::json!({"type": "object"})));
+        assert!(!cacheable(&structured));
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        // cache off => nothing stored
+        let conn = db(None);
+        store(&conn, "k", "openai", &resp("Hello"));
+        assert!(lookup(&conn, "k", "openai").is_none());
+
+        let conn = db(Some(3600));
+        store(&conn, "k", "openai", &resp("Hello"));
+        // tool calls / empty replies are not answers
+        store(&conn, "empty", "openai", &resp("  "));
+        assert!(lookup(&conn, "empty", "openai").is_none());
+
+        let hit = lookup(&conn, "k", "openai").unwrap();
+        assert_eq!(hit.text, "Hello");
+        assert!(hit.usage.is_none());
+
+        conn.execute("UPDATE llm_cache SET expires_at=datetime('now', '-1 seconds')", []).unwrap();
+        assert!(lookup(&conn, "k", "openai").is_none());
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM llm_cache", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 0);
+
+        let (hits, misses): (i64, i64) = conn
+            .query_row("SELECT SUM(hits), SUM(misses) FROM llm_cache_stats", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!((hits, misses), (1, 2));
+    }
+}
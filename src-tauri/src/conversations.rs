@@ -0,0 +1,366 @@
+// -------------------------
+// ✅ Chat memory
+// conversations + messages in SQLite; each llm_reply with a conversation id
+// sends the most recent history that fits the token budget.
+// -------------------------
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::llm::ChatMessage;
+use crate::{ensure_column, ensure_user_settings_table, open_db, write_log};
+
+const DEFAULT_HISTORY_TOKEN_BUDGET: i64 = 3000;
+
+pub fn ensure_conversation_tables(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS conversations (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS messages (
+            id TEXT PRIMARY KEY,
+            conversation_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            tokens INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages (conversation_id, created_at)",
+        [],
+    );
+
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "history_token_budget INTEGER NULL");
+}
+
+// Rough estimate (~4 chars per token + per-message overhead); good enough for trimming
+pub fn estimate_tokens(text: &str) -> i64 {
+    (text.chars().count() as i64 + 3) / 4 + 4
+}
+
+fn history_token_budget(conn: &Connection) -> i64 {
+    conn.query_row(
+        "SELECT history_token_budget FROM user_settings WHERE id=1",
+        [],
+        |r| r.get::<_, Option<i64>>(0),
+    )
+    .ok()
+    .flatten()
+    .filter(|b| *b > 0)
+    .unwrap_or(DEFAULT_HISTORY_TOKEN_BUDGET)
+}
+
+fn conversation_exists(conn: &Connection, id: &str) -> bool {
+    conn.query_row("SELECT 1 FROM conversations WHERE id=?1", [id], |_| Ok(()))
+        .optional()
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+// Newest-first walk until the budget is used; the result is oldest-first.
+pub fn trim_history(history: Vec<(ChatMessage, i64)>, budget: i64) -> Vec<ChatMessage> {
+    let mut used = 0;
+    let mut kept: Vec<ChatMessage> = vec![];
+
+    for (msg, tokens) in history.into_iter().rev() {
+        // always keep the newest message (the prompt itself)
+        if !kept.is_empty() && used + tokens > budget {
+            break;
+        }
+        used += tokens;
+        kept.push(msg);
+    }
+
+    kept.reverse();
+
+    // providers expect the conversation to start with a user turn
+    while kept.len() > 1 && kept[0].role != "user" {
+        kept.remove(0);
+    }
+
+    // a failed call leaves two user turns in a row; merge so roles alternate
+    let mut merged: Vec<ChatMessage> = vec![];
+    for msg in kept {
+        match merged.last_mut() {
+            Some(prev) if prev.role == msg.role => {
+                prev.content.push_str("\n\n");
+                prev.content.push_str(&msg.content);
+            }
+            _ => merged.push(msg),
+        }
+    }
+
+    merged
+}
+
+pub fn append_message(conn: &Connection, conversation_id: &str, role: &str, content: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO messages (id, conversation_id, role, content, tokens, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, strftime('%Y-%m-%d %H:%M:%f', 'now'))",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            conversation_id,
+            role,
+            content,
+            estimate_tokens(content)
+        ],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+
+    conn.execute(
+        "UPDATE conversations SET updated_at=datetime('now') WHERE id=?1",
+        [conversation_id],
+    )
+    .map_err(|e| format!("DB update failed: {}", e))?;
+
+    Ok(())
+}
+
+// Stores the new user message and returns the trimmed history to send (ending with it)
+pub fn push_user_message(conversation_id: &str, prompt: &str) -> Result<Vec<ChatMessage>, String> {
+    let conn = open_db()?;
+    ensure_conversation_tables(&conn);
+
+    if !conversation_exists(&conn, conversation_id) {
+        return Err("❌ Conversation not found.".to_string());
+    }
+
+    append_message(&conn, conversation_id, "user", prompt)?;
+
+    // first user message names an untitled conversation
+    let short: String = prompt.trim().chars().take(40).collect();
+    let _ = conn.execute(
+        "UPDATE conversations SET title=?1 WHERE id=?2 AND title='New chat'",
+        params![short, conversation_id],
+    );
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT role, content, tokens FROM messages
+             WHERE conversation_id=?1
+             ORDER BY created_at ASC",
+        )
+        .map_err(|e| format!("Query failed: {}", e))?;
+
+    let history: Vec<(ChatMessage, i64)> = stmt
+        .query_map([conversation_id], |r| {
+            Ok((
+                ChatMessage::new(&r.get::<_, String>(0)?, &r.get::<_, String>(1)?),
+                r.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| format!("Query map failed: {}", e))?
+        .flatten()
+        .collect();
+
+    Ok(trim_history(history, history_token_budget(&conn)))
+}
+
+pub fn push_assistant_message(conversation_id: &str, content: &str) -> Result<(), String> {
+    let conn = open_db()?;
+    ensure_conversation_tables(&conn);
+    append_message(&conn, conversation_id, "assistant", content)
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// Returns the new conversation id (the UI keeps it and passes it to llm_reply)
+redacted! {
+    #[tauri::command]
+    pub fn new_conversation(title: Option<String>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_conversation_tables(&conn);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let title = title
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| "New chat".to_string());
+
+        conn.execute(
+            "INSERT INTO conversations (id, title, created_at, updated_at)
+         VALUES (?1, ?2, datetime('now'), datetime('now'))",
+            params![id, title],
+        )
+        .map_err(|e| format!("DB insert failed: {}", e))?;
+
+        write_log("INFO", &format!("Conversation created id={}", id));
+        Ok(id)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn list_conversations() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_conversation_tables(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.title, c.updated_at, COUNT(m.id)
+             FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             GROUP BY c.id
+             ORDER BY c.updated_at DESC",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                    r.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("💬 Conversations:\n\n");
+        let mut count = 0;
+
+        for (id, title, updated_at, messages) in rows.flatten() {
+            count += 1;
+            out.push_str(&format!(
+                "{}. {}\n   id: {}\n   messages: {}\n   updated: {}\n\n",
+                count, title, id, messages, updated_at
+            ));
+        }
+
+        if count == 0 {
+            Ok("ℹ️ No conversations yet.".to_string())
+        } else {
+            Ok(out)
+        }
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn rename_conversation(id: String, title: String) -> Result<String, String> {
+        let title = title.trim().to_string();
+        if title.is_empty() {
+            return Err("❌ Title is empty.".to_string());
+        }
+
+        let conn = open_db()?;
+        ensure_conversation_tables(&conn);
+
+        let n = conn
+            .execute(
+                "UPDATE conversations SET title=?1, updated_at=datetime('now') WHERE id=?2",
+                params![title, id],
+            )
+            .map_err(|e| format!("DB update failed: {}", e))?;
+
+        if n == 0 {
+            return Err("❌ Conversation not found.".to_string());
+        }
+
+        Ok(format!("✅ Conversation renamed to '{}'", title))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn delete_conversation(id: String) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_conversation_tables(&conn);
+
+        conn.execute("DELETE FROM messages WHERE conversation_id=?1", [&id])
+            .map_err(|e| format!("DB delete failed: {}", e))?;
+        let n = conn
+            .execute("DELETE FROM conversations WHERE id=?1", [&id])
+            .map_err(|e| format!("DB delete failed: {}", e))?;
+
+        if n == 0 {
+            return Err("❌ Conversation not found.".to_string());
+        }
+
+        write_log("INFO", &format!("Conversation deleted id={}", id));
+        Ok("✅ Conversation deleted.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: &str, content: &str, tokens: i64) -> (ChatMessage, i64) {
+        (ChatMessage::new(role, content), tokens)
+    }
+
+    fn shape(msgs: &[ChatMessage]) -> Vec<(String, String)> {
+        msgs.iter().map(|m| (m.role.clone(), m.content.clone())).collect()
+    }
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(r, c)| (r.to_string(), c.to_string())).collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_messages_within_budget() {
+        let history = vec![
+            msg("user", "q1", 10),
+            msg("assistant", "a1", 10),
+            msg("user", "q2", 10),
+            msg("assistant", "a2", 10),
+            msg("user", "q3", 10),
+        ];
+        let kept = trim_history(history.clone(), 30);
+        assert_eq!(shape(&kept), pairs(&[("user", "q2"), ("assistant", "a2"), ("user", "q3")]));
+
+        assert_eq!(trim_history(history, 1000).len(), 5);
+    }
+
+    #[test]
+    fn the_prompt_is_kept_even_over_budget() {
+        let kept = trim_history(vec![msg("user", "old", 5), msg("user", "huge prompt", 500)], 100);
+        assert_eq!(shape(&kept), pairs(&[("user", "huge prompt")]));
+        assert!(trim_history(vec![], 100).is_empty());
+    }
+
+    #[test]
+    fn drops_leading_non_user_turns() {
+        // the budget cuts between q1 and a1 => history would start with the assistant
+        let history = vec![
+            msg("user", "q1", 10),
+            msg("assistant", "a1", 10),
+            msg("assistant", "a1b", 10),
+            msg("user", "q2", 10),
+        ];
+        let kept = trim_history(history, 30);
+        assert_eq!(shape(&kept), pairs(&[("user", "q2")]));
+
+        // a lone newest message is kept whatever its role
+        assert_eq!(trim_history(vec![msg("assistant", "a", 1)], 10).len(), 1);
+    }
+
+    #[test]
+    fn merges_repeated_roles() {
+        // a failed call leaves q1 without an answer
+        let history = vec![
+            msg("user", "q1", 10),
+            msg("user", "q1 again", 10),
+            msg("assistant", "a", 10),
+            msg("user", "q2", 10),
+        ];
+        let kept = trim_history(history, 100);
+        assert_eq!(
+            shape(&kept),
+            pairs(&[("user", "q1\n\nq1 again"), ("assistant", "a"), ("user", "q2")])
+        );
+    }
+}
@@ -0,0 +1,447 @@
+// -------------------------
+// ✅ LLM drafts for agents
+// System prompt = agent role + goal + house style guide (template "draft_system").
+// User prompt = the agent's own template, or "draft_post" / "draft_comment".
+// The exact prompt is stored with the approval for audit.
+// If the LLM fails, the old fixed template is used (and marked as such).
+// The LLM step takes any `LlmProvider`, so evals (eval.rs) can run it offline.
+// A draft too close to something the agent recently published (memory.rs)
+// is redrafted once with those posts listed as "do not repeat".
+// -------------------------
+use rusqlite::{params, OptionalExtension};
+
+use crate::embeddings::{self, EmbeddingProvider};
+use crate::llm::{LlmProvider, LlmRequest, LlmResponse};
+use crate::memory::{self, Memory};
+use crate::prompts::{render_template, PromptVars};
+use crate::{
+    build_demo1_post, build_demo2_comment, ensure_agents_table, ensure_column, ensure_user_settings_table,
+    open_db, write_log, write_log_agent, RoutedProvider,
+};
+
+// Template name (or "name@version") + variables => text.
+// The app renders from the DB library; evals can render the built-ins.
+pub type Render = fn(&str, &PromptVars) -> Result<String, String>;
+
+pub const DEFAULT_STYLE_GUIDE: &str = "- Friendly, practical, first-person voice
+- Plain text only (LinkedIn does not render markdown)
+- Posts: max 1200 characters; comments: max 300 characters
+- At most 4 hashtags, placed at the end
+- No clickbait, no invented facts, numbers or quotes";
+
+pub struct AgentProfile {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub goal: String,
+    pub prompt_template: Option<String>,
+}
+
+pub struct Draft {
+    pub text: String,
+    pub prompt: String,
+    pub llm_used: String,
+}
+
+#[derive(Clone, Copy)]
+pub enum DraftKind {
+    Post,
+    Comment,
+}
+
+impl DraftKind {
+    fn label(&self) -> &'static str {
+        match self {
+            DraftKind::Post => "LinkedIn post",
+            DraftKind::Comment => "LinkedIn comment",
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            DraftKind::Post => "draft_post",
+            DraftKind::Comment => "draft_comment",
+        }
+    }
+}
+
+pub fn load_agent(agent_id: &str) -> Result<AgentProfile, String> {
+    let conn = open_db()?;
+    ensure_agents_table(&conn);
+
+    conn.query_row(
+        "SELECT id, name, role, goal, prompt_template FROM agents WHERE id=?1",
+        [agent_id],
+        |r| {
+            Ok(AgentProfile {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                role: r.get(2)?,
+                goal: r.get(3)?,
+                prompt_template: r.get(4)?,
+            })
+        },
+    )
+    .map_err(|_| "❌ Agent not found.".to_string())
+}
+
+fn ensure_style_guide_column(conn: &rusqlite::Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "style_guide TEXT NULL");
+}
+
+pub fn style_guide() -> String {
+    let saved: Option<String> = open_db().ok().and_then(|conn| {
+        ensure_style_guide_column(&conn);
+        conn.query_row("SELECT style_guide FROM user_settings WHERE id=1", [], |r| r.get(0))
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+    });
+
+    saved
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_STYLE_GUIDE.to_string())
+}
+
+pub fn build_system_prompt(
+    render: Render,
+    agent: &AgentProfile,
+    kind: DraftKind,
+    style: &str,
+) -> Result<String, String> {
+    render(
+        "draft_system",
+        &PromptVars::new()
+            .agent(agent)
+            .text("style_guide", style)
+            .text("kind", kind.label()),
+    )
+}
+
+// Every value the agent's template may reference
+pub fn build_user_prompt(
+    render: Render,
+    agent: &AgentProfile,
+    kind: DraftKind,
+    topics: &[String],
+    repo_url: Option<&str>,
+) -> Result<String, String> {
+    let top5: Vec<String> = topics.iter().take(5).cloned().collect();
+    let vars = PromptVars::new()
+        .agent(agent)
+        .list("topics", &top5)
+        .text("topic", top5.first().cloned().unwrap_or_default())
+        .text("repo_url", repo_url.unwrap_or_default())
+        .text("kind", kind.label());
+
+    let template = agent.prompt_template.as_deref().unwrap_or(kind.default_template());
+    render(template, &vars)
+}
+
+pub struct DraftPrompt {
+    pub system: String,
+    pub user: String,
+}
+
+impl DraftPrompt {
+    pub fn build(
+        render: Render,
+        agent: &AgentProfile,
+        kind: DraftKind,
+        topics: &[String],
+        repo_url: Option<&str>,
+        style: &str,
+    ) -> Result<Self, String> {
+        Ok(DraftPrompt {
+            system: build_system_prompt(render, agent, kind, style)?,
+            user: build_user_prompt(render, agent, kind, topics, repo_url)?,
+        })
+    }
+
+    fn avoiding(&self, recent: &[Memory]) -> Self {
+        let list = recent
+            .iter()
+            .take(3)
+            .map(|m| format!("---\n{}", m.text.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        DraftPrompt {
+            system: self.system.clone(),
+            user: format!(
+                "{}\n\nYou recently published the following. Pick a different topic or angle; do not repeat them:\n{}",
+                self.user, list
+            ),
+        }
+    }
+
+    // stored with the approval
+    pub fn audit_text(&self) -> String {
+        format!("SYSTEM:\n{}\n\nUSER:\n{}", self.system, self.user)
+    }
+}
+
+// Models like to wrap the answer in quotes or code fences
+fn clean_draft(text: &str) -> String {
+    let t = text.trim();
+    let t = t.trim_start_matches("```").trim_end_matches("```").trim();
+    let t = t
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(t);
+    t.trim().to_string()
+}
+
+// The LLM part of a draft. Returns the cleaned text; an empty reply is an error.
+pub async fn draft_step(provider: &dyn LlmProvider, prompt: &DraftPrompt) -> Result<(String, LlmResponse), String> {
+    let req = LlmRequest {
+        system: Some(prompt.system.clone()),
+        ..LlmRequest::prompt(&prompt.user)
+    };
+
+    let resp = provider.generate(&req).await?;
+    let text = clean_draft(&resp.text);
+    if text.is_empty() {
+        return Err("empty response".to_string());
+    }
+    Ok((text, resp))
+}
+
+// One redraft when the agent already published something this similar.
+// Embedding errors never block a draft.
+async fn avoid_repeats(
+    provider: &dyn LlmProvider,
+    embedder: &dyn EmbeddingProvider,
+    agent: &AgentProfile,
+    prompt: DraftPrompt,
+    text: String,
+    resp: LlmResponse,
+) -> (String, DraftPrompt, LlmResponse) {
+    let recent = match memory::similar_recent(embedder, &agent.id, &text).await {
+        Ok(r) if !r.is_empty() => r,
+        Ok(_) => return (text, prompt, resp),
+        Err(e) => {
+            write_log_agent("WARN", &agent.id, &format!("Memory check skipped: {}", e));
+            return (text, prompt, resp);
+        }
+    };
+
+    let retry = prompt.avoiding(&recent);
+    match draft_step(provider, &retry).await {
+        Ok((new_text, new_resp)) => {
+            write_log_agent("INFO", &agent.id, "Redrafted to avoid repeating a recent post");
+            (new_text, retry, new_resp)
+        }
+        Err(e) => {
+            write_log_agent("WARN", &agent.id, &format!("Redraft failed, keeping first draft: {}", e));
+            (text, prompt, resp)
+        }
+    }
+}
+
+pub async fn generate_draft(
+    agent: &AgentProfile,
+    kind: DraftKind,
+    topics: &[String],
+    repo_url: Option<&str>,
+) -> Result<Draft, String> {
+    // a broken template is a configuration error, not an LLM failure
+    let prompt = DraftPrompt::build(render_template, agent, kind, topics, repo_url, &style_guide())?;
+    let provider = RoutedProvider::new(Some(&agent.id));
+
+    match draft_step(&provider, &prompt).await {
+        Ok((text, resp)) => {
+            let (text, prompt, resp) = match embeddings::routed_embedder(Some(&agent.id)) {
+                Ok(embedder) => avoid_repeats(&provider, embedder.as_ref(), agent, prompt, text, resp).await,
+                Err(e) => {
+                    write_log_agent("WARN", &agent.id, &format!("Memory check skipped: {}", e));
+                    (text, prompt, resp)
+                }
+            };
+            Ok(Draft {
+                text,
+                prompt: prompt.audit_text(),
+                llm_used: format!("{} / {}", provider.label(), resp.model),
+            })
+        }
+        Err(reason) => {
+            write_log_agent(
+                "WARN",
+                &agent.id,
+                &format!("LLM draft failed, using template: {}", reason),
+            );
+            Ok(Draft {
+                text: match kind {
+                    DraftKind::Post => build_demo1_post(topics)?,
+                    DraftKind::Comment => build_demo2_comment(repo_url.unwrap_or_default())?,
+                },
+                prompt: prompt.audit_text(),
+                llm_used: "template (LLM unavailable)".to_string(),
+            })
+        }
+    }
+}
+
+// Trending topics (blocking openclaw call) -> LLM draft -> pending approval
+pub async fn draft_post_for_agent(agent_id: &str) -> Result<String, String> {
+    let agent = load_agent(agent_id)?;
+
+    let topics = tokio::task::spawn_blocking(crate::get_trending_topics)
+        .await
+        .map_err(|e| format!("Join error: {}", e))?;
+
+    let draft = generate_draft(&agent, DraftKind::Post, &topics, None).await?;
+
+    let approval_id = crate::create_approval_with_prompt(
+        &agent.id,
+        "linkedin_post",
+        &draft.text,
+        Some(&draft.prompt),
+        Some(&draft.llm_used),
+    )?;
+
+    write_log_agent(
+        "INFO",
+        &agent.id,
+        &format!("Created approval id={} (draft by {})", approval_id, draft.llm_used),
+    );
+
+    Ok(approval_id)
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn set_style_guide(style_guide: Option<String>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_style_guide_column(&conn);
+
+        let value = style_guide.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+        conn.execute(
+            "UPDATE user_settings SET style_guide=?1, updated_at=datetime('now') WHERE id=1",
+            params![value],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", "House style guide updated");
+        Ok(if value.is_some() {
+            "✅ Style guide saved.".to_string()
+        } else {
+            "✅ Style guide reset to default.".to_string()
+        })
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn show_style_guide() -> Result<String, String> {
+        Ok(format!("🎨 House style guide:\n\n{}", style_guide()))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn get_approval_prompt(id: String) -> Result<String, String> {
+        let conn = open_db()?;
+        crate::ensure_approvals_table(&conn);
+
+        let (prompt, llm_used): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT prompt, llm_used FROM approvals WHERE id=?1",
+                [&id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(|_| "❌ Approval not found.".to_string())?;
+
+        Ok(format!(
+            "🔎 Approval {}\nDrafted by: {}\n\n{}",
+            id,
+            llm_used.unwrap_or_else(|| "(manual/template)".to_string()),
+            prompt.unwrap_or_else(|| "(no prompt stored)".to_string())
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::Embeddings;
+    use crate::llm::test_support::{reply, MockProvider};
+    use async_trait::async_trait;
+
+    // Texts about Rust point one way, everything else the other
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn model(&self) -> &str {
+            "mock-embed"
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Embeddings, String> {
+            Ok(Embeddings {
+                model: "mock-embed".to_string(),
+                vectors: texts
+                    .iter()
+                    .map(|t| if t.to_lowercase().contains("rust") { vec![1.0, 0.0] } else { vec![0.0, 1.0] })
+                    .collect(),
+                usage: None,
+            })
+        }
+    }
+
+    // memories go to this test thread's throwaway DB (see app_data_dir)
+    async fn agent_that_published(text: &str) -> AgentProfile {
+        let agent = AgentProfile {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: "Tester".to_string(),
+            role: "Writer".to_string(),
+            goal: "Post about programming".to_string(),
+            prompt_template: None,
+        };
+        memory::remember(&TopicEmbedder, &agent.id, "linkedin_post", text, None).await.unwrap();
+        agent
+    }
+
+    fn first_draft(text: &str) -> (DraftPrompt, String, LlmResponse) {
+        let prompt = DraftPrompt {
+            system: "You write LinkedIn posts.".to_string(),
+            user: "Write a post about today's topic.".to_string(),
+        };
+        (prompt, text.to_string(), reply(text, vec![]))
+    }
+
+    #[tokio::test]
+    async fn a_similar_past_post_triggers_one_redraft() {
+        let agent = agent_that_published("Rust 1.80 is out and it is great").await;
+        let provider = MockProvider::replying("Three tips for better code reviews");
+        let (prompt, text, resp) = first_draft("Why I love Rust");
+
+        let (text, prompt, _) = avoid_repeats(&provider, &TopicEmbedder, &agent, prompt, text, resp).await;
+        assert_eq!(text, "Three tips for better code reviews");
+        assert_eq!(provider.requests().len(), 1);
+        assert!(prompt.user.contains("do not repeat them"));
+        assert!(prompt.user.contains("Rust 1.80 is out and it is great"));
+        assert!(provider.requests()[0].messages[0].content.contains("Rust 1.80 is out"));
+    }
+
+    #[tokio::test]
+    async fn a_dissimilar_past_post_keeps_the_draft() {
+        let agent = agent_that_published("My balcony garden in July").await;
+        let provider = MockProvider::replying("unused");
+        let (prompt, text, resp) = first_draft("Why I love Rust");
+
+        let (text, prompt, _) = avoid_repeats(&provider, &TopicEmbedder, &agent, prompt, text, resp).await;
+        assert_eq!(text, "Why I love Rust");
+        assert!(provider.requests().is_empty());
+        assert!(!prompt.user.contains("do not repeat"));
+    }
+}
@@ -0,0 +1,360 @@
+// -------------------------
+// ✅ Embeddings (OpenAI, Gemini, local Ollama)
+// Same routing as chat: agent provider -> active provider -> local.
+// Anthropic has no embeddings API, so it falls back to the local model.
+// Vectors from different models are not comparable; callers keep the
+// provider/model next to every stored vector (see memory.rs).
+// -------------------------
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::llm::{LlmResponse, Usage};
+use crate::{get_saved_llm, usage, write_log};
+
+pub const DEFAULT_LOCAL_EMBED_MODEL: &str = "nomic-embed-text";
+
+#[derive(Debug)]
+pub struct Embeddings {
+    pub model: String,
+    // one vector per input, same order
+    pub vectors: Vec<Vec<f32>>,
+    pub usage: Option<Usage>,
+}
+
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    // same ids as LlmProvider::name ("gemini", "openai", "local")
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+    async fn embed(&self, texts: &[String]) -> Result<Embeddings, String>;
+}
+
+pub fn embedder_for(provider: &str, key: Option<String>) -> Box<dyn EmbeddingProvider> {
+    match (provider, key) {
+        ("openai", Some(key)) => Box::new(OpenAiEmbedder::new(key)),
+        ("gemini", Some(key)) => Box::new(GeminiEmbedder::new(key)),
+        _ => {
+            let local = crate::ollama::local_settings();
+            Box::new(OllamaEmbedder::new(local.base_url, DEFAULT_LOCAL_EMBED_MODEL.to_string()))
+        }
+    }
+}
+
+// Routed embedder for an agent (or the app when None)
+pub fn routed_embedder(agent_id: Option<&str>) -> Result<Box<dyn EmbeddingProvider>, String> {
+    Ok(match get_saved_llm(agent_id)? {
+        Some((provider, key)) => embedder_for(&provider, Some(key)),
+        None => embedder_for("local", None),
+    })
+}
+
+// Budget check -> embed -> usage row (embeddings only bill input tokens)
+pub async fn embed(
+    agent_id: Option<&str>,
+    embedder: &dyn EmbeddingProvider,
+    texts: &[String],
+) -> Result<Embeddings, String> {
+    usage::check_budget(agent_id, embedder.name())?;
+
+    let out = embedder
+        .embed(texts)
+        .await
+        .map_err(|e| format!("(Embeddings: {} / {}) Error: {}", embedder.name(), embedder.model(), e))?;
+
+    if out.vectors.len() != texts.len() {
+        return Err(format!(
+            "(Embeddings: {}) expected {} vectors, got {}",
+            embedder.name(),
+            texts.len(),
+            out.vectors.len()
+        ));
+    }
+
+    usage::record_usage(
+        agent_id,
+        embedder.name(),
+        &LlmResponse {
+            text: String::new(),
+            model: out.model.clone(),
+            usage: out.usage,
+            tool_calls: vec![],
+        },
+    );
+
+    Ok(out)
+}
+
+fn parse_vector(v: &serde_json::Value) -> Option<Vec<f32>> {
+    v.as_array()?
+        .iter()
+        .map(|x| x.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+// ===== OpenAI =====
+pub struct OpenAiEmbedder {
+    key: String,
+    model: String,
+}
+
+impl OpenAiEmbedder {
+    pub fn new(key: String) -> Self {
+        OpenAiEmbedder {
+            key,
+            model: "text-embedding-3-small".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbedder {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Embeddings, String> {
+        let client = crate::http::client();
+
+        let resp = client
+            .post("https://api.openai.com/v1/embeddings")
+            .bearer_auth(&self.key)
+            .json(&json!({ "model": self.model, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| format!("OpenAI request failed: {}", e))?;
+
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed reading OpenAI response: {}", e))?;
+
+        if !status.is_success() {
+            write_log("ERROR", &format!("OpenAI embeddings HTTP {}: {}", status, body));
+            return Err(format!("OpenAI HTTP {}:\n{}", status, body));
+        }
+
+        let val: serde_json::Value =
+            serde_json::from_str(&body).map_err(|e| format!("Parse failed: {}\nRaw:\n{}", e, body))?;
+
+        // "data" carries an index; do not rely on the order
+        let mut data: Vec<(i64, Vec<f32>)> = val["data"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|d| Some((d["index"].as_i64()?, parse_vector(&d["embedding"])?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        data.sort_by_key(|(i, _)| *i);
+
+        Ok(Embeddings {
+            model: val["model"].as_str().unwrap_or(&self.model).to_string(),
+            vectors: data.into_iter().map(|(_, v)| v).collect(),
+            usage: val["usage"]["prompt_tokens"].as_i64().map(|t| Usage {
+                input_tokens: t,
+                output_tokens: 0,
+            }),
+        })
+    }
+}
+
+// ===== Gemini =====
+pub struct GeminiEmbedder {
+    key: String,
+    model: String,
+}
+
+impl GeminiEmbedder {
+    pub fn new(key: String) -> Self {
+        GeminiEmbedder {
+            key,
+            model: "text-embedding-004".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for GeminiEmbedder {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Embeddings, String> {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:batchEmbedContents?key={}",
+            self.model, self.key
+        );
+
+        let requests: Vec<serde_json::Value> = texts
+            .iter()
+            .map(|t| {
+                json!({
+                    "model": format!("models/{}", self.model),
+                    "content": { "parts": [{ "text": t }] }
+                })
+            })
+            .collect();
+
+        let client = crate::http::client();
+        let resp = client
+            .post(url)
+            .json(&json!({ "requests": requests }))
+            .send()
+            .await
+            .map_err(|e| format!("Gemini request failed: {}", e))?;
+
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed reading Gemini response: {}", e))?;
+
+        if !status.is_success() {
+            write_log("ERROR", &format!("Gemini embeddings HTTP {}: {}", status, body));
+            return Err(format!("Gemini HTTP {}:\n{}", status, body));
+        }
+
+        let val: serde_json::Value =
+            serde_json::from_str(&body).map_err(|e| format!("Parse failed: {}\nRaw:\n{}", e, body))?;
+
+        let vectors = val["embeddings"]
+            .as_array()
+            .map(|items| items.iter().filter_map(|e| parse_vector(&e["values"])).collect())
+            .unwrap_or_default();
+
+        // batchEmbedContents does not report token usage
+        Ok(Embeddings {
+            model: self.model.clone(),
+            vectors,
+            usage: None,
+        })
+    }
+}
+
+// ===== Local (Ollama) =====
+pub struct OllamaEmbedder {
+    base_url: String,
+    model: String,
+}
+
+impl OllamaEmbedder {
+    pub fn new(base_url: String, model: String) -> Self {
+        OllamaEmbedder { base_url, model }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbedder {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Embeddings, String> {
+        let client = crate::http::client();
+
+        let res = client
+            .post(format!("{}/api/embed", self.base_url.trim_end_matches('/')))
+            .json(&json!({ "model": self.model, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| {
+                format!(
+                    "Local LLM (Ollama) is not reachable at {}: {}\nCheck with: ollama status",
+                    self.base_url, e
+                )
+            })?;
+
+        let status = res.status();
+        let body = res
+            .text()
+            .await
+            .map_err(|e| format!("Local LLM read body failed: {}", e))?;
+
+        if !status.is_success() {
+            write_log("ERROR", &format!("Local embeddings HTTP {}: {}", status, body));
+            if status.as_u16() == 404 && body.contains("not found") {
+                return Err(format!(
+                    "Local embedding model '{}' is not installed. Run: pull model {}",
+                    self.model, self.model
+                ));
+            }
+            return Err(format!("Local LLM HTTP {}:\n{}", status, body));
+        }
+
+        let val: serde_json::Value =
+            serde_json::from_str(&body).map_err(|e| format!("Parse failed: {}\nRaw:\n{}", e, body))?;
+
+        let vectors = val["embeddings"]
+            .as_array()
+            .map(|items| items.iter().filter_map(parse_vector).collect())
+            .unwrap_or_default();
+
+        Ok(Embeddings {
+            model: self.model.clone(),
+            vectors,
+            usage: val["prompt_eval_count"].as_i64().map(|t| Usage {
+                input_tokens: t,
+                output_tokens: 0,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn ollama_embeds_a_batch() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_json(json!({ "model": "nomic-embed-text", "input": ["a", "b"] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "nomic-embed-text",
+                "embeddings": [[0.1, 0.2], [0.3, 0.4]],
+                "prompt_eval_count": 4
+            })))
+            .mount(&server)
+            .await;
+
+        let embedder = OllamaEmbedder::new(server.uri(), DEFAULT_LOCAL_EMBED_MODEL.to_string());
+        let out = embedder.embed(&["a".to_string(), "b".to_string()]).await.unwrap();
+
+        assert_eq!(out.vectors, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        assert_eq!(out.usage.unwrap().input_tokens, 4);
+    }
+
+    #[tokio::test]
+    async fn ollama_missing_model_gives_pull_hint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(json!({ "error": "model \"nomic-embed-text\" not found" })),
+            )
+            .mount(&server)
+            .await;
+
+        let embedder = OllamaEmbedder::new(server.uri(), DEFAULT_LOCAL_EMBED_MODEL.to_string());
+        let err = embedder.embed(&["a".to_string()]).await.unwrap_err();
+
+        assert!(err.contains("pull model nomic-embed-text"), "{}", err);
+    }
+}
@@ -0,0 +1,347 @@
+// -------------------------
+// ✅ Draft evals
+// Runs the draft step (drafts::draft_step) over the fixture set in
+// evals/drafts.json and checks each draft: length, hashtags, banned
+// phrases, required mentions, and what reached the prompt.
+// `cargo test` runs it offline with a mock provider and the built-in
+// templates; `run_draft_eval` runs it in the app against the routed LLM.
+// -------------------------
+use serde::Deserialize;
+
+use crate::drafts::{self, AgentProfile, DraftKind, DraftPrompt, Render};
+use crate::llm::LlmProvider;
+use crate::prompts::render_template;
+use crate::{ensure_agents_table, guard, open_db, write_log, RoutedProvider};
+
+const FIXTURES: &str = include_str!("../evals/drafts.json");
+
+#[derive(Deserialize)]
+pub struct EvalSuite {
+    pub agent: EvalAgent,
+    pub cases: Vec<EvalCase>,
+}
+
+#[derive(Deserialize)]
+pub struct EvalAgent {
+    pub name: String,
+    pub role: String,
+    pub goal: String,
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+}
+
+impl EvalAgent {
+    fn profile(&self) -> AgentProfile {
+        AgentProfile {
+            id: "eval".to_string(),
+            name: self.name.clone(),
+            role: self.role.clone(),
+            goal: self.goal.clone(),
+            prompt_template: self.prompt_template.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EvalCase {
+    pub name: String,
+    // "post" | "comment"
+    pub kind: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub repo_url: Option<String>,
+    // canned reply for offline runs (ignored by run_draft_eval)
+    #[serde(default)]
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub mock_reply: Option<String>,
+    #[serde(default)]
+    pub expect: Expect,
+}
+
+impl EvalCase {
+    fn draft_kind(&self) -> Result<DraftKind, String> {
+        match self.kind.trim().to_lowercase().as_str() {
+            "post" => Ok(DraftKind::Post),
+            "comment" => Ok(DraftKind::Comment),
+            other => Err(format!("unknown kind '{}' (use post or comment)", other)),
+        }
+    }
+}
+
+// Every field is optional; phrase/hashtag checks are case-insensitive
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Expect {
+    pub min_chars: Option<usize>,
+    pub max_chars: Option<usize>,
+    pub max_hashtags: Option<usize>,
+    pub required_hashtags: Vec<String>,
+    pub banned_phrases: Vec<String>,
+    // the draft must contain these
+    pub mentions: Vec<String>,
+    // the user prompt must / must not contain these
+    pub prompt_mentions: Vec<String>,
+    pub prompt_excludes: Vec<String>,
+}
+
+pub struct CaseResult {
+    pub name: String,
+    pub failures: Vec<String>,
+    pub text: String,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub fn load_suite() -> Result<EvalSuite, String> {
+    serde_json::from_str(FIXTURES).map_err(|e| format!("❌ Invalid eval fixtures: {}", e))
+}
+
+pub fn check(expect: &Expect, text: &str, prompt: &DraftPrompt) -> Vec<String> {
+    let mut failures = vec![];
+    let lower = text.to_lowercase();
+    let chars = text.chars().count();
+
+    if let Some(min) = expect.min_chars.filter(|m| chars < *m) {
+        failures.push(format!("too short: {} chars (min {})", chars, min));
+    }
+    if let Some(max) = expect.max_chars.filter(|m| chars > *m) {
+        failures.push(format!("too long: {} chars (max {})", chars, max));
+    }
+
+    let tags = guard::hashtags(text);
+    if let Some(max) = expect.max_hashtags.filter(|m| tags.len() > *m) {
+        failures.push(format!("{} hashtags (max {})", tags.len(), max));
+    }
+    for tag in &expect.required_hashtags {
+        let tag = format!("#{}", tag.trim().trim_start_matches('#').to_lowercase());
+        if !tags.contains(&tag) {
+            failures.push(format!("missing hashtag {}", tag));
+        }
+    }
+
+    for phrase in &expect.banned_phrases {
+        if lower.contains(&phrase.to_lowercase()) {
+            failures.push(format!("contains banned phrase \"{}\"", phrase));
+        }
+    }
+    for m in &expect.mentions {
+        if !lower.contains(&m.to_lowercase()) {
+            failures.push(format!("does not mention \"{}\"", m));
+        }
+    }
+
+    for m in &expect.prompt_mentions {
+        if !prompt.user.contains(m.as_str()) {
+            failures.push(format!("prompt does not contain \"{}\"", m));
+        }
+    }
+    for m in &expect.prompt_excludes {
+        if prompt.user.contains(m.as_str()) {
+            failures.push(format!("prompt contains \"{}\"", m));
+        }
+    }
+
+    failures
+}
+
+pub async fn run_case(
+    provider: &dyn LlmProvider,
+    render: Render,
+    agent: &AgentProfile,
+    style: &str,
+    case: &EvalCase,
+) -> CaseResult {
+    let result = |failures: Vec<String>, text: String| CaseResult {
+        name: case.name.clone(),
+        failures,
+        text,
+    };
+
+    let prompt = match case
+        .draft_kind()
+        .and_then(|kind| DraftPrompt::build(render, agent, kind, &case.topics, case.repo_url.as_deref(), style))
+    {
+        Ok(p) => p,
+        Err(e) => return result(vec![format!("prompt: {}", e)], String::new()),
+    };
+
+    match drafts::draft_step(provider, &prompt).await {
+        Ok((text, _)) => result(check(&case.expect, &text, &prompt), text),
+        Err(e) => result(vec![format!("LLM error: {}", e)], String::new()),
+    }
+}
+
+pub fn report(results: &[CaseResult]) -> String {
+    let passed = results.iter().filter(|r| r.passed()).count();
+    let mut out = format!("🧪 Draft eval: {}/{} passed\n", passed, results.len());
+
+    for r in results {
+        if r.passed() {
+            out.push_str(&format!("\n✅ {}", r.name));
+        } else {
+            out.push_str(&format!("\n❌ {}", r.name));
+            for f in &r.failures {
+                out.push_str(&format!("\n   - {}", f));
+            }
+        }
+        if !r.text.is_empty() {
+            let preview: String = r.text.chars().take(120).collect();
+            out.push_str(&format!("\n   {}", preview.replace('\n', " ")));
+        }
+    }
+
+    out
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// Runs the fixtures through the real LLM route (costs tokens; counts toward usage).
+// agent_name = None => the fixture agent with the current template library.
+redacted! {
+    #[tauri::command]
+    pub async fn run_draft_eval(agent_name: Option<String>) -> Result<String, String> {
+        let suite = load_suite()?;
+
+        let agent = match agent_name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+            Some(name) => {
+                let agent_id: String = {
+                    let conn = open_db()?;
+                    ensure_agents_table(&conn);
+                    conn.query_row(
+                        "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                        [&name],
+                        |r| r.get(0),
+                    )
+                    .map_err(|_| format!("❌ Agent '{}' not found.", name))?
+                };
+                drafts::load_agent(&agent_id)?
+            }
+            None => suite.agent.profile(),
+        };
+
+        let provider = RoutedProvider::new(if agent.id == "eval" { None } else { Some(&agent.id) });
+        let style = drafts::style_guide();
+
+        write_log("INFO", &format!("Draft eval started for agent '{}'", agent.name));
+
+        let mut results = vec![];
+        for case in &suite.cases {
+            results.push(run_case(&provider, render_template, &agent, &style, case).await);
+        }
+
+        let out = report(&results);
+        write_log("INFO", &format!("Draft eval finished ({})", provider.label()));
+        Ok(format!("{}\n\nLLM: {}", out, provider.label()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::MockProvider;
+    use crate::prompts::render_builtin;
+
+    fn case(kind: &str, reply: &str, expect: Expect) -> EvalCase {
+        EvalCase {
+            name: "adhoc".to_string(),
+            kind: kind.to_string(),
+            topics: vec!["Local-first LLMs".to_string()],
+            repo_url: None,
+            mock_reply: Some(reply.to_string()),
+            expect,
+        }
+    }
+
+    #[tokio::test]
+    async fn fixtures_pass_with_mock_replies() {
+        let suite = load_suite().unwrap();
+        let agent = suite.agent.profile();
+
+        let mut results = vec![];
+        for c in &suite.cases {
+            let provider = MockProvider::replying(c.mock_reply.as_deref().unwrap_or_default());
+            results.push(run_case(&provider, render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, c).await);
+        }
+
+        let out = report(&results);
+        assert!(results.iter().all(|r| r.passed()), "{}", out);
+        assert!(out.contains(&format!("{}/{} passed", results.len(), results.len())), "{}", out);
+    }
+
+    #[tokio::test]
+    async fn system_prompt_carries_agent_and_style_guide() {
+        let suite = load_suite().unwrap();
+        let agent = suite.agent.profile();
+        let provider = MockProvider::replying("Short post #openclaw");
+
+        let c = case("post", "", Expect::default());
+        run_case(&provider, render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, &c).await;
+
+        let requests = provider.requests();
+        let system = requests[0].system.as_deref().unwrap();
+        assert!(system.contains("Trending Agent"));
+        assert!(system.contains("At most 4 hashtags"));
+        assert!(system.contains("LinkedIn post"));
+    }
+
+    #[tokio::test]
+    async fn reports_every_failed_assertion() {
+        let agent = load_suite().unwrap().agent.profile();
+        let reply = "This is a game-changer! #ai #ml #llm #agents #tech";
+        let c = case(
+            "post",
+            reply,
+            Expect {
+                min_chars: Some(80),
+                max_hashtags: Some(4),
+                required_hashtags: vec!["openclaw".to_string()],
+                banned_phrases: vec!["Game-Changer".to_string()],
+                prompt_excludes: vec!["Local-first".to_string()],
+                ..Expect::default()
+            },
+        );
+
+        let provider = MockProvider::replying(reply);
+        let r = run_case(&provider, render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, &c).await;
+
+        assert!(!r.passed());
+        assert_eq!(r.failures.len(), 5, "{:?}", r.failures);
+        assert!(r.failures.iter().any(|f| f.starts_with("too short")));
+        assert!(r.failures.iter().any(|f| f == "5 hashtags (max 4)"));
+        assert!(r.failures.iter().any(|f| f == "missing hashtag #openclaw"));
+        assert!(r.failures.iter().any(|f| f == "contains banned phrase \"Game-Changer\""));
+        assert!(report(&[r]).contains("0/1 passed"));
+    }
+
+    #[tokio::test]
+    async fn provider_error_and_empty_reply_fail_the_case() {
+        let agent = load_suite().unwrap().agent.profile();
+        let c = case("post", "", Expect::default());
+
+        let r = run_case(&MockProvider::failing("timeout"), render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, &c).await;
+        assert_eq!(r.failures, vec!["LLM error: timeout".to_string()]);
+
+        let r = run_case(&MockProvider::replying("  \"\"  "), render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, &c).await;
+        assert_eq!(r.failures, vec!["LLM error: empty response".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn invalid_repo_url_is_a_prompt_failure() {
+        let agent = load_suite().unwrap().agent.profile();
+        let mut c = case("comment", "hi", Expect::default());
+        c.repo_url = Some("not a url".to_string());
+
+        let provider = MockProvider::replying("hi");
+        let r = run_case(&provider, render_builtin, &agent, drafts::DEFAULT_STYLE_GUIDE, &c).await;
+
+        assert!(r.failures[0].starts_with("prompt:") && r.failures[0].contains("repo_url"), "{:?}", r.failures);
+        assert!(provider.requests().is_empty());
+    }
+}
@@ -0,0 +1,596 @@
+// -------------------------
+// ✅ Content guard
+// Runs before anything is published (and when an approval is created):
+// banned words, link allowlist, hashtag limit, duplicates of past posts,
+// and an optional LLM tone check. Result: pass / warn / block.
+// Block stops the publish; warn is shown but does not.
+// -------------------------
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::llm::{JsonSchema, LlmRequest};
+use crate::{
+    ensure_approvals_table, ensure_column, ensure_user_settings_table, open_db, structured, write_log, RoutedProvider,
+};
+
+// Jaccard similarity of word sets
+const DUPLICATE_BLOCK_SIMILARITY: f64 = 0.9;
+const DUPLICATE_WARN_SIMILARITY: f64 = 0.7;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Level {
+    Pass,
+    Warn,
+    Block,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Pass => "pass",
+            Level::Warn => "warn",
+            Level::Block => "block",
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Level::Pass => "✅",
+            Level::Warn => "⚠️",
+            Level::Block => "⛔",
+        }
+    }
+}
+
+pub struct Finding {
+    pub check: &'static str,
+    pub level: Level,
+    pub message: String,
+}
+
+pub struct Verdict {
+    pub findings: Vec<Finding>,
+}
+
+impl Verdict {
+    pub fn level(&self) -> Level {
+        self.findings
+            .iter()
+            .map(|f| f.level)
+            .fold(Level::Pass, |a, b| if b > a { b } else { a })
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.level() == Level::Block
+    }
+
+    // one line per finding; "" when everything passed
+    pub fn report(&self) -> String {
+        self.findings
+            .iter()
+            .map(|f| format!("{} {}: {}", f.level.icon(), f.check, f.message))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn summary(&self) -> String {
+        let level = self.level();
+        if self.findings.is_empty() {
+            return format!("{} pass", level.icon());
+        }
+        format!("{} {}\n{}", level.icon(), level.as_str(), self.report())
+    }
+
+    fn push(&mut self, check: &'static str, level: Level, message: String) {
+        self.findings.push(Finding { check, level, message });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GuardConfig {
+    // whole-word / phrase, case-insensitive => block
+    pub banned_words: Vec<String>,
+    // links must point to one of these domains (or a subdomain); empty = any
+    pub allowed_domains: Vec<String>,
+    // more hashtags => warn
+    pub max_hashtags: usize,
+    // compare against posts published in the last N days
+    pub duplicate_days: i64,
+    // ask the LLM to classify tone before publishing
+    pub tone_check: bool,
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        GuardConfig {
+            banned_words: vec![],
+            allowed_domains: vec!["github.com".to_string(), "linkedin.com".to_string()],
+            max_hashtags: 5,
+            duplicate_days: 30,
+            tone_check: false,
+        }
+    }
+}
+
+fn ensure_guard_columns(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "guard_config TEXT NULL");
+}
+
+pub fn load_config() -> GuardConfig {
+    open_db()
+        .ok()
+        .and_then(|conn| {
+            ensure_guard_columns(&conn);
+            conn.query_row("SELECT guard_config FROM user_settings WHERE id=1", [], |r| {
+                r.get::<_, Option<String>>(0)
+            })
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+        })
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_config(cfg: &GuardConfig) -> Result<(), String> {
+    let conn = open_db()?;
+    ensure_guard_columns(&conn);
+    let json = serde_json::to_string(cfg).map_err(|e| format!("Serialize failed: {}", e))?;
+    conn.execute(
+        "UPDATE user_settings SET guard_config=?1, updated_at=datetime('now') WHERE id=1",
+        params![json],
+    )
+    .map_err(|e| format!("DB update failed: {}", e))?;
+    Ok(())
+}
+
+fn link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"https?://[^\s)\]]+").expect("valid link regex"))
+}
+
+fn hashtag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|\s)#[\p{L}\p{N}_]+").expect("valid hashtag regex"))
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(|w| w.to_string())
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let inter = a.intersection(b).count() as f64;
+    let union = a.union(b).count() as f64;
+    inter / union
+}
+
+fn check_banned_words(cfg: &GuardConfig, text: &str, v: &mut Verdict) {
+    for word in cfg.banned_words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+        let pattern = format!(r"(?i)\b{}\b", regex::escape(word));
+        if Regex::new(&pattern).map(|re| re.is_match(text)).unwrap_or(false) {
+            v.push("banned_words", Level::Block, format!("contains \"{}\"", word));
+        }
+    }
+}
+
+fn check_links(cfg: &GuardConfig, text: &str, v: &mut Verdict) {
+    for m in link_regex().find_iter(text) {
+        let link = m.as_str().trim_end_matches(['.', ',', '!', '?']);
+
+        // e.g. https://github.com/<YOUR_USERNAME>/<YOUR_REPO>
+        if link.contains('<') || link.contains('>') {
+            v.push("links", Level::Block, format!("placeholder link {}", link));
+            continue;
+        }
+
+        if cfg.allowed_domains.is_empty() {
+            continue;
+        }
+        let host = link
+            .split("://")
+            .nth(1)
+            .unwrap_or("")
+            .split(['/', '?', '#', ':'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        let allowed = cfg.allowed_domains.iter().any(|d| {
+            let d = d.trim().to_lowercase();
+            host == d || host.ends_with(&format!(".{}", d))
+        });
+        if !allowed {
+            v.push("links", Level::Block, format!("{} is not on the link allowlist", host));
+        }
+    }
+}
+
+// "#OpenClaw" => "#openclaw"
+pub fn hashtags(text: &str) -> Vec<String> {
+    hashtag_regex()
+        .find_iter(text)
+        .map(|m| m.as_str().trim().to_lowercase())
+        .collect()
+}
+
+fn check_hashtags(cfg: &GuardConfig, text: &str, v: &mut Verdict) {
+    let count = hashtags(text).len();
+    if count > cfg.max_hashtags {
+        v.push(
+            "hashtags",
+            Level::Warn,
+            format!("{} hashtags (max {})", count, cfg.max_hashtags),
+        );
+    }
+}
+
+// Everything that was actually posted: approvals and the direct publish paths
+// (linkedin_post, the demo2 comments). Keyed by the approval id when there is one.
+pub fn ensure_published_table(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS published_posts (
+            id TEXT PRIMARY KEY,
+            agent_id TEXT NULL,
+            kind TEXT NOT NULL,
+            text TEXT NOT NULL,
+            published_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    // approvals published before this table existed
+    ensure_approvals_table(conn);
+    let _ = conn.execute(
+        "INSERT OR IGNORE INTO published_posts (id, agent_id, kind, text, published_at)
+         SELECT id, agent_id, kind, draft_text, COALESCE(decided_at, created_at)
+         FROM approvals WHERE status='approved'",
+        [],
+    );
+}
+
+// Call after a real (not dry-run) publish; approval_id = the approval it came from
+pub fn record_published(agent_id: Option<&str>, kind: &str, text: &str, approval_id: Option<&str>) {
+    let id = approval_id
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let res = open_db().and_then(|conn| {
+        ensure_published_table(&conn);
+        conn.execute(
+            "INSERT OR IGNORE INTO published_posts (id, agent_id, kind, text, published_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![id, agent_id, kind, text],
+        )
+        .map_err(|e| e.to_string())
+    });
+    if let Err(e) = res {
+        write_log("WARN", &format!("Could not record published {}: {}", kind, e));
+    }
+}
+
+// Published in the last duplicate_days, (id, text). `exclude` is the approval being checked.
+fn published_texts(cfg: &GuardConfig, exclude: Option<&str>) -> Vec<(String, String)> {
+    let Ok(conn) = open_db() else { return vec![] };
+    ensure_published_table(&conn);
+
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT id, text FROM published_posts
+         WHERE published_at >= datetime('now', ?1) AND id != ?2",
+    ) else {
+        return vec![];
+    };
+
+    stmt.query_map(
+        params![format!("-{} days", cfg.duplicate_days.max(1)), exclude.unwrap_or("")],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .map(|rows| rows.flatten().collect())
+    .unwrap_or_default()
+}
+
+fn check_duplicates(text: &str, past: &[(String, String)], v: &mut Verdict) {
+    let mine = words(text);
+    let best = past
+        .iter()
+        .map(|(id, t)| {
+            let s = if t.trim() == text.trim() { 1.0 } else { similarity(&mine, &words(t)) };
+            (id, s)
+        })
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    if let Some((id, s)) = best {
+        let pct = (s * 100.0).round();
+        if s >= DUPLICATE_BLOCK_SIMILARITY {
+            v.push("duplicates", Level::Block, format!("{}% similar to published {}", pct, id));
+        } else if s >= DUPLICATE_WARN_SIMILARITY {
+            v.push("duplicates", Level::Warn, format!("{}% similar to published {}", pct, id));
+        }
+    }
+}
+
+fn apply_rules(cfg: &GuardConfig, text: &str, past: &[(String, String)]) -> Verdict {
+    let mut v = Verdict { findings: vec![] };
+
+    if text.trim().is_empty() {
+        v.push("content", Level::Block, "text is empty".to_string());
+        return v;
+    }
+
+    check_banned_words(cfg, text, &mut v);
+    check_links(cfg, text, &mut v);
+    check_hashtags(cfg, text, &mut v);
+    check_duplicates(text, past, &mut v);
+    v
+}
+
+// Fast, offline checks (no LLM). Used when an approval is created.
+pub fn check_rules(text: &str, exclude: Option<&str>) -> Verdict {
+    let cfg = load_config();
+    let past = if text.trim().is_empty() { vec![] } else { published_texts(&cfg, exclude) };
+    apply_rules(&cfg, text, &past)
+}
+
+async fn check_tone(agent_id: Option<&str>, text: &str, v: &mut Verdict) {
+    let schema = JsonSchema::new(
+        "tone_review",
+        json!({
+            "type": "object",
+            "properties": {
+                "tone": {
+                    "type": "string",
+                    "enum": ["professional", "friendly", "promotional", "aggressive", "offensive"]
+                },
+                "reason": { "type": "string" }
+            },
+            "required": ["tone", "reason"]
+        }),
+    );
+
+    let req = LlmRequest {
+        system: Some(
+            "You review LinkedIn drafts for brand safety. Classify the tone of the draft.".to_string(),
+        ),
+        ..LlmRequest::prompt(&format!("Draft:\n{}", text))
+    };
+
+    match structured::generate_structured(&RoutedProvider::new(agent_id), &req, &schema, 1).await {
+        Ok((_, out)) => {
+            let tone = out["tone"].as_str().unwrap_or("");
+            let reason = out["reason"].as_str().unwrap_or("").trim().to_string();
+            match tone {
+                "offensive" => v.push("tone", Level::Block, format!("offensive: {}", reason)),
+                "aggressive" => v.push("tone", Level::Warn, format!("aggressive: {}", reason)),
+                _ => {}
+            }
+        }
+        Err(e) => v.push("tone", Level::Warn, format!("tone check unavailable ({})", e)),
+    }
+}
+
+// Full pipeline, run right before publishing
+pub async fn check_before_publish(agent_id: Option<&str>, text: &str, exclude: Option<&str>) -> Verdict {
+    let mut v = check_rules(text, exclude);
+    if load_config().tone_check && !v.is_blocked() {
+        check_tone(agent_id, text, &mut v).await;
+    }
+    v
+}
+
+pub fn store_verdict(conn: &Connection, approval_id: &str, v: &Verdict) {
+    let _ = conn.execute(
+        "UPDATE approvals SET guard_verdict=?1, guard_report=?2 WHERE id=?3",
+        params![v.level().as_str(), v.report(), approval_id],
+    );
+}
+
+// Logs and turns a block into the error returned to the UI
+pub fn enforce(v: &Verdict, what: &str) -> Result<(), String> {
+    match v.level() {
+        Level::Pass => Ok(()),
+        Level::Warn => {
+            write_log("WARN", &format!("Content guard warnings for {}:\n{}", what, v.report()));
+            Ok(())
+        }
+        Level::Block => {
+            write_log("WARN", &format!("Content guard blocked {}:\n{}", what, v.report()));
+            Err(format!("⛔ Content guard blocked {}:\n{}", what, v.report()))
+        }
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn show_guard_settings() -> Result<String, String> {
+        let cfg = load_config();
+        let list = |v: &[String]| if v.is_empty() { "(none)".to_string() } else { v.join(", ") };
+        Ok(format!(
+            "🛡️ Content guard:\nbanned_words: {}\nallowed_domains: {}\nmax_hashtags: {}\nduplicate_days: {}\ntone_check: {}",
+            list(&cfg.banned_words),
+            list(&cfg.allowed_domains),
+            cfg.max_hashtags,
+            cfg.duplicate_days,
+            if cfg.tone_check { "on" } else { "off" }
+        ))
+    }
+}
+
+// key: banned_words | allowed_domains (comma lists) | max_hashtags | duplicate_days | tone_check (on/off)
+redacted! {
+    #[tauri::command]
+    pub fn set_guard_setting(key: String, value: String) -> Result<String, String> {
+        let mut cfg = load_config();
+        let value = value.trim();
+        let list = |v: &str| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("none"))
+                .collect::<Vec<_>>()
+        };
+
+        match key.trim().to_lowercase().as_str() {
+            "banned_words" => cfg.banned_words = list(value),
+            "allowed_domains" => cfg.allowed_domains = list(value).into_iter().map(|d| d.to_lowercase()).collect(),
+            "max_hashtags" => {
+                cfg.max_hashtags = value
+                    .parse()
+                    .map_err(|_| "❌ max_hashtags must be a whole number.".to_string())?
+            }
+            "duplicate_days" => {
+                cfg.duplicate_days = value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|d| *d > 0)
+                    .ok_or_else(|| "❌ duplicate_days must be a positive number.".to_string())?
+            }
+            "tone_check" => cfg.tone_check = matches!(value.to_lowercase().as_str(), "on" | "true" | "yes" | "1"),
+            other => {
+                return Err(format!(
+                    "❌ Unknown guard setting '{}'. Use: banned_words, allowed_domains, max_hashtags, duplicate_days, tone_check",
+                    other
+                ))
+            }
+        }
+
+        save_config(&cfg)?;
+        write_log("INFO", &format!("Content guard setting changed: {}", key.trim()));
+        show_guard_settings()
+    }
+}
+
+// Preview what the guard would say about a text (includes the tone check if enabled)
+redacted! {
+    #[tauri::command]
+    pub async fn check_content(text: String) -> Result<String, String> {
+        let v = check_before_publish(None, &text, None).await;
+        Ok(format!("🛡️ Content guard: {}", v.summary()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> GuardConfig {
+        GuardConfig {
+            banned_words: vec!["crypto".to_string(), "get rich".to_string()],
+            ..GuardConfig::default()
+        }
+    }
+
+    fn checks(v: &Verdict) -> Vec<(&'static str, Level)> {
+        v.findings.iter().map(|f| (f.check, f.level)).collect()
+    }
+
+    #[test]
+    fn banned_words_match_whole_words_only() {
+        let v = apply_rules(&cfg(), "Thoughts on CRYPTO today", &[]);
+        assert_eq!(checks(&v), vec![("banned_words", Level::Block)]);
+        assert!(apply_rules(&cfg(), "How to GET RICH quickly", &[]).is_blocked());
+
+        // cryptography / cryptocurrency are not "crypto"
+        assert!(apply_rules(&cfg(), "Cryptography and cryptocurrency basics", &[]).findings.is_empty());
+        assert!(apply_rules(&cfg(), "   ", &[]).is_blocked());
+    }
+
+    #[test]
+    fn links_must_be_on_the_allowlist() {
+        let c = cfg();
+        assert!(apply_rules(&c, "Repo: https://github.com/me/app.", &[]).findings.is_empty());
+        assert!(apply_rules(&c, "See https://gist.github.com/me/1", &[]).findings.is_empty());
+        assert!(apply_rules(&c, "See https://www.LinkedIn.com/in/me?x=1", &[]).findings.is_empty());
+
+        let v = apply_rules(&c, "Try https://evilgithub.com/x and https://github.com.evil.io/y", &[]);
+        assert_eq!(checks(&v), vec![("links", Level::Block), ("links", Level::Block)]);
+        assert!(v.report().contains("evilgithub.com is not on the link allowlist"));
+
+        let open = GuardConfig {
+            allowed_domains: vec![],
+            ..cfg()
+        };
+        assert!(apply_rules(&open, "https://example.org", &[]).findings.is_empty());
+    }
+
+    #[test]
+    fn placeholder_links_are_blocked_even_when_allowed() {
+        let v = apply_rules(&cfg(), "Repo: https://github.com/<YOUR_USERNAME>/<YOUR_REPO>", &[]);
+        assert!(v.is_blocked());
+        assert!(v.report().contains("placeholder link"));
+    }
+
+    #[test]
+    fn too_many_hashtags_warn() {
+        let c = GuardConfig {
+            max_hashtags: 2,
+            ..cfg()
+        };
+        assert_eq!(hashtags("#OpenClaw and #AI, not a#b"), vec!["#openclaw", "#ai"]);
+        assert!(apply_rules(&c, "#a #b", &[]).findings.is_empty());
+        let v = apply_rules(&c, "#a #b #c", &[]);
+        assert_eq!(checks(&v), vec![("hashtags", Level::Warn)]);
+        assert!(!v.is_blocked());
+    }
+
+    #[test]
+    fn duplicate_thresholds() {
+        let past = vec![(
+            "ap-old".to_string(),
+            "shipping desktop assistant built with tauri and openclaw today friends".to_string(),
+        )];
+        let c = cfg();
+
+        // identical (after trimming) => block
+        let v = apply_rules(&c, &format!("  {}  ", past[0].1), &past);
+        assert_eq!(checks(&v), vec![("duplicates", Level::Block)]);
+        assert!(v.report().contains("100% similar to published ap-old"));
+
+        // 8 of 10 words shared => warn
+        let v = apply_rules(&c, "shipping desktop assistant built with tauri and openclaw", &past);
+        assert_eq!(checks(&v), vec![("duplicates", Level::Warn)]);
+
+        // unrelated => pass
+        assert!(apply_rules(&c, "weekend hiking photos from the mountains", &past).findings.is_empty());
+        assert!(apply_rules(&c, "anything", &[]).findings.is_empty());
+    }
+
+    #[test]
+    fn every_publish_path_counts_as_published() {
+        let text = "shipping desktop assistant built with tauri and openclaw today friends";
+        assert!(check_rules(text, None).findings.is_empty());
+
+        // direct publish (no approval)
+        record_published(Some("a1"), "linkedin_comment", text, None);
+        let v = check_rules(text, None);
+        assert_eq!(checks(&v), vec![("duplicates", Level::Block)]);
+
+        // approvals published before the table existed are picked up too
+        let conn = open_db().unwrap();
+        ensure_approvals_table(&conn);
+        conn.execute(
+            "INSERT INTO approvals (id, agent_id, kind, draft_text, status, created_at, decided_at)
+             VALUES ('ap-old', 'a1', 'linkedin_post', 'weekend hiking photos from the mountains', 'approved',
+                     datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        let v = check_rules("weekend hiking photos from the mountains", None);
+        assert!(v.report().contains("published ap-old"), "{}", v.report());
+        // ...but not against itself
+        assert!(check_rules("weekend hiking photos from the mountains", Some("ap-old")).findings.is_empty());
+    }
+}
@@ -0,0 +1,1191 @@
+// ------------------------
+// ✅ LLM calls (Local + External)
+// Every provider implements `LlmProvider` so the router, usage accounting
+// and tests can treat them the same way.
+// ------------------------
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use crate::{redact, write_log};
+
+#[derive(Clone, Debug)]
+pub struct ChatMessage {
+    pub role: String, // "user" | "assistant" | "tool"
+    pub content: String,
+    // assistant turn: the tools the model asked for
+    pub tool_calls: Vec<ToolCall>,
+    // "tool" turn: the call this result answers
+    pub tool_call: Option<ToolCall>,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: &str) -> Self {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: vec![],
+            tool_call: None,
+        }
+    }
+
+    pub fn user(content: &str) -> Self {
+        ChatMessage::new("user", content)
+    }
+
+    pub fn assistant(content: &str) -> Self {
+        ChatMessage::new("assistant", content)
+    }
+
+    pub fn assistant_tool_calls(content: &str, calls: Vec<ToolCall>) -> Self {
+        ChatMessage {
+            tool_calls: calls,
+            ..ChatMessage::assistant(content)
+        }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: &str) -> Self {
+        ChatMessage {
+            tool_call: Some(call.clone()),
+            ..ChatMessage::new("tool", content)
+        }
+    }
+}
+
+// A function the model may call; `parameters` is a JSON schema (type=object)
+#[derive(Clone, Debug)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Clone, Debug)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+// Asks the provider for JSON matching `schema` (see structured.rs)
+#[derive(Clone, Debug)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+impl JsonSchema {
+    pub fn new(name: &str, schema: serde_json::Value) -> Self {
+        JsonSchema {
+            name: name.to_string(),
+            schema,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LlmRequest {
+    pub system: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub max_tokens: u32,
+    pub response_schema: Option<JsonSchema>,
+    pub tools: Vec<ToolSpec>,
+    // skip the response cache for this call (see cache.rs)
+    pub bypass_cache: bool,
+    // aborts the provider call when cancelled (see http.rs / cancel_llm)
+    pub cancel: Option<CancellationToken>,
+}
+
+impl LlmRequest {
+    // Single user message, no system prompt (the old llm_reply behavior)
+    pub fn prompt(prompt: &str) -> Self {
+        LlmRequest {
+            system: None,
+            messages: vec![ChatMessage::user(prompt)],
+            max_tokens: 800,
+            response_schema: None,
+            tools: vec![],
+            bypass_cache: false,
+            cancel: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Usage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LlmResponse {
+    pub text: String,
+    pub model: String,
+    pub usage: Option<Usage>,
+    // empty unless the request offered tools
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    // provider id used for routing, pricing and usage rows ("gemini", "openai", "anthropic", "local")
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+    async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String>;
+
+    // what produced the last reply, for logs and approvals
+    fn label(&self) -> String {
+        self.name().to_string()
+    }
+}
+
+// Build the client for a routed provider. key = None => local Ollama.
+pub fn provider_for(provider: &str, key: Option<String>) -> Result<Box<dyn LlmProvider>, String> {
+    match (provider, key) {
+        ("gemini", Some(key)) => Ok(Box::new(GeminiProvider::new(key))),
+        ("openai", Some(key)) => Ok(Box::new(OpenAiProvider::new(key))),
+        ("anthropic", Some(key)) => Ok(Box::new(AnthropicProvider::new(key))),
+        ("local", _) => {
+            let local = crate::ollama::local_settings();
+            Ok(Box::new(OllamaProvider::new(local.base_url, local.model)))
+        }
+        (other, _) => Err(format!(
+            "Unknown provider '{}'. Use: gemini | openai | anthropic (claude).",
+            other
+        )),
+    }
+}
+
+// ===== Gemini =====
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct GeminiContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_response: Option<serde_json::Value>,
+}
+
+impl GeminiPart {
+    fn text(text: &str) -> Self {
+        GeminiPart {
+            text: Some(text.to_string()),
+            function_call: None,
+            function_response: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    candidates: Option<Vec<GeminiCandidate>>,
+    error: Option<GeminiError>,
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Deserialize)]
+struct GeminiCandidate {
+    content: GeminiCandidateContent,
+}
+
+#[derive(Deserialize)]
+struct GeminiCandidateContent {
+    parts: Vec<GeminiCandidatePart>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidatePart {
+    text: Option<String>,
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Deserialize)]
+struct GeminiFunctionCall {
+    name: String,
+    args: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GeminiError {
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    prompt_token_count: Option<i64>,
+    candidates_token_count: Option<i64>,
+}
+
+impl From<GeminiUsage> for Usage {
+    fn from(u: GeminiUsage) -> Self {
+        Usage {
+            input_tokens: u.prompt_token_count.unwrap_or(0),
+            output_tokens: u.candidates_token_count.unwrap_or(0),
+        }
+    }
+}
+
+pub struct GeminiProvider {
+    key: String,
+    model: String,
+}
+
+impl GeminiProvider {
+    pub fn new(key: String) -> Self {
+        // You can change model later if needed
+        GeminiProvider {
+            key,
+            model: "gemini-1.5-flash".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String> {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            self.model, self.key
+        );
+
+        let body = GeminiRequest {
+            contents: gemini_contents(&req.messages),
+            system_instruction: req.system.as_ref().map(|s| GeminiContent {
+                role: None,
+                parts: vec![GeminiPart::text(s)],
+            }),
+            generation_config: req.response_schema.as_ref().map(|s| {
+                json!({
+                    "responseMimeType": "application/json",
+                    "responseSchema": gemini_schema(&s.schema)
+                })
+            }),
+            tools: (!req.tools.is_empty()).then(|| {
+                let declarations: Vec<serde_json::Value> = req
+                    .tools
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "name": t.name,
+                            "description": t.description,
+                            "parameters": gemini_schema(&t.parameters)
+                        })
+                    })
+                    .collect();
+                json!([{ "functionDeclarations": declarations }])
+            }),
+        };
+
+        let client = crate::http::client();
+
+        let resp = client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Gemini request failed: {}", e))?;
+
+        let status = resp.status();
+        let text_body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed reading Gemini response: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "Gemini HTTP {}: {}",
+                status.as_u16(),
+                redact::redact(&text_body)
+            ));
+        }
+
+        let parsed: GeminiResponse = serde_json::from_str(&text_body).map_err(|e| {
+            format!(
+                "Failed parsing Gemini JSON: {} | body={}",
+                e,
+                redact::redact(&text_body)
+            )
+        })?;
+
+        if let Some(err) = parsed.error {
+            return Err(format!(
+                "Gemini error: {}",
+                err.message.unwrap_or("Unknown error".to_string())
+            ));
+        }
+
+        let usage = parsed.usage_metadata.map(Usage::from);
+
+        let parts = parsed
+            .candidates
+            .and_then(|mut c| c.pop())
+            .map(|c| c.content.parts)
+            .unwrap_or_default();
+
+        let mut texts = vec![];
+        let mut tool_calls = vec![];
+        for p in parts {
+            if let Some(t) = p.text {
+                texts.push(t);
+            }
+            // Gemini has no call ids; results are matched by name
+            if let Some(fc) = p.function_call {
+                tool_calls.push(ToolCall {
+                    id: format!("call_{}", tool_calls.len()),
+                    name: fc.name,
+                    arguments: fc.args.unwrap_or_else(|| json!({})),
+                });
+            }
+        }
+
+        let answer = if texts.is_empty() && tool_calls.is_empty() {
+            "(No response from Gemini)".to_string()
+        } else {
+            texts.join("")
+        };
+
+        Ok(LlmResponse {
+            text: answer,
+            model: self.model.clone(),
+            usage,
+            tool_calls,
+        })
+    }
+}
+
+// Gemini calls the assistant "model"; tool results go back as functionResponse
+// parts, and consecutive results share one turn.
+fn gemini_contents(messages: &[ChatMessage]) -> Vec<GeminiContent> {
+    let mut out: Vec<GeminiContent> = vec![];
+    let mut prev_tool = false;
+
+    for m in messages {
+        let (role, parts) = match m.role.as_str() {
+            "assistant" => {
+                let mut parts = vec![];
+                if !m.content.is_empty() || m.tool_calls.is_empty() {
+                    parts.push(GeminiPart::text(&m.content));
+                }
+                for c in &m.tool_calls {
+                    parts.push(GeminiPart {
+                        text: None,
+                        function_call: Some(json!({ "name": c.name, "args": c.arguments })),
+                        function_response: None,
+                    });
+                }
+                ("model", parts)
+            }
+            "tool" => {
+                let name = m.tool_call.as_ref().map(|c| c.name.clone()).unwrap_or_default();
+                let part = GeminiPart {
+                    text: None,
+                    function_call: None,
+                    function_response: Some(json!({ "name": name, "response": { "content": m.content } })),
+                };
+                if prev_tool {
+                    if let Some(last) = out.last_mut() {
+                        last.parts.push(part);
+                        continue;
+                    }
+                }
+                prev_tool = true;
+                ("user", vec![part])
+            }
+            _ => ("user", vec![GeminiPart::text(&m.content)]),
+        };
+
+        if m.role != "tool" {
+            prev_tool = false;
+        }
+        out.push(GeminiContent {
+            role: Some(role.to_string()),
+            parts,
+        });
+    }
+
+    out
+}
+
+// Gemini's responseSchema is an OpenAPI subset: drop keywords it rejects
+// (additionalProperties, $schema, ...). Validation still runs on our side.
+fn gemini_schema(schema: &serde_json::Value) -> serde_json::Value {
+    const KEEP: [&str; 12] = [
+        "type", "format", "description", "nullable", "enum", "properties", "required", "items",
+        "minItems", "maxItems", "minimum", "maximum",
+    ];
+
+    match schema {
+        serde_json::Value::Object(map) => {
+            let mut out = serde_json::Map::new();
+            for (k, v) in map {
+                if !KEEP.contains(&k.as_str()) {
+                    continue;
+                }
+                let v = match k.as_str() {
+                    "properties" => match v {
+                        serde_json::Value::Object(props) => serde_json::Value::Object(
+                            props.iter().map(|(name, p)| (name.clone(), gemini_schema(p))).collect(),
+                        ),
+                        other => other.clone(),
+                    },
+                    "items" => gemini_schema(v),
+                    _ => v.clone(),
+                };
+                out.insert(k.clone(), v);
+            }
+            serde_json::Value::Object(out)
+        }
+        other => other.clone(),
+    }
+}
+
+// ===== OpenAI (Chat Completions API) =====
+#[derive(Serialize)]
+struct OpenAIChatRequest {
+    model: String,
+    messages: Vec<OpenAIChatMessage>,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Serialize)]
+struct OpenAIChatMessage {
+    role: String,
+    // null for an assistant turn that only calls tools
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl OpenAIChatMessage {
+    fn from_message(m: &ChatMessage) -> Self {
+        OpenAIChatMessage {
+            role: m.role.clone(),
+            content: if m.content.is_empty() && !m.tool_calls.is_empty() {
+                None
+            } else {
+                Some(m.content.clone())
+            },
+            tool_calls: m
+                .tool_calls
+                .iter()
+                .map(|c| {
+                    json!({
+                        "id": c.id,
+                        "type": "function",
+                        "function": { "name": c.name, "arguments": c.arguments.to_string() }
+                    })
+                })
+                .collect(),
+            tool_call_id: m.tool_call.as_ref().map(|c| c.id.clone()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAIChatResponse {
+    choices: Vec<OpenAIChatChoice>,
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIChatChoice {
+    message: OpenAIChatChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct OpenAIChatChoiceMessage {
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIToolCall {
+    id: String,
+    function: OpenAIFunctionCall,
+}
+
+#[derive(Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    // JSON encoded as a string
+    arguments: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAIUsage {
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+}
+
+impl From<OpenAIUsage> for Usage {
+    fn from(u: OpenAIUsage) -> Self {
+        Usage {
+            input_tokens: u.prompt_tokens.unwrap_or(0),
+            output_tokens: u.completion_tokens.unwrap_or(0),
+        }
+    }
+}
+
+pub struct OpenAiProvider {
+    key: String,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(key: String) -> Self {
+        OpenAiProvider {
+            key,
+            model: "gpt-4o-mini".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String> {
+        let url = "https://api.openai.com/v1/chat/completions";
+
+        let mut messages = vec![];
+        if let Some(system) = &req.system {
+            messages.push(OpenAIChatMessage::from_message(&ChatMessage::new("system", system)));
+        }
+        messages.extend(req.messages.iter().map(OpenAIChatMessage::from_message));
+
+        let body = OpenAIChatRequest {
+            model: self.model.clone(),
+            messages,
+            max_tokens: req.max_tokens,
+            // strict mode would require additionalProperties=false everywhere
+            response_format: req.response_schema.as_ref().map(|s| {
+                json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": s.name, "schema": s.schema, "strict": false }
+                })
+            }),
+            tools: (!req.tools.is_empty()).then(|| req.tools.iter().map(openai_style_tool).collect()),
+        };
+
+        let client = crate::http::client();
+        let resp = client
+            .post(url)
+            .bearer_auth(&self.key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("OpenAI request failed: {}", e))?;
+
+        let status = resp.status();
+        let text_body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed reading OpenAI response: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "OpenAI HTTP {}: {}",
+                status.as_u16(),
+                redact::redact(&text_body)
+            ));
+        }
+
+        let parsed: OpenAIChatResponse = serde_json::from_str(&text_body).map_err(|e| {
+            format!(
+                "Failed parsing OpenAI JSON: {} | body={}",
+                e,
+                redact::redact(&text_body)
+            )
+        })?;
+
+        let usage = parsed.usage.map(Usage::from);
+
+        let message = parsed.choices.into_iter().next().map(|c| c.message);
+
+        let tool_calls: Vec<ToolCall> = message
+            .as_ref()
+            .and_then(|m| m.tool_calls.as_ref())
+            .map(|calls| {
+                calls
+                    .iter()
+                    .map(|c| ToolCall {
+                        id: c.id.clone(),
+                        name: c.function.name.clone(),
+                        // bad JSON is passed on as a string; argument validation reports it
+                        arguments: serde_json::from_str(&c.function.arguments)
+                            .unwrap_or_else(|_| serde_json::Value::String(c.function.arguments.clone())),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let ans = message.and_then(|m| m.content).unwrap_or_else(|| {
+            if tool_calls.is_empty() {
+                "(No response from OpenAI)".to_string()
+            } else {
+                String::new()
+            }
+        });
+
+        Ok(LlmResponse {
+            text: ans,
+            model: self.model.clone(),
+            usage,
+            tool_calls,
+        })
+    }
+}
+
+// OpenAI and Ollama share the {"type":"function","function":{...}} tool shape
+fn openai_style_tool(t: &ToolSpec) -> serde_json::Value {
+    json!({
+        "type": "function",
+        "function": { "name": t.name, "description": t.description, "parameters": t.parameters }
+    })
+}
+
+// ===== Anthropic (Claude Messages API) =====
+#[derive(Serialize)]
+struct AnthropicRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct AnthropicMessage {
+    role: String,
+    // plain string, or content blocks for tool_use / tool_result
+    content: serde_json::Value,
+}
+
+// Tool results are user turns with tool_result blocks; consecutive ones share a turn.
+fn anthropic_messages(messages: &[ChatMessage]) -> Vec<AnthropicMessage> {
+    let mut out: Vec<AnthropicMessage> = vec![];
+
+    for m in messages {
+        match m.role.as_str() {
+            "assistant" if !m.tool_calls.is_empty() => {
+                let mut blocks = vec![];
+                if !m.content.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": m.content }));
+                }
+                for c in &m.tool_calls {
+                    blocks.push(json!({ "type": "tool_use", "id": c.id, "name": c.name, "input": c.arguments }));
+                }
+                out.push(AnthropicMessage {
+                    role: "assistant".to_string(),
+                    content: serde_json::Value::Array(blocks),
+                });
+            }
+            "tool" => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": m.tool_call.as_ref().map(|c| c.id.clone()).unwrap_or_default(),
+                    "content": m.content
+                });
+                match out.last_mut() {
+                    Some(last) if last.role == "user" && last.content.is_array() => {
+                        if let Some(blocks) = last.content.as_array_mut() {
+                            blocks.push(block);
+                        }
+                    }
+                    _ => out.push(AnthropicMessage {
+                        role: "user".to_string(),
+                        content: json!([block]),
+                    }),
+                }
+            }
+            _ => out.push(AnthropicMessage {
+                role: m.role.clone(),
+                content: serde_json::Value::String(m.content.clone()),
+            }),
+        }
+    }
+
+    out
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContentBlock>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    text: Option<String>,
+    id: Option<String>,
+    name: Option<String>,
+    input: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicUsage {
+    input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+}
+
+impl From<AnthropicUsage> for Usage {
+    fn from(u: AnthropicUsage) -> Self {
+        Usage {
+            input_tokens: u.input_tokens.unwrap_or(0),
+            output_tokens: u.output_tokens.unwrap_or(0),
+        }
+    }
+}
+
+pub struct AnthropicProvider {
+    key: String,
+    model: String,
+}
+
+impl AnthropicProvider {
+    pub fn new(key: String) -> Self {
+        AnthropicProvider {
+            key,
+            model: "claude-3-5-sonnet-20240620".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String> {
+        let url = "https://api.anthropic.com/v1/messages";
+
+        // JSON mode = one forced tool whose input is the answer.
+        // Tool input must be an object, so other roots are wrapped in {"value": ...}.
+        let structured = req.response_schema.as_ref().map(|s| {
+            let wrapped = s.schema["type"] != "object";
+            let input_schema = if wrapped {
+                json!({ "type": "object", "properties": { "value": s.schema }, "required": ["value"] })
+            } else {
+                s.schema.clone()
+            };
+            (s.name.clone(), input_schema, wrapped)
+        });
+
+        let body = AnthropicRequest {
+            model: self.model.clone(),
+            max_tokens: req.max_tokens,
+            system: req.system.clone(),
+            messages: anthropic_messages(&req.messages),
+            // JSON mode wins over regular tools (tool_choice forces its tool anyway)
+            tools: match &structured {
+                Some((name, input_schema, _)) => Some(json!([{
+                    "name": name,
+                    "description": "Return the answer as structured data.",
+                    "input_schema": input_schema
+                }])),
+                None if !req.tools.is_empty() => Some(serde_json::Value::Array(
+                    req.tools
+                        .iter()
+                        .map(|t| json!({ "name": t.name, "description": t.description, "input_schema": t.parameters }))
+                        .collect(),
+                )),
+                None => None,
+            },
+            tool_choice: structured
+                .as_ref()
+                .map(|(name, _, _)| json!({ "type": "tool", "name": name })),
+        };
+
+        let client = crate::http::client();
+        let resp = client
+            .post(url)
+            .header("x-api-key", &self.key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Anthropic request failed: {}", e))?;
+
+        let status = resp.status();
+        let text_body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed reading Anthropic response: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "Anthropic HTTP {}: {}",
+                status.as_u16(),
+                redact::redact(&text_body)
+            ));
+        }
+
+        let parsed: AnthropicResponse = serde_json::from_str(&text_body).map_err(|e| {
+            format!(
+                "Failed parsing Anthropic JSON: {} | body={}",
+                e,
+                redact::redact(&text_body)
+            )
+        })?;
+
+        let usage = parsed.usage.map(Usage::from);
+
+        if let Some((_, _, wrapped)) = &structured {
+            let ans = parsed
+                .content
+                .into_iter()
+                .filter(|b| b.block_type == "tool_use")
+                .find_map(|b| b.input)
+                .map(|input| if *wrapped { input["value"].clone() } else { input })
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "(No structured response from Claude)".to_string());
+
+            return Ok(LlmResponse {
+                text: ans,
+                model: self.model.clone(),
+                usage,
+                tool_calls: vec![],
+            });
+        }
+
+        let mut texts = vec![];
+        let mut tool_calls = vec![];
+        for b in parsed.content {
+            match b.block_type.as_str() {
+                "text" => texts.extend(b.text),
+                "tool_use" => tool_calls.push(ToolCall {
+                    id: b.id.unwrap_or_default(),
+                    name: b.name.unwrap_or_default(),
+                    arguments: b.input.unwrap_or_else(|| json!({})),
+                }),
+                _ => {}
+            }
+        }
+
+        let ans = if texts.is_empty() && tool_calls.is_empty() {
+            "(No response from Claude)".to_string()
+        } else {
+            texts.join("")
+        };
+
+        Ok(LlmResponse {
+            text: ans,
+            model: self.model.clone(),
+            usage,
+            tool_calls,
+        })
+    }
+}
+
+// Ollama reports token counts as prompt_eval_count / eval_count
+fn ollama_usage(val: &serde_json::Value) -> Option<Usage> {
+    match (val["prompt_eval_count"].as_i64(), val["eval_count"].as_i64()) {
+        (None, None) => None,
+        (i, o) => Some(Usage {
+            input_tokens: i.unwrap_or(0),
+            output_tokens: o.unwrap_or(0),
+        }),
+    }
+}
+
+// ===== Local (Ollama; phi3 unless another model is chosen) =====
+pub struct OllamaProvider {
+    base_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: String, model: String) -> Self {
+        OllamaProvider { base_url, model }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String> {
+        let client = crate::http::client();
+
+        let mut messages = vec![];
+        if let Some(system) = &req.system {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        for m in &req.messages {
+            let mut msg = json!({ "role": m.role, "content": m.content });
+            if !m.tool_calls.is_empty() {
+                msg["tool_calls"] = m
+                    .tool_calls
+                    .iter()
+                    .map(|c| json!({ "function": { "name": c.name, "arguments": c.arguments } }))
+                    .collect();
+            }
+            if let Some(c) = &m.tool_call {
+                msg["tool_name"] = json!(c.name);
+            }
+            messages.push(msg);
+        }
+
+        let mut body = json!({
+          "model": self.model,
+          "messages": messages,
+          "stream": false
+        });
+
+        // Ollama >= 0.5 accepts a JSON schema as "format"
+        if let Some(s) = &req.response_schema {
+            body["format"] = s.schema.clone();
+        }
+        if !req.tools.is_empty() {
+            body["tools"] = req.tools.iter().map(openai_style_tool).collect();
+        }
+
+        let res = client
+            .post(format!("{}/api/chat", self.base_url.trim_end_matches('/')))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                format!(
+                    "Local LLM (Ollama) is not reachable at {}: {}\nCheck with: ollama status",
+                    self.base_url, e
+                )
+            })?;
+
+        let status = res.status();
+        let raw_text = res
+            .text()
+            .await
+            .map_err(|e| format!("Local LLM read body failed: {}", e))?;
+
+        if !status.is_success() {
+            write_log("ERROR", &format!("Local LLM HTTP {}: {}", status, raw_text));
+            if status.as_u16() == 404 && raw_text.contains("not found") {
+                return Err(format!(
+                    "Local model '{}' is not installed. Run: pull model {}",
+                    self.model, self.model
+                ));
+            }
+            return Err(format!("Local LLM HTTP {}:\n{}", status, raw_text));
+        }
+
+        let val: serde_json::Value =
+            serde_json::from_str(&raw_text).map_err(|e| format!("Parse failed: {}\nRaw:\n{}", e, raw_text))?;
+
+        let text = val["message"]["content"].as_str().unwrap_or("").to_string();
+
+        // Ollama has no call ids either
+        let tool_calls: Vec<ToolCall> = val["message"]["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| ToolCall {
+                        id: format!("call_{}", i),
+                        name: c["function"]["name"].as_str().unwrap_or("").to_string(),
+                        arguments: c["function"]["arguments"].clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if text.trim().is_empty() && tool_calls.is_empty() {
+            write_log("ERROR", "Local LLM returned empty content");
+            return Err(format!("Local LLM returned empty content.\nRaw:\n{}", raw_text));
+        }
+
+        let usage = ollama_usage(&val);
+
+        Ok(LlmResponse {
+            text,
+            model: self.model.clone(),
+            usage,
+            tool_calls,
+        })
+    }
+}
+
+// Scripted provider shared by the tests of everything that takes a `&dyn LlmProvider`
+#[cfg(test)]
+pub mod test_support {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Plays back the scripted replies in order (the last one repeats) and keeps every request
+    pub struct MockProvider {
+        replies: Vec<Result<LlmResponse, String>>,
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    impl MockProvider {
+        pub fn scripted(replies: Vec<LlmResponse>) -> Self {
+            MockProvider {
+                replies: replies.into_iter().map(Ok).collect(),
+                requests: Mutex::new(vec![]),
+            }
+        }
+
+        pub fn replying(text: &str) -> Self {
+            Self::scripted(vec![reply(text, vec![])])
+        }
+
+        pub fn failing(error: &str) -> Self {
+            MockProvider {
+                replies: vec![Err(error.to_string())],
+                requests: Mutex::new(vec![]),
+            }
+        }
+
+        pub fn requests(&self) -> Vec<LlmRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for MockProvider {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn model(&self) -> &str {
+            "mock-1"
+        }
+
+        async fn generate(&self, req: &LlmRequest) -> Result<LlmResponse, String> {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(req.clone());
+            let i = (requests.len() - 1).min(self.replies.len() - 1);
+            self.replies[i].clone()
+        }
+    }
+
+    // A reply with text and (name, arguments) tool calls
+    pub fn reply(text: &str, calls: Vec<(&str, serde_json::Value)>) -> LlmResponse {
+        LlmResponse {
+            text: text.to_string(),
+            model: "mock-1".to_string(),
+            usage: None,
+            tool_calls: calls
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, arguments))| ToolCall {
+                    id: format!("call-{}", i),
+                    name: name.to_string(),
+                    arguments,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(u: Option<Usage>) -> Option<(i64, i64)> {
+        u.map(|u| (u.input_tokens, u.output_tokens))
+    }
+
+    #[test]
+    fn reads_usage_from_each_provider_shape() {
+        let gemini: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"parts":[{"text":"hi"}]}}],
+                "usageMetadata":{"promptTokenCount":12,"candidatesTokenCount":5,"totalTokenCount":17}}"#,
+        )
+        .unwrap();
+        assert_eq!(tokens(gemini.usage_metadata.map(Usage::from)), Some((12, 5)));
+
+        let openai: OpenAIChatResponse = serde_json::from_str(
+            r#"{"choices":[{"message":{"content":"hi"}}],
+                "usage":{"prompt_tokens":30,"completion_tokens":8,"total_tokens":38}}"#,
+        )
+        .unwrap();
+        assert_eq!(tokens(openai.usage.map(Usage::from)), Some((30, 8)));
+
+        let anthropic: AnthropicResponse = serde_json::from_str(
+            r#"{"content":[{"type":"text","text":"hi"}],"usage":{"input_tokens":40,"output_tokens":9}}"#,
+        )
+        .unwrap();
+        assert_eq!(tokens(anthropic.usage.map(Usage::from)), Some((40, 9)));
+
+        let ollama = serde_json::json!({"message":{"content":"hi"},"prompt_eval_count":21,"eval_count":7});
+        assert_eq!(tokens(ollama_usage(&ollama)), Some((21, 7)));
+    }
+
+    #[test]
+    fn missing_usage_is_none_and_partial_counts_default_to_zero() {
+        let openai: OpenAIChatResponse =
+            serde_json::from_str(r#"{"choices":[{"message":{"content":"hi"}}]}"#).unwrap();
+        assert!(openai.usage.is_none());
+
+        let gemini: GeminiResponse =
+            serde_json::from_str(r#"{"candidates":[],"usageMetadata":{"promptTokenCount":4}}"#).unwrap();
+        assert_eq!(tokens(gemini.usage_metadata.map(Usage::from)), Some((4, 0)));
+
+        assert!(ollama_usage(&serde_json::json!({"message":{"content":"hi"}})).is_none());
+        assert_eq!(tokens(ollama_usage(&serde_json::json!({"eval_count":3}))), Some((0, 3)));
+    }
+}
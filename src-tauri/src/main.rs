@@ -1,10 +1,35 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::process::Command;
 
+// first: the command modules use redacted! (redact.rs)
+#[macro_use]
+mod redact;
+mod accounts;
+mod agent_loop;
+mod artifacts;
+mod automations;
+mod browser;
+mod cache;
+mod conversations;
+mod drafts;
+mod embeddings;
+mod eval;
+mod guard;
+mod http;
+mod llm;
+mod memory;
+mod ollama;
+mod prompts;
+mod protocol;
+mod queue;
+mod runner;
+mod secrets;
+mod session_health;
+mod structured;
+mod usage;
+
 // ------------------------
 // ANSI cleaner
 // ------------------------
@@ -13,28 +38,52 @@ fn clean_ansi(input: &[u8]) -> String {
     String::from_utf8_lossy(&stripped).to_string()
 }
 
+// Process output shown to the user / logged: ANSI stripped + secrets masked
+fn clean_output(input: &[u8]) -> String {
+    redact::redact(&clean_ansi(input))
+}
+
 // -------------------------
-// ✅ DB path (outside src-tauri)
-// Windows: %LOCALAPPDATA%\personaliz-desktop\personaliz.sqlite
+// ✅ App data dir (outside src-tauri)
+// Windows: %LOCALAPPDATA%\personaliz-desktop
+// Others:  ~/.personaliz-desktop
 // -------------------------
-fn db_file_path() -> std::path::PathBuf {
+#[cfg(not(test))]
+fn app_data_dir() -> std::path::PathBuf {
     use std::path::PathBuf;
 
     #[cfg(target_os = "windows")]
-    {
+    let dir = {
         let base = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
-        let dir = PathBuf::from(base).join("personaliz-desktop");
-        let _ = std::fs::create_dir_all(&dir);
-        return dir.join("personaliz.sqlite");
-    }
+        PathBuf::from(base).join("personaliz-desktop")
+    };
 
     #[cfg(not(target_os = "windows"))]
-    {
+    let dir = {
         let base = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let dir = PathBuf::from(base).join(".personaliz-desktop");
-        let _ = std::fs::create_dir_all(&dir);
-        return dir.join("personaliz.sqlite");
+        PathBuf::from(base).join(".personaliz-desktop")
+    };
+
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+// Tests never touch the user's data (DB, sessions, artifacts):
+// each test thread gets a throwaway dir
+#[cfg(test)]
+fn app_data_dir() -> std::path::PathBuf {
+    thread_local! {
+        static TEST_DIR: std::path::PathBuf = std::env::temp_dir()
+            .join(format!("personaliz-test-{}", uuid::Uuid::new_v4()));
     }
+    let dir = TEST_DIR.with(|d| d.clone());
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+// Windows: %LOCALAPPDATA%\personaliz-desktop\personaliz.sqlite
+fn db_file_path() -> std::path::PathBuf {
+    app_data_dir().join("personaliz.sqlite")
 }
 
 // -------------------------
@@ -53,8 +102,28 @@ fn ensure_approvals_table(conn: &rusqlite::Connection) {
         )",
         [],
     );
+
+    ensure_column(conn, "approvals", "prompt TEXT NULL");
+    ensure_column(conn, "approvals", "llm_used TEXT NULL");
+    // content guard result (pass / warn / block) + findings
+    ensure_column(conn, "approvals", "guard_verdict TEXT NULL");
+    ensure_column(conn, "approvals", "guard_report TEXT NULL");
+    // last dry run of this draft (run id, reason, screenshot)
+    ensure_column(conn, "approvals", "dry_run_at TEXT NULL");
+    ensure_column(conn, "approvals", "dry_run_note TEXT NULL");
 }
 fn create_approval(agent_id: &str, kind: &str, draft_text: &str) -> Result<String, String> {
+    create_approval_with_prompt(agent_id, kind, draft_text, None, None)
+}
+
+// prompt + llm_used are kept for audit when the draft came from an LLM
+fn create_approval_with_prompt(
+    agent_id: &str,
+    kind: &str,
+    draft_text: &str,
+    prompt: Option<&str>,
+    llm_used: Option<&str>,
+) -> Result<String, String> {
     use rusqlite::params;
     use uuid::Uuid;
 
@@ -64,12 +133,23 @@ fn create_approval(agent_id: &str, kind: &str, draft_text: &str) -> Result<Strin
     let id = Uuid::new_v4().to_string();
 
     conn.execute(
-        "INSERT INTO approvals (id, agent_id, kind, draft_text, created_at)
-         VALUES (?1, ?2, ?3, ?4, datetime('now'))",
-        params![id, agent_id, kind, draft_text],
+        "INSERT INTO approvals (id, agent_id, kind, draft_text, prompt, llm_used, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
+        params![id, agent_id, kind, draft_text, prompt, llm_used],
     )
     .map_err(|e| format!("DB insert failed: {}", e))?;
 
+    // rule checks now so the reviewer sees them; the full check runs again on approve
+    let verdict = guard::check_rules(draft_text, Some(&id));
+    guard::store_verdict(&conn, &id, &verdict);
+    if verdict.level() != guard::Level::Pass {
+        write_log_agent(
+            "WARN",
+            agent_id,
+            &format!("Content guard on approval {}:\n{}", id, verdict.report()),
+        );
+    }
+
     Ok(id)
 }
 
@@ -89,7 +169,11 @@ fn ensure_agents_table(conn: &rusqlite::Connection) {
 )",
         [],
     );
-   
+
+    // per-agent provider override (NULL => use the active provider)
+    ensure_column(conn, "agents", "llm_provider TEXT NULL");
+    // prompt template name for LLM drafts (NULL => default for the draft kind)
+    ensure_column(conn, "agents", "prompt_template TEXT NULL");
 }
 fn ensure_logs_table(conn: &rusqlite::Connection) {
     let _ = conn.execute(
@@ -107,6 +191,22 @@ fn ensure_logs_table(conn: &rusqlite::Connection) {
     );
 }
 
+// ALTER TABLE for columns added after the first release (errors = already there)
+fn ensure_column(conn: &rusqlite::Connection, table: &str, column_def: &str) {
+    let _ = conn.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, column_def), []);
+}
+
+fn ensure_llm_credentials_table(conn: &rusqlite::Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_credentials (
+            provider TEXT PRIMARY KEY,
+            api_key TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    );
+}
+
 fn ensure_user_settings_table(conn: &rusqlite::Connection) {
     let _ = conn.execute(
         "CREATE TABLE IF NOT EXISTS user_settings (
@@ -124,6 +224,24 @@ fn ensure_user_settings_table(conn: &rusqlite::Connection) {
          VALUES (1, NULL, NULL, NULL)",
         [],
     );
+
+    ensure_column(conn, "user_settings", "active_provider TEXT NULL");
+    ensure_llm_credentials_table(conn);
+
+    // Migrate the old single-key row into llm_credentials (runs once: key is cleared after)
+    let _ = conn.execute(
+        "INSERT OR IGNORE INTO llm_credentials (provider, api_key, updated_at)
+         SELECT COALESCE(llm_provider, 'gemini'), TRIM(llm_api_key), COALESCE(updated_at, datetime('now'))
+         FROM user_settings
+         WHERE id=1 AND llm_api_key IS NOT NULL AND TRIM(llm_api_key) <> ''",
+        [],
+    );
+    let _ = conn.execute(
+        "UPDATE user_settings
+         SET active_provider = COALESCE(active_provider, llm_provider, 'gemini'), llm_api_key = NULL
+         WHERE id=1 AND llm_api_key IS NOT NULL",
+        [],
+    );
 }
 
 fn open_db() -> Result<rusqlite::Connection, String> {
@@ -144,33 +262,43 @@ fn write_log_agent(level: &str, agent_id: &str, message: &str) {
 }
 
 fn write_log_with_agent(level: &str, agent_id: Option<&str>, message: &str) {
+    let _ = write_log_entry(level, agent_id, message, None);
+}
+
+// Returns the log id so other tables (llm_usage, ...) can point at the entry
+fn write_log_entry(
+    level: &str,
+    agent_id: Option<&str>,
+    message: &str,
+    llm_used: Option<&str>,
+) -> Option<String> {
     use rusqlite::{params, Connection};
     use std::time::{SystemTime, UNIX_EPOCH};
     use uuid::Uuid;
 
     let path = db_file_path();
-    let conn = match Connection::open(path) {
-        Ok(c) => c,
-        Err(_) => return,
-    };
+    let conn = Connection::open(path).ok()?;
 
     ensure_logs_table(&conn);
     ensure_user_settings_table(&conn);
     ensure_agents_table(&conn);
     
 
-    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(d) => d.as_secs(),
-        Err(_) => return,
-    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
 
     let id = Uuid::new_v4().to_string();
 
-    let _ = conn.execute(
+    // never persist secrets (command output, LLM errors, script output all land here)
+    let message = redact::redact(message);
+
+    conn.execute(
         "INSERT INTO logs (id, agent_id, timestamp, level, message, llm_used, status, error)
-         VALUES (?1, ?2, ?3, ?4, ?5, NULL, NULL, NULL)",
-        params![id, agent_id, now.to_string(), level, message],
-    );
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, NULL)",
+        params![id, agent_id, now.to_string(), level, message, llm_used],
+    )
+    .ok()?;
+
+    Some(id)
 }
 
 // -------------------------
@@ -220,6 +348,8 @@ fn read_last_logs(limit: i64) -> String {
 
             let mut out = String::from("🧾 Last logs:\n\n");
             for (ts, level, msg, agent_name) in items {
+                // rows written before redaction existed
+                let msg = redact::redact(&msg);
                 if let Some(name) = agent_name {
                     out.push_str(&format!("[{}] {} [Agent: {}] — {}\n", ts, level, name, msg));
                 } else {
@@ -236,517 +366,723 @@ fn read_last_logs(limit: i64) -> String {
 // -------------------------
 // ✅ User settings helpers
 // Architecture:
-// - If llm_api_key exists => use external provider (gemini/openai/anthropic)
-// - else => local phi3 via Ollama
+// - llm_credentials holds one key per provider (gemini/openai/anthropic)
+// - active_provider (or agents.llm_provider) picks which one is used
+// - no usable key => local phi3 via Ollama
 // -------------------------
-fn project_root() -> std::path::PathBuf {
-    // src-tauri -> project root
-    let mut dir = std::env::current_dir().unwrap_or_else(|_| ".".into());
-    // if we are inside src-tauri, go up one
-    if dir.ends_with("src-tauri") {
-        dir.pop();
-    }
-    dir
-}
-
-fn automation_dir() -> std::path::PathBuf {
-    // Always: .../personaliz-desktop/src-tauri
-    let tauri_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-
-    // Go up to: .../personaliz-desktop
-    let project_root = tauri_dir
-        .parent()
-        .unwrap_or(&tauri_dir)
-        .to_path_buf();
-
-    project_root.join("automation")
-}
-
-fn run_node_script(script: &str, args: Vec<String>) -> Result<String, String> {
-    let script_path = automation_dir().join(script);
-
-    if !script_path.exists() {
-        return Err(format!("❌ Script not found: {}", script_path.display()));
-    }
-
-    let mut cmd = Command::new("node");
+// "local" = offline Ollama (no key needed)
+const LOCAL_PROVIDER: &str = "local";
+const EXTERNAL_PROVIDERS: [&str; 3] = ["gemini", "openai", "anthropic"];
 
-    // ✅ Don't set current_dir at all (avoid Windows 267 issues)
-    cmd.arg(script_path);
-
-    for a in args {
-        cmd.arg(a);
-    }
-
-    let out = cmd.output().map_err(|e| format!("Failed running node: {}", e))?;
-    let stdout = clean_ansi(&out.stdout);
-    let stderr = clean_ansi(&out.stderr);
-
-    if !out.status.success() {
-        return Err(format!(
-            "Node script failed:\n{}",
-            if stderr.trim().is_empty() { stdout } else { stderr }
-        ));
-    }
-
-    Ok(if stdout.trim().is_empty() {
-        "✅ Done.".to_string()
-    } else {
-        stdout
-    })
-}
-fn run_node_script_args(script: &str, args: Vec<String>) -> Result<String, String> {
-    run_node_script(script, args)
-}
 fn normalize_provider(p: &str) -> String {
     let s = p.trim().to_lowercase();
     match s.as_str() {
         "gemini" | "google" => "gemini".to_string(),
         "openai" | "gpt" => "openai".to_string(),
         "claude" | "anthropic" => "anthropic".to_string(),
+        "local" | "ollama" | "phi3" | "local_phi3" => LOCAL_PROVIDER.to_string(),
         other => other.to_string(), // still store, but router will reject unknown
     }
 }
 
-fn get_saved_llm() -> Option<(String, String)> {
-    use rusqlite::OptionalExtension;
+// Providers a key can be saved (or cleared) for
+fn external_provider(p: &str) -> Result<String, String> {
+    let provider = normalize_provider(p);
+    if !EXTERNAL_PROVIDERS.contains(&provider.as_str()) {
+        return Err(format!(
+            "❌ Unknown provider '{}'. Use: gemini | openai | anthropic (claude).",
+            provider
+        ));
+    }
+    Ok(provider)
+}
 
-    let conn = open_db().ok()?;
-    ensure_user_settings_table(&conn);
+fn has_provider_key(conn: &rusqlite::Connection, provider: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM llm_credentials WHERE provider=?1",
+        [provider],
+        |r| r.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .unwrap_or(false)
+}
 
-    let row: Option<(Option<String>, Option<String>)> = conn
+// Keys are stored encrypted; a locked vault is an error (not a silent fallback to local)
+fn get_provider_key(conn: &rusqlite::Connection, provider: &str) -> Result<Option<String>, String> {
+    use rusqlite::OptionalExtension;
+
+    let stored: Option<String> = conn
         .query_row(
-            "SELECT llm_api_key, llm_provider FROM user_settings WHERE id=1",
-            [],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            "SELECT api_key FROM llm_credentials WHERE provider=?1",
+            [provider],
+            |r| r.get(0),
         )
         .optional()
-        .ok()?;
+        .map_err(|e| format!("DB read failed: {}", e))?;
 
-    let (key_opt, prov_opt) = row?;
-    let key = key_opt?.trim().to_string();
-    if key.is_empty() {
-        return None;
-    }
-    let provider = normalize_provider(prov_opt.unwrap_or_else(|| "gemini".to_string()).as_str());
-    Some((provider, key))
+    let Some(stored) = stored else {
+        return Ok(None);
+    };
+
+    let key = secrets::decrypt_str(&stored)?;
+    let key = key.trim().to_string();
+    Ok(if key.is_empty() { None } else { Some(key) })
 }
-#[tauri::command]
-fn demo1_run() -> Result<String, String> {
-    // Find “daily trending agent” (latest agent with tools_json containing demo_trending)
+
+// Routing order:
+// 1) agent override (agents.llm_provider) if it has a saved key
+// 2) active provider (user_settings.active_provider) if it has a saved key
+// 3) None => local phi3 via Ollama
+fn get_saved_llm(agent_id: Option<&str>) -> Result<Option<(String, String)>, String> {
+    use rusqlite::OptionalExtension;
+
     let conn = open_db()?;
+    ensure_user_settings_table(&conn);
     ensure_agents_table(&conn);
-    ensure_approvals_table(&conn);
 
-    let (agent_id, agent_name): (String, String) = conn
-        .query_row(
-            "SELECT id, name FROM agents
-             WHERE tools_json LIKE '%demo_trending%'
-             ORDER BY created_at DESC
-             LIMIT 1",
-            [],
-            |r| Ok((r.get(0)?, r.get(1)?)),
-        )
-        .map_err(|_| "❌ No agent found with tool demo_trending. Create Demo1 agent first.".to_string())?;
-
-    write_log_agent("INFO", &agent_id, "Demo1 trigger started");
-
-    // Pull trending topics (OpenClaw or fallback)
-    let topics = get_trending_topics();
-    let bullet = topics
-        .iter()
-        .take(5)
-        .enumerate()
-        .map(|(i, t)| format!("{}. {}", i + 1, t))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    // Simple post template (no LLM dependency)
-    let draft = format!(
-        "Today’s trends I’m watching 👇\n\n{}\n\nCurious: which one will dominate 2026?\n#openclaw #automation #ai",
-        bullet
-    );
+    let agent_provider: Option<String> = agent_id.and_then(|id| {
+        conn.query_row("SELECT llm_provider FROM agents WHERE id=?1", [id], |r| {
+            r.get::<_, Option<String>>(0)
+        })
+        .optional()
+        .ok()
+        .flatten()
+        .flatten()
+    });
 
-    // Create approval (waits for user)
-    let approval_id = create_approval(&agent_id, "linkedin_post", &draft)?;
+    let active_provider: Option<String> = conn
+        .query_row("SELECT active_provider FROM user_settings WHERE id=1", [], |r| r.get(0))
+        .optional()
+        .ok()
+        .flatten()
+        .flatten();
+
+    for candidate in [agent_provider, active_provider].into_iter().flatten() {
+        let provider = normalize_provider(&candidate);
+        if provider == LOCAL_PROVIDER {
+            return Ok(None);
+        }
+        if let Some(key) = get_provider_key(&conn, &provider)? {
+            return Ok(Some((provider, key)));
+        }
+    }
 
-    write_log_agent(
-        "INFO",
-        &agent_id,
-        &format!("Demo1 created approval id={} for agent '{}'", approval_id, agent_name),
-    );
+    Ok(None)
+}
+redacted! {
+    #[tauri::command]
+    fn demo1_run() -> Result<String, String> {
+        // Find “daily trending agent” (latest agent with tools_json containing demo_trending)
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
+        ensure_approvals_table(&conn);
 
-    Ok(format!(
-        "✅ Demo1 prepared a LinkedIn draft for approval.\n\nRun:\npending approvals\nThen:\napprove {}\n",
-        approval_id
-    ))
+        let (agent_id, agent_name): (String, String) = conn
+            .query_row(
+                "SELECT id, name FROM agents
+             WHERE tools_json LIKE '%demo_trending%'
+             ORDER BY created_at DESC
+             LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(|_| "❌ No agent found with tool demo_trending. Create Demo1 agent first.".to_string())?;
+
+        write_log_agent("INFO", &agent_id, "Demo1 trigger started");
+
+        // Pull trending topics (OpenClaw or fallback)
+        let topics = get_trending_topics();
+        let top5: Vec<String> = topics.into_iter().take(5).collect();
+
+        // Simple post template (no LLM dependency)
+        let draft = prompts::render_template(
+            "trend_digest_post",
+            &prompts::PromptVars::new().list("topics", &top5),
+        )?;
+
+        // Create approval (waits for user)
+        let approval_id = create_approval(&agent_id, "linkedin_post", &draft)?;
+
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("Demo1 created approval id={} for agent '{}'", approval_id, agent_name),
+        );
+
+        Ok(format!(
+            "✅ Demo1 prepared a LinkedIn draft for approval.\n\nRun:\npending approvals\nThen:\napprove {}\n",
+            approval_id
+        ))
+    }
 }
 
-#[tauri::command]
-fn demo2_run() -> Result<String, String> {
-    // Find “hourly hashtag agent” (latest agent with tools_json containing linkedin_comment)
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
+redacted! {
+    #[tauri::command]
+    async fn demo2_run() -> Result<String, String> {
+        // Find “hourly hashtag agent” (latest agent with tools_json containing linkedin_comment)
+        let (agent_id, agent_name, repo_url): (String, String, String) = {
+            let conn = open_db()?;
+            ensure_agents_table(&conn);
 
-    let (agent_id, agent_name): (String, String) = conn
-        .query_row(
-            "SELECT id, name FROM agents
+            let (id, name) = conn.query_row(
+                "SELECT id, name FROM agents
              WHERE tools_json LIKE '%linkedin_comment%'
              ORDER BY created_at DESC
              LIMIT 1",
-            [],
-            |r| Ok((r.get(0)?, r.get(1)?)),
-        )
-        .map_err(|_| "❌ No agent found with tool linkedin_comment. Create Demo2 agent first.".to_string())?;
-
-    write_log_agent("INFO", &agent_id, "Demo2 trigger started (auto comment)");
-
-    // This is the promo comment text (edit it to your repo + pitch)
-    let comment = "Just shipped a desktop automation assistant using OpenClaw + Tauri 🚀\nTry it if you’re non-technical too — it’s chat-first.\nRepo: https://github.com/<YOUR_USERNAME>/<YOUR_REPO>\n#openclaw";
-
-    // Run Playwright comment automation (must exist)
-    let out = run_node_script("linkedin_comment.js", vec![comment.to_string()])?;
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(|_| "❌ No agent found with tool linkedin_comment. Create Demo2 agent first.".to_string())?;
+            (id, name, demo_repo_url(&conn)?)
+        };
+
+        write_log_agent("INFO", &agent_id, "Demo2 trigger started (auto comment)");
+
+        // This is the promo comment text (edit the template "repo_promo_comment" for your pitch)
+        let comment = prompts::render_template(
+            "repo_promo_comment",
+            &prompts::PromptVars::new().text("repo_url", repo_url),
+        )?;
+
+        guard::enforce(
+            &guard::check_before_publish(Some(&agent_id), &comment, None).await,
+            "Demo2 comment",
+        )?;
+
+        // Run Playwright comment automation (must exist)
+        let out = runner::run_script("linkedin_comment", vec![comment.clone()], &runner::RunOptions::agent(&agent_id)).await?;
+        if out.dry_run.is_none() {
+            guard::record_published(Some(&agent_id), "linkedin_comment", &comment, None);
+        }
 
-    write_log_agent(
-        "INFO",
-        &agent_id,
-        &format!("Demo2 completed for agent '{}'", agent_name),
-    );
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("Demo2 completed for agent '{}'", agent_name),
+        );
 
-    Ok(format!("✅ Demo2 done (commented via browser automation).\n\n{}", out))
+        Ok(format!("✅ Demo2 done (commented via browser automation).\n\n{}", out))
+    }
 }
 
-#[tauri::command]
-fn run_demo1_now(agent_name: String) -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
-    ensure_approvals_table(&conn);
+redacted! {
+    #[tauri::command]
+    fn run_demo1_now(agent_name: String) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
+        ensure_approvals_table(&conn);
 
-    // find agent by name (latest)
-    let agent_id: String = conn
-        .query_row(
-            "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
-            [agent_name.clone()],
-            |r| r.get(0),
-        )
-        .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+        // find agent by name (latest)
+        let agent_id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.clone()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
 
-    write_log_agent("INFO", &agent_id, "Demo1 trigger: generating trending post draft");
+        write_log_agent("INFO", &agent_id, "Demo1 trigger: generating trending post draft");
 
-    let topics = get_trending_topics();
-    let top = topics.get(0).cloned().unwrap_or_else(|| "OpenClaw".to_string());
+        let topics = get_trending_topics();
+        let top = topics.get(0).cloned().unwrap_or_else(|| "OpenClaw".to_string());
 
-    let draft = format!(
-        "🚀 Trending today: {}\n\nI’m building a Tauri desktop assistant that uses OpenClaw + browser automation to run daily workflows.\n\nWhat’s one automation you wish your desktop could do for you?",
-        top
-    );
+        let draft = prompts::render_template(
+            "trend_spotlight_post",
+            &prompts::PromptVars::new().text("topic", top),
+        )?;
 
-    let approval_id = create_approval(&agent_id, "linkedin_post", &draft)?;
-    write_log_agent("INFO", &agent_id, &format!("Created approval id={}", approval_id));
+        let approval_id = create_approval(&agent_id, "linkedin_post", &draft)?;
+        write_log_agent("INFO", &agent_id, &format!("Created approval id={}", approval_id));
 
-    Ok(format!(
-        "✅ Demo1 draft created for agent '{}'\nApproval id: {}\n\nType: pending approvals",
-        agent_name, approval_id
-    ))
+        Ok(format!(
+            "✅ Demo1 draft created for agent '{}'\nApproval id: {}\n\nType: pending approvals",
+            agent_name, approval_id
+        ))
+    }
 }
 fn parse_tools(tools_json: &str) -> Vec<String> {
     serde_json::from_str::<Vec<String>>(tools_json).unwrap_or_default()
 }
 
-fn build_demo1_post(topics: &[String]) -> String {
+fn build_demo1_post(topics: &[String]) -> Result<String, String> {
     let t = topics.get(0).cloned().unwrap_or_else(|| "AI agents".to_string());
-    format!(
-"🚀 Today’s OpenClaw trend: **{}**
-
-What’s interesting is how fast “agentic workflows” are moving from experiments to real desktop automation:
-✅ local-first LLM routing  
-✅ approvals before risky actions  
-✅ browser automation (no APIs needed)  
-✅ scheduled repeatability
-
-If you’re building with OpenClaw, what’s the most useful agent you’ve made so far?
-
-#openclaw #automation #aiagents #productivity",
-        t
-    )
+    prompts::render_template("trending_post", &prompts::PromptVars::new().text("topic", t))
 }
 
-fn build_demo2_comment(repo_url: &str) -> String {
-    format!(
-"Hey! I just shipped a new OpenClaw-powered desktop assistant (Tauri) that automates LinkedIn actions via browser automation (not API). Repo: {} 🚀
-
-If you’re non-technical and want to try it, tell me — I’ll share a quick setup guide.",
-        repo_url
-    )
+fn build_demo2_comment(repo_url: &str) -> Result<String, String> {
+    prompts::render_template("hashtag_comment", &prompts::PromptVars::new().text("repo_url", repo_url))
 }
 
-#[tauri::command]
-async fn run_demo2_now(agent_name: String, github_url: String) -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
-
-    // find agent by name
-    let agent_id: String = conn
-        .query_row(
-            "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
-            [agent_name.clone()],
-            |r| r.get(0),
-        )
-        .map_err(|_| "❌ Agent not found by that name.".to_string())?;
-
-    let comment = format!(
-        "🚀 Quick share: I just shipped a desktop automation assistant built with Tauri + OpenClaw.\nRepo: {}\nIf you're non-technical, you can still use it — it’s chat-based + does browser automation for you.",
-        github_url
-    );
-
-    write_log_agent("INFO", &agent_id, "Demo2 trigger: posting comment on #openclaw");
+// Repo the Demo2 comments promote ("set repo url <url>")
+fn ensure_demo_settings(conn: &rusqlite::Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "demo_repo_url TEXT NULL");
+}
 
-    let res = tokio::task::spawn_blocking(move || {
-        run_node_script("linkedin_comment_openclaw.js", vec![comment])
+fn demo_repo_url(conn: &rusqlite::Connection) -> Result<String, String> {
+    ensure_demo_settings(conn);
+    conn.query_row("SELECT demo_repo_url FROM user_settings WHERE id=1", [], |r| {
+        r.get::<_, Option<String>>(0)
     })
-    .await
-    .map_err(|e| format!("Join error: {}", e))??;
-
-    write_log_agent("INFO", &agent_id, "Demo2 completed: comment posted");
-    Ok(res)
-}
-#[tauri::command]
-fn create_demo_agents() -> Result<String, String> {
-    // Demo 1: daily trending -> approval -> post
-    save_agent_config(
-        "Trending Agent".to_string(),
-        "Assistant".to_string(),
-        "Find trending topic and post to LinkedIn daily (approval required)".to_string(),
-        serde_json::to_string(&vec!["demo_trending".to_string(), "linkedin_post".to_string()]).unwrap(),
-        Some("daily".to_string()),
-        None,
-        false,
-    )?;
-
-    // Demo 2: hourly hashtag -> auto comment
-    save_agent_config(
-        "Hashtag Promo Agent".to_string(),
-        "Assistant".to_string(),
-        "Every hour comment on #openclaw posts promoting repo".to_string(),
-        serde_json::to_string(&vec!["demo_hashtag".to_string(), "linkedin_comment".to_string()]).unwrap(),
-        Some("hourly".to_string()),
-        None,
-        false,
-    )?;
-
-    Ok("✅ Created Demo 1 + Demo 2 agents.".to_string())
-}
-
-#[tauri::command]
-fn run_demo1_once() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
-    ensure_approvals_table(&conn);
+    .ok()
+    .flatten()
+    .map(|u| u.trim().to_string())
+    .filter(|u| !u.is_empty())
+    .ok_or_else(|| "❌ No repo URL set for Demo2.\nRun: set repo url https://github.com/you/your-repo".to_string())
+}
+
+redacted! {
+    #[tauri::command]
+    fn set_repo_url(url: String) -> Result<String, String> {
+        let url = url.trim().to_string();
+        let valid = (url.starts_with("https://") || url.starts_with("http://"))
+            && !url.contains(['<', '>'])
+            && !url.contains(char::is_whitespace);
+        if !valid {
+            return Err("❌ Format:\nset repo url https://github.com/you/your-repo".to_string());
+        }
 
-    // find Demo1 agent
-    let (agent_id, tools_json): (String, String) = conn.query_row(
-        "SELECT id, tools_json FROM agents WHERE name='Trending Agent' ORDER BY created_at DESC LIMIT 1",
-        [],
-        |r| Ok((r.get(0)?, r.get(1)?)),
-    ).map_err(|_| "❌ Trending Agent not found. Run: create demo agents".to_string())?;
+        let conn = open_db()?;
+        ensure_demo_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET demo_repo_url=?1, updated_at=datetime('now') WHERE id=1",
+            [url.as_str()],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
 
-    let tools = parse_tools(&tools_json);
-    if !tools.iter().any(|t| t == "demo_trending") {
-        return Err("❌ Trending Agent tools missing demo_trending".to_string());
+        write_log("INFO", &format!("Demo2 repo URL set: {}", url));
+        Ok(format!("✅ Demo2 comments will promote {}", url))
     }
+}
 
-    write_log_agent("INFO", &agent_id, "Demo1 started: fetching trending topics...");
-    let topics = get_trending_topics();
-    let draft = build_demo1_post(&topics);
-
-    let approval_id = create_approval(&agent_id, "linkedin_post", &draft)?;
-    write_log_agent("INFO", &agent_id, &format!("Created approval id={}", approval_id));
+redacted! {
+    #[tauri::command]
+    async fn run_demo2_now(agent_name: String, github_url: String) -> Result<String, String> {
+        // find agent by name
+        let agent_id: String = {
+            let conn = open_db()?;
+            ensure_agents_table(&conn);
+
+            conn.query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.clone()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?
+        };
+
+        let comment = prompts::render_template(
+            "repo_share_comment",
+            &prompts::PromptVars::new().text("repo_url", github_url),
+        )?;
+
+        guard::enforce(
+            &guard::check_before_publish(Some(&agent_id), &comment, None).await,
+            "Demo2 comment",
+        )?;
+
+        write_log_agent("INFO", &agent_id, "Demo2 trigger: posting comment on #openclaw");
+
+        let res = runner::run_script("linkedin_comment_openclaw", vec![comment.clone()], &runner::RunOptions::agent(&agent_id)).await?;
+        if res.dry_run.is_none() {
+            guard::record_published(Some(&agent_id), "linkedin_comment", &comment, None);
+        }
 
-    Ok(format!(
-        "🧩 Demo1 Draft Ready (approval required)\nApproval ID: {}\n\nType:\napprove {}\n\nOr view:\npending approvals",
-        approval_id, approval_id
-    ))
+        write_log_agent("INFO", &agent_id, "Demo2 completed: comment posted");
+        Ok(res.to_string())
+    }
+}
+redacted! {
+    #[tauri::command]
+    fn create_demo_agents() -> Result<String, String> {
+        // Demo 1: daily trending -> approval -> post
+        save_agent_config(
+            "Trending Agent".to_string(),
+            "Assistant".to_string(),
+            "Find trending topic and post to LinkedIn daily (approval required)".to_string(),
+            serde_json::to_string(&vec!["demo_trending".to_string(), "linkedin_post".to_string()]).unwrap(),
+            Some("daily".to_string()),
+            None,
+            false,
+        )?;
+
+        // Demo 2: hourly hashtag -> auto comment
+        save_agent_config(
+            "Hashtag Promo Agent".to_string(),
+            "Assistant".to_string(),
+            "Every hour comment on #openclaw posts promoting repo".to_string(),
+            serde_json::to_string(&vec!["demo_hashtag".to_string(), "linkedin_comment".to_string()]).unwrap(),
+            Some("hourly".to_string()),
+            None,
+            false,
+        )?;
+
+        Ok("✅ Created Demo 1 + Demo 2 agents.".to_string())
+    }
 }
 
-#[tauri::command]
-fn run_demo2_once() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
+redacted! {
+    #[tauri::command]
+    async fn run_demo1_once() -> Result<String, String> {
+        let (agent_id, tools_json): (String, String) = {
+            let conn = open_db()?;
+            ensure_agents_table(&conn);
+            ensure_approvals_table(&conn);
+
+            // find Demo1 agent
+            conn.query_row(
+                "SELECT id, tools_json FROM agents WHERE name='Trending Agent' ORDER BY created_at DESC LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            ).map_err(|_| "❌ Trending Agent not found. Run: create demo agents".to_string())?
+        };
+
+        let tools = parse_tools(&tools_json);
+        if !tools.iter().any(|t| t == "demo_trending") {
+            return Err("❌ Trending Agent tools missing demo_trending".to_string());
+        }
 
-    let (agent_id, tools_json): (String, String) = conn.query_row(
-        "SELECT id, tools_json FROM agents WHERE name='Hashtag Promo Agent' ORDER BY created_at DESC LIMIT 1",
-        [],
-        |r| Ok((r.get(0)?, r.get(1)?)),
-    ).map_err(|_| "❌ Hashtag Promo Agent not found. Run: create demo agents".to_string())?;
+        write_log_agent("INFO", &agent_id, "Demo1 started: fetching trending topics...");
+        let approval_id = drafts::draft_post_for_agent(&agent_id).await?;
 
-    let tools = parse_tools(&tools_json);
-    if !tools.iter().any(|t| t == "demo_hashtag") {
-        return Err("❌ Hashtag Promo Agent tools missing demo_hashtag".to_string());
+        Ok(format!(
+            "🧩 Demo1 Draft Ready (approval required)\nApproval ID: {}\n\nType:\napprove {}\n\nOr view:\npending approvals",
+            approval_id, approval_id
+        ))
     }
+}
 
-    let repo_url = "https://github.com/<YOUR_USERNAME>/<YOUR_REPO>"; // ✅ change this
-    let comment = build_demo2_comment(repo_url);
+// scheduled = Some(true) when fired by scheduler_loop (browser runs headless in "auto" mode);
+// the chat's "run demo2" leaves it out
+redacted! {
+    #[tauri::command]
+    async fn run_demo2_once(scheduled: Option<bool>) -> Result<String, String> {
+        let (agent_id, tools_json, repo_url): (String, String, String) = {
+            let conn = open_db()?;
+            ensure_agents_table(&conn);
+
+            let (id, tools) = conn.query_row(
+                "SELECT id, tools_json FROM agents WHERE name='Hashtag Promo Agent' ORDER BY created_at DESC LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            ).map_err(|_| "❌ Hashtag Promo Agent not found. Run: create demo agents".to_string())?;
+            (id, tools, demo_repo_url(&conn)?)
+        };
+
+        let tools = parse_tools(&tools_json);
+        if !tools.iter().any(|t| t == "demo_hashtag") {
+            return Err("❌ Hashtag Promo Agent tools missing demo_hashtag".to_string());
+        }
 
-    write_log_agent("INFO", &agent_id, "Demo2 started: commenting on #openclaw...");
-    let res = run_node_script("linkedin_comment.js", vec![comment])?;
-    write_log_agent("INFO", &agent_id, "Demo2 completed: comments posted.");
+        let agent = drafts::load_agent(&agent_id)?;
+        let draft = drafts::generate_draft(&agent, drafts::DraftKind::Comment, &[], Some(&repo_url)).await?;
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("Demo2 comment drafted by {}\n{}", draft.llm_used, draft.prompt),
+        );
+
+        guard::enforce(
+            &guard::check_before_publish(Some(&agent_id), &draft.text, None).await,
+            "Demo2 comment",
+        )?;
+
+        write_log_agent("INFO", &agent_id, "Demo2 started: commenting on #openclaw...");
+        let opts = runner::RunOptions::agent(&agent_id).scheduled(scheduled.unwrap_or(false));
+        let res = runner::run_script("linkedin_comment", vec![draft.text.clone()], &opts).await?;
+        if res.dry_run.is_none() {
+            guard::record_published(Some(&agent_id), "linkedin_comment", &draft.text, None);
+            memory::remember_published(&agent_id, "linkedin_comment", &draft.text, None).await;
+        }
+        write_log_agent("INFO", &agent_id, "Demo2 completed: comments posted.");
 
-    Ok(format!("✅ Demo2 done.\n\n{}", res))
+        Ok(format!("✅ Demo2 done.\n\n{}", res))
+    }
 }
 
 // ✅ For video: simulate scheduler (no waiting 1 hour / 9am)
-#[tauri::command]
-fn scheduler_tick_now() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
+redacted! {
+    #[tauri::command]
+    fn scheduler_tick_now() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
 
-    let mut stmt = conn
-        .prepare("SELECT id, name, schedule FROM agents WHERE schedule IS NOT NULL")
-        .map_err(|e| format!("Query failed: {}", e))?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, Option<String>>(2)?,
-            ))
-        })
-        .map_err(|e| format!("Query map failed: {}", e))?;
+        let mut stmt = conn
+            .prepare("SELECT id, name, schedule FROM agents WHERE schedule IS NOT NULL")
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("⏱ Scheduler tick executed:\n");
+
+        for r in rows.flatten() {
+            let agent_id = r.0;
+            let name = r.1;
+            let sched = r.2.unwrap_or_default().to_lowercase();
+
+            if sched.contains("daily") {
+                write_log_agent("INFO", &agent_id, &format!("Scheduler tick: daily agent '{}' fired", name));
+                out.push_str(&format!("✅ daily fired: {}\n", name));
+            }
+            if sched.contains("hourly") {
+                write_log_agent("INFO", &agent_id, &format!("Scheduler tick: hourly agent '{}' fired", name));
+                out.push_str(&format!("✅ hourly fired: {}\n", name));
+            }
+        }
 
-    let mut out = String::from("⏱ Scheduler tick executed:\n");
+        Ok(out)
+    }
+}
 
-    for r in rows.flatten() {
-        let agent_id = r.0;
-        let name = r.1;
-        let sched = r.2.unwrap_or_default().to_lowercase();
+redacted! {
+    #[tauri::command]
+    fn list_pending_approvals() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_approvals_table(&conn);
 
-        if sched.contains("daily") {
-            write_log_agent("INFO", &agent_id, &format!("Scheduler tick: daily agent '{}' fired", name));
-            out.push_str(&format!("✅ daily fired: {}\n", name));
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, draft_text, llm_used, guard_verdict, guard_report, dry_run_at, dry_run_note
+             FROM approvals WHERE status='pending' ORDER BY created_at DESC",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("📝 Pending Approvals:\n\n");
+        let mut count = 0;
+
+        for r in rows.flatten() {
+            count += 1;
+            let report = r.5.filter(|s| !s.trim().is_empty()).map(|s| format!("\n{}", s)).unwrap_or_default();
+            let dry_run = match (r.6, r.7) {
+                (Some(at), note) => format!("\n   🧪 Dry run: {} {}", at, note.unwrap_or_default()),
+                _ => String::new(),
+            };
+            out.push_str(&format!(
+                "{}. ID: {}\n   Type: {}\n   Drafted by: {}\n   Guard: {}{}{}\n   Draft:\n{}\n\n",
+                count,
+                r.0,
+                r.1,
+                r.3.as_deref().unwrap_or("template"),
+                r.4.as_deref().unwrap_or("not checked"),
+                report,
+                dry_run,
+                r.2
+            ));
         }
-        if sched.contains("hourly") {
-            write_log_agent("INFO", &agent_id, &format!("Scheduler tick: hourly agent '{}' fired", name));
-            out.push_str(&format!("✅ hourly fired: {}\n", name));
+
+        if count == 0 {
+            Ok("ℹ️ No pending approvals.".to_string())
+        } else {
+            Ok(out)
         }
     }
-
-    Ok(out)
+}
+// "run <id> (<reason>), screenshot <path>"; None => not a dry run
+fn dry_run_note(result: &protocol::ScriptResult) -> Option<String> {
+    result.dry_run.as_ref().map(|reason| {
+        format!(
+            "run {} ({}){}",
+            result.run_id,
+            reason,
+            result.screenshot().map(|p| format!(", screenshot {}", p)).unwrap_or_default()
+        )
+    })
 }
 
-#[tauri::command]
-fn list_pending_approvals() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_approvals_table(&conn);
+// Approval status after its publish run: approved only when something was
+// posted; a dry run or any error (timeout, selector, cancel, session) => pending
+fn settle_approval(
+    conn: &rusqlite::Connection,
+    id: &str,
+    outcome: &Result<protocol::ScriptResult, protocol::ScriptError>,
+) {
+    use rusqlite::params;
 
-    let mut stmt = conn
-        .prepare("SELECT id, kind, draft_text FROM approvals WHERE status='pending' ORDER BY created_at DESC")
-        .map_err(|e| format!("Query failed: {}", e))?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })
-        .map_err(|e| format!("Query map failed: {}", e))?;
+    let _ = match outcome.as_ref().map(dry_run_note) {
+        Ok(None) => conn.execute(
+            "UPDATE approvals SET status='approved', decided_at=datetime('now') WHERE id=?1",
+            params![id],
+        ),
+        Ok(Some(note)) => conn.execute(
+            "UPDATE approvals SET status='pending', decided_at=NULL, dry_run_at=datetime('now'), dry_run_note=?2
+             WHERE id=?1",
+            params![id, note],
+        ),
+        Err(_) => conn.execute(
+            "UPDATE approvals SET status='pending', decided_at=NULL WHERE id=?1",
+            params![id],
+        ),
+    };
+}
 
-    let mut out = String::from("📝 Pending Approvals:\n\n");
-    let mut count = 0;
+// dry_run = Some(true) rehearses the publish; the approval stays pending
+redacted! {
+    #[tauri::command]
+    async fn approve_action(id: String, dry_run: Option<bool>) -> Result<String, String> {
+        use rusqlite::params;
 
-    for r in rows.flatten() {
-        count += 1;
-        out.push_str(&format!(
-            "{}. ID: {}\n   Type: {}\n   Draft:\n{}\n\n",
-            count, r.0, r.1, r.2
-        ));
-    }
+        // get approval payload
+        let (agent_id, kind, draft_text): (String, String, String) = {
+            let conn = open_db()?;
+            ensure_approvals_table(&conn);
 
-    if count == 0 {
-        Ok("ℹ️ No pending approvals.".to_string())
-    } else {
-        Ok(out)
-    }
-}
-#[tauri::command]
-fn approve_action(id: String) -> Result<String, String> {
-    use rusqlite::params;
+            conn.query_row(
+                "SELECT agent_id, kind, draft_text FROM approvals WHERE id=?1 AND status='pending'",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .map_err(|_| "❌ Approval not found or already decided.".to_string())?
+        };
 
-    let conn = open_db()?;
-    ensure_approvals_table(&conn);
+        if kind != "linkedin_post" && kind != "linkedin_comment" {
+            return Err(format!("❌ Unknown approval kind '{}'", kind));
+        }
 
-    // get approval payload
-    let (agent_id, kind, draft_text): (String, String, String) = conn
-        .query_row(
-            "SELECT agent_id, kind, draft_text FROM approvals WHERE id=?1 AND status='pending'",
-            params![id],
-            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
-        )
-        .map_err(|_| "❌ Approval not found or already decided.".to_string())?;
+        // ✅ content guard (rules + optional tone check) right before publishing
+        let verdict = guard::check_before_publish(Some(&agent_id), &draft_text, Some(&id)).await;
+        {
+            let conn = open_db()?;
+            guard::store_verdict(&conn, &id, &verdict);
+
+            if verdict.is_blocked() {
+                conn.execute(
+                    "UPDATE approvals SET status='blocked', decided_at=datetime('now') WHERE id=?1",
+                    params![id],
+                )
+                .map_err(|e| format!("DB update failed: {}", e))?;
+            } else {
+                // 'approved' only once it is posted; 'publishing' keeps a second approve out
+                let claimed = conn
+                    .execute(
+                        "UPDATE approvals SET status='publishing' WHERE id=?1 AND status='pending'",
+                        params![id],
+                    )
+                    .map_err(|e| format!("DB update failed: {}", e))?;
+                if claimed == 0 {
+                    return Err("❌ Approval is already being published or was decided.".to_string());
+                }
+            }
+        }
+        guard::enforce(&verdict, &format!("approval {}", id))?;
 
-    // mark approved
-    conn.execute(
-        "UPDATE approvals SET status='approved', decided_at=datetime('now') WHERE id=?1",
-        params![id],
-    )
-    .map_err(|e| format!("DB update failed: {}", e))?;
+        write_log_agent("INFO", &agent_id, &format!("Approval accepted id={}", id));
+
+        let warnings = if verdict.findings.is_empty() {
+            String::new()
+        } else {
+            format!("\n\n🛡️ Guard warnings:\n{}", verdict.report())
+        };
 
-    write_log_agent("INFO", &agent_id, &format!("Approval accepted id={}", id));
+        // ✅ AUTO RUN ACTION (once)
+        // approval kind == automation name
+        let (action, done) = if kind == "linkedin_post" {
+            ("Posting to LinkedIn...", "Posted")
+        } else {
+            ("Commenting on LinkedIn...", "Commented")
+        };
 
-    // ✅ AUTO RUN ACTION (once)
-    match kind.as_str() {
-        "linkedin_post" => {
-            write_log_agent("INFO", &agent_id, "Posting to LinkedIn...");
+        write_log_agent("INFO", &agent_id, action);
 
-            let result = run_node_script("linkedin_post.js", vec![draft_text.clone()])?;
+        let outcome = runner::run_script(
+            &kind,
+            vec![draft_text.clone()],
+            &runner::RunOptions::agent(&agent_id).dry_run(dry_run),
+        )
+        .await;
+        if let Ok(conn) = open_db() {
+            settle_approval(&conn, &id, &outcome);
+        }
 
-            write_log_agent("INFO", &agent_id, "LinkedIn post completed");
+        let result = match outcome {
+            Ok(r) => r,
+            // nothing was published => the draft stays pending
+            Err(e) if dry_run.unwrap_or(false) => {
+                write_log_agent("WARN", &agent_id, &format!("Approval {} dry run failed: {}", id, e.message));
+                return Err(format!("🧪 Dry run failed: {}\nThe draft is still pending: approve {}", e, id));
+            }
+            Err(e) => {
+                write_log_agent("WARN", &agent_id, &format!("Approval {} back to pending: {}", id, e.message));
+                return Err(format!("{}\nThe draft is still pending: approve {}", e, id));
+            }
+        };
 
-            Ok(format!("✅ Approved & Posted.\n\n{}", result))
+        // rehearsal only: pending again so it can be approved for real later
+        if let Some(note) = dry_run_note(&result) {
+            write_log_agent("INFO", &agent_id, &format!("Approval {} dry run: {}", id, note));
+            return Ok(format!("{}{}\n\nStill pending: approve {}", result, warnings, id));
         }
-        "linkedin_comment" => {
-            write_log_agent("INFO", &agent_id, "Commenting on LinkedIn...");
 
-            let result = run_node_script("linkedin_comment.js", vec![draft_text.clone()])?;
+        guard::record_published(Some(&agent_id), &kind, &draft_text, Some(&id));
+        memory::remember_published(&agent_id, &kind, &draft_text, Some(&id)).await;
 
-            write_log_agent("INFO", &agent_id, "LinkedIn comment completed");
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("LinkedIn {} completed (run {})", kind.trim_start_matches("linkedin_"), result.run_id),
+        );
 
-            Ok(format!("✅ Approved & Commented.\n\n{}", result))
-        }
-        other => Err(format!("❌ Unknown approval kind '{}'", other)),
+        Ok(format!("✅ Approved & {}.{}\n\n{}", done, warnings, result))
     }
 }
 
 
 
-#[tauri::command]
-async fn linkedin_login() -> Result<String, String> {
-    write_log("INFO", "LinkedIn login (record session) started");
+// account = label of a LinkedIn account (None => default)
+redacted! {
+    #[tauri::command]
+    async fn linkedin_login(account: Option<String>) -> Result<String, String> {
+        let account = {
+            let conn = open_db()?;
+            accounts::ensure_accounts_table(&conn);
+            accounts::find_or_default(&conn, "linkedin", account.as_deref())?
+        };
+        write_log("INFO", &format!("LinkedIn login (record session) started for {}", account.display()));
 
-    let res = tokio::task::spawn_blocking(|| {
-        run_node_script("linkedin_login.js", vec![])
-    })
-    .await
-    .map_err(|e| format!("Join error: {}", e))??;
+        let opts = runner::RunOptions::default().account(Some(account.id.clone()));
+        let res = runner::run_script("linkedin_login", vec![], &opts).await?;
 
-    write_log("INFO", "LinkedIn login session saved");
+        write_log("INFO", &format!("LinkedIn login session saved for {}", account.display()));
 
-    Ok(res)
+        Ok(format!("{}\n👤 Account: {}", res, account.display()))
+    }
 }
-#[tauri::command]
-async fn linkedin_post(text: String) -> Result<String, String> {
-    write_log("INFO", "LinkedIn post requested");
+redacted! {
+    #[tauri::command]
+    async fn linkedin_post(text: String, dry_run: Option<bool>) -> Result<String, String> {
+        write_log("INFO", "LinkedIn post requested");
+        guard::enforce(&guard::check_before_publish(None, &text, None).await, "LinkedIn post")?;
 
-    let res = tokio::task::spawn_blocking(move || {
-        run_node_script("linkedin_post.js", vec![text])
-    })
-    .await
-    .map_err(|e| format!("Join error: {}", e))??;
+        let res = runner::run_script("linkedin_post", vec![text.clone()], &runner::RunOptions::default().dry_run(dry_run)).await?;
+        if res.dry_run.is_none() {
+            guard::record_published(None, "linkedin_post", &text, None);
+        }
 
-    write_log("INFO", "LinkedIn post completed");
-    Ok(res)
+        write_log("INFO", "LinkedIn post completed");
+        Ok(res.to_string())
+    }
 }
 fn run_openclaw(args: &[&str]) -> Result<String, String> {
     let out = Command::new("cmd")
@@ -754,8 +1090,8 @@ fn run_openclaw(args: &[&str]) -> Result<String, String> {
         .output()
         .map_err(|e| format!("Failed to run openclaw: {}", e))?;
 
-    let stdout = clean_ansi(&out.stdout);
-    let stderr = clean_ansi(&out.stderr);
+    let stdout = clean_output(&out.stdout);
+    let stderr = clean_output(&out.stderr);
 
     if !out.status.success() {
         return Err(if stderr.trim().is_empty() { stdout } else { stderr });
@@ -786,141 +1122,306 @@ fn get_trending_topics() -> Vec<String> {
         "Browser automation for daily ops".to_string(),
     ]
 }
-#[tauri::command]
-fn save_user_api_key(llm_api_key: String, llm_provider: String) -> Result<String, String> {
-    use rusqlite::params;
+redacted! {
+    #[tauri::command]
+    fn save_user_api_key(llm_api_key: String, llm_provider: String) -> Result<String, String> {
+        use rusqlite::params;
 
-    let key = llm_api_key.trim().to_string();
-    if key.is_empty() {
-        return Err("❌ API key is empty.".to_string());
-    }
+        let key = llm_api_key.trim().to_string();
+        if key.is_empty() {
+            return Err("❌ API key is empty.".to_string());
+        }
 
-    let provider = normalize_provider(&llm_provider);
+        let provider = external_provider(&llm_provider)?;
 
-    let conn = open_db()?;
-    ensure_user_settings_table(&conn);
+        let conn = open_db()?;
+        ensure_user_settings_table(&conn);
 
-    conn.execute(
-        "UPDATE user_settings
-         SET llm_api_key = ?1, llm_provider = ?2, updated_at = datetime('now')
-         WHERE id=1",
-        params![key, provider],
-    )
-    .map_err(|e| format!("DB update failed: {}", e))?;
+        let encrypted = secrets::encrypt_str(&key)?;
+
+        // Keys for other providers are kept; saving a key also makes it the active provider
+        conn.execute(
+            "INSERT INTO llm_credentials (provider, api_key, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(provider) DO UPDATE SET api_key = excluded.api_key, updated_at = excluded.updated_at",
+            params![provider, encrypted],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
 
-    write_log("INFO", "Saved external LLM API key + provider");
-    Ok("✅ Saved key. LLM will switch to external provider automatically.".to_string())
+        conn.execute(
+            "UPDATE user_settings SET active_provider = ?1, updated_at = datetime('now') WHERE id=1",
+            params![provider],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", &format!("Saved external LLM API key for provider {}", provider));
+        Ok(format!(
+            "✅ Saved {} key. Active provider is now {}.",
+            provider, provider
+        ))
+    }
 }
 
-#[tauri::command]
-fn clear_user_api_key() -> Result<String, String> {
-    use rusqlite::params;
+redacted! {
+    #[tauri::command]
+    fn clear_user_api_key(llm_provider: Option<String>) -> Result<String, String> {
+        use rusqlite::params;
 
-    let conn = open_db()?;
-    ensure_user_settings_table(&conn);
+        let provider = llm_provider.map(|p| external_provider(&p)).transpose()?;
 
-    conn.execute(
-        "UPDATE user_settings
-         SET llm_api_key = NULL, llm_provider = NULL, updated_at = datetime('now')
-         WHERE id=1",
-        params![],
-    )
-    .map_err(|e| format!("DB update failed: {}", e))?;
+        let conn = open_db()?;
+        ensure_user_settings_table(&conn);
 
-    write_log("INFO", "Cleared external LLM key");
-    Ok("✅ Cleared key. LLM will use local Phi-3 (offline) again.".to_string())
+        // No provider => clear everything (old behavior)
+        let Some(provider) = provider else {
+            conn.execute("DELETE FROM llm_credentials", [])
+                .map_err(|e| format!("DB update failed: {}", e))?;
+            conn.execute(
+                "UPDATE user_settings
+             SET llm_api_key = NULL, llm_provider = NULL, active_provider = NULL, updated_at = datetime('now')
+             WHERE id=1",
+                params![],
+            )
+            .map_err(|e| format!("DB update failed: {}", e))?;
+
+            write_log("INFO", "Cleared all external LLM keys");
+            return Ok("✅ Cleared keys. LLM will use local Phi-3 (offline) again.".to_string());
+        };
+
+        conn.execute("DELETE FROM llm_credentials WHERE provider=?1", params![provider])
+            .map_err(|e| format!("DB update failed: {}", e))?;
+        conn.execute(
+            "UPDATE user_settings SET active_provider = NULL, updated_at = datetime('now')
+         WHERE id=1 AND active_provider=?1",
+            params![provider],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", &format!("Cleared external LLM key for provider {}", provider));
+        Ok(format!("✅ Cleared {} key.", provider))
+    }
 }
 
-#[tauri::command]
-fn get_user_settings() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_user_settings_table(&conn);
+redacted! {
+    #[tauri::command]
+    fn set_active_provider(llm_provider: String) -> Result<String, String> {
+        use rusqlite::params;
 
-    let (key, provider): (Option<String>, Option<String>) = conn
-        .query_row(
-            "SELECT llm_api_key, llm_provider FROM user_settings WHERE id=1",
-            [],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+        let provider = normalize_provider(&llm_provider);
+
+        let conn = open_db()?;
+        ensure_user_settings_table(&conn);
+
+        if provider != LOCAL_PROVIDER {
+            if !EXTERNAL_PROVIDERS.contains(&provider.as_str()) {
+                return Err(format!(
+                    "❌ Unknown provider '{}'. Use: gemini | openai | anthropic | local.",
+                    provider
+                ));
+            }
+            if !has_provider_key(&conn, &provider) {
+                return Err(format!(
+                    "❌ No key saved for {}. Run: set key {} <KEY>",
+                    provider, provider
+                ));
+            }
+        }
+
+        conn.execute(
+            "UPDATE user_settings SET active_provider = ?1, updated_at = datetime('now') WHERE id=1",
+            params![provider],
         )
-        .map_err(|e| format!("DB read failed: {}", e))?;
+        .map_err(|e| format!("DB update failed: {}", e))?;
 
-    Ok(format!(
-        "llm_api_key: {}\nllm_provider: {}\nrouter: {}",
-        if key.as_ref().map(|k| !k.trim().is_empty()).unwrap_or(false) {
-            "✅ set"
-        } else {
-            "❌ not set"
-        },
-        provider.unwrap_or_else(|| "(none)".to_string()),
-        if key.as_ref().map(|k| !k.trim().is_empty()).unwrap_or(false) {
-            "external"
-        } else {
-            "local_phi3"
+        write_log("INFO", &format!("Active LLM provider set to {}", provider));
+        Ok(format!("✅ Active provider: {}", provider))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    fn set_agent_provider(agent_name: String, llm_provider: Option<String>) -> Result<String, String> {
+        use rusqlite::params;
+
+        let provider = llm_provider
+            .map(|p| normalize_provider(&p))
+            .filter(|p| !p.is_empty() && p != "default" && p != "none");
+
+        if let Some(p) = provider.as_deref() {
+            if p != LOCAL_PROVIDER && !EXTERNAL_PROVIDERS.contains(&p) {
+                return Err(format!(
+                    "❌ Unknown provider '{}'. Use: gemini | openai | anthropic | local.",
+                    p
+                ));
+            }
         }
-    ))
+
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
+
+        let agent_id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.clone()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+
+        conn.execute(
+            "UPDATE agents SET llm_provider = ?1 WHERE id=?2",
+            params![provider, agent_id],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("Agent provider override: {}", provider.as_deref().unwrap_or("none")),
+        );
+
+        Ok(match provider {
+            Some(p) => format!("✅ Agent '{}' will use {}.", agent_name, p),
+            None => format!("✅ Agent '{}' will use the active provider.", agent_name),
+        })
+    }
 }
 
-// ------------------------
-// 1) Safe command executor
-// ------------------------
-#[tauri::command]
-fn send_message(message: String) -> String {
-    let trimmed = message.trim();
-    let msg = trimmed.to_lowercase();
+// sandboxed agents always dry-run their automations
+redacted! {
+    #[tauri::command]
+    fn set_agent_sandbox(agent_name: String, enabled: bool) -> Result<String, String> {
+        use rusqlite::params;
+
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
 
-    write_log("INFO", &format!("User ran command: {}", trimmed));
+        let agent_id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.clone()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+
+        conn.execute(
+            "UPDATE agents SET sandbox = ?1 WHERE id=?2",
+            params![if enabled { 1 } else { 0 }, agent_id],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
 
-    // ✅ Special command: show logs
-    if msg == "show logs" || msg == "logs" || msg == "openclaw logs" {
-        write_log("INFO", "User requested logs");
-        return read_last_logs(10);
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!("Agent sandbox: {}", if enabled { "ON" } else { "OFF" }),
+        );
+
+        Ok(if enabled {
+            format!("🧪 Agent '{}' is sandboxed: its automations only dry-run.", agent_name)
+        } else {
+            format!("✅ Agent '{}' can publish for real.", agent_name)
+        })
     }
+}
 
-    // ✅ Allowed command prefixes (safe list)
-    let allowed = ["whoami", "dir", "echo", "openclaw", "node", "npm", "python", "setup openclaw"];
+redacted! {
+    #[tauri::command]
+    fn get_user_settings() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_user_settings_table(&conn);
 
-    let mut is_allowed = false;
-    for cmd in allowed.iter() {
-        if msg.starts_with(cmd) {
-            is_allowed = true;
-            break;
+        let active: Option<String> = conn
+            .query_row("SELECT active_provider FROM user_settings WHERE id=1", [], |r| r.get(0))
+            .map_err(|e| format!("DB read failed: {}", e))?;
+
+        let mut out = format!(
+            "active_provider: {}\nrouter: {}\nkeys:\n",
+            active.clone().unwrap_or_else(|| "(none)".to_string()),
+            match get_saved_llm(None) {
+                Ok(Some((provider, _))) => format!("external ({})", provider),
+                Ok(None) => format!("local_{}", ollama::local_settings().model),
+                Err(_) => "🔒 vault locked".to_string(),
+            }
+        );
+
+        for provider in EXTERNAL_PROVIDERS {
+            out.push_str(&format!(
+                "  {}: {}\n",
+                provider,
+                if has_provider_key(&conn, provider) {
+                    "✅ set"
+                } else {
+                    "❌ not set"
+                }
+            ));
         }
-    }
 
-    if !is_allowed {
-        write_log("WARN", &format!("Blocked command: {}", trimmed));
-        return "Blocked ❌: This command is not allowed for safety.\nTry: whoami, dir, echo, node -v, openclaw, or setup openclaw"
-            .to_string();
+        Ok(out)
     }
+}
 
-    // If user types "setup openclaw" in chat, call function directly
-    if msg == "setup openclaw" {
-        return setup_openclaw();
-    }
+// ------------------------
+// 1) Safe command executor
+// ------------------------
+redacted! {
+    #[tauri::command]
+    fn send_message(message: String) -> String {
+        let trimmed = message.trim();
+        let msg = trimmed.to_lowercase();
+
+        write_log("INFO", &format!("User ran command: {}", trimmed));
+
+        // ✅ Special command: show logs
+        if msg == "show logs" || msg == "logs" || msg == "openclaw logs" {
+            write_log("INFO", "User requested logs");
+            return read_last_logs(10);
+        }
+
+        // ✅ Allowed command prefixes (safe list)
+        let allowed = ["whoami", "dir", "echo", "openclaw", "node", "npm", "python", "setup openclaw"];
+
+        let mut is_allowed = false;
+        for cmd in allowed.iter() {
+            if msg.starts_with(cmd) {
+                is_allowed = true;
+                break;
+            }
+        }
 
-    let output = Command::new("cmd").args(["/C", trimmed]).output();
+        if !is_allowed {
+            write_log("WARN", &format!("Blocked command: {}", trimmed));
+            return "Blocked ❌: This command is not allowed for safety.\nTry: whoami, dir, echo, node -v, openclaw, or setup openclaw"
+                .to_string();
+        }
 
-    match output {
-        Ok(result) => {
-            if result.stdout.is_empty() {
-                let err = clean_ansi(&result.stderr);
-                if err.trim().is_empty() {
-                    write_log("INFO", "Command output: (No output)");
-                    "(No output)".to_string()
+        // If user types "setup openclaw" in chat, call function directly
+        if msg == "setup openclaw" {
+            return setup_openclaw();
+        }
+
+        let output = Command::new("cmd").args(["/C", trimmed]).output();
+
+        match output {
+            Ok(result) => {
+                if result.stdout.is_empty() {
+                    let err = clean_output(&result.stderr);
+                    if err.trim().is_empty() {
+                        write_log("INFO", "Command output: (No output)");
+                        "(No output)".to_string()
+                    } else {
+                        write_log("ERROR", &format!("Command error output: {}", err));
+                        err
+                    }
                 } else {
-                    write_log("ERROR", &format!("Command error output: {}", err));
-                    err
+                    let out = clean_output(&result.stdout);
+                    write_log("INFO", &format!("Command output: {}", out));
+                    out
                 }
-            } else {
-                let out = clean_ansi(&result.stdout);
-                write_log("INFO", &format!("Command output: {}", out));
-                out
             }
-        }
-        Err(e) => {
-            let err = format!("Failed to execute command: {}", e);
-            write_log("ERROR", &err);
-            err
+            Err(e) => {
+                let err = format!("Failed to execute command: {}", e);
+                write_log("ERROR", &err);
+                err
+            }
         }
     }
 }
@@ -928,634 +1429,452 @@ fn send_message(message: String) -> String {
 // -----------------------------------------
 // 2) OpenClaw setup
 // -----------------------------------------
-#[tauri::command]
-fn setup_openclaw() -> String {
-    write_log("INFO", "Setup OpenClaw triggered");
-
-    let mut log: Vec<String> = vec![];
-
-    log.push("🔎 Checking Node.js...".to_string());
-    let node_check = Command::new("cmd").args(["/C", "node -v"]).output();
-
-    if node_check.is_err() || node_check.as_ref().unwrap().status.success() == false {
-        log.push("❌ Node.js not found. Please install Node.js first, then retry Setup OpenClaw.".to_string());
-        write_log("ERROR", "Setup failed: Node.js not found");
-        return log.join("\n");
-    } else {
-        let v = clean_ansi(&node_check.unwrap().stdout);
-        log.push(format!("✅ Node found: {}", v.trim()));
-    }
+redacted! {
+    #[tauri::command]
+    fn setup_openclaw() -> String {
+        write_log("INFO", "Setup OpenClaw triggered");
 
-    log.push("🔎 Checking if OpenClaw is installed...".to_string());
-    let check = Command::new("cmd").args(["/C", "where openclaw"]).output();
+        let mut log: Vec<String> = vec![];
 
-    let is_installed = match check {
-        Ok(res) => res.status.success() && !res.stdout.is_empty(),
-        Err(_) => false,
-    };
+        log.push("🔎 Checking Node.js...".to_string());
+        let node_check = Command::new("cmd").args(["/C", "node -v"]).output();
 
-    if !is_installed {
-        log.push("⬇️ OpenClaw not found. Installing OpenClaw (PowerShell)...".to_string());
-
-        let install = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-ExecutionPolicy",
-                "Bypass",
-                "-Command",
-                "iwr -useb https://openclaw.ai/install.ps1 | iex",
-            ])
+        if node_check.is_err() || node_check.as_ref().unwrap().status.success() == false {
+            log.push("❌ Node.js not found. Please install Node.js first, then retry Setup OpenClaw.".to_string());
+            write_log("ERROR", "Setup failed: Node.js not found");
+            return log.join("\n");
+        } else {
+            let v = clean_output(&node_check.unwrap().stdout);
+            log.push(format!("✅ Node found: {}", v.trim()));
+        }
+
+        log.push("🔎 Checking if OpenClaw is installed...".to_string());
+        let check = Command::new("cmd").args(["/C", "where openclaw"]).output();
+
+        let is_installed = match check {
+            Ok(res) => res.status.success() && !res.stdout.is_empty(),
+            Err(_) => false,
+        };
+
+        if !is_installed {
+            log.push("⬇️ OpenClaw not found. Installing OpenClaw (PowerShell)...".to_string());
+
+            let install = Command::new("powershell")
+                .args([
+                    "-NoProfile",
+                    "-ExecutionPolicy",
+                    "Bypass",
+                    "-Command",
+                    "iwr -useb https://openclaw.ai/install.ps1 | iex",
+                ])
+                .output();
+
+            match install {
+                Ok(res) => {
+                    if res.status.success() {
+                        log.push("✅ OpenClaw installed successfully.".to_string());
+                        let out = clean_output(&res.stdout);
+                        if !out.trim().is_empty() {
+                            log.push(out);
+                        }
+                    } else {
+                        log.push("❌ OpenClaw install failed.".to_string());
+                        let err = clean_output(&res.stderr);
+                        if !err.trim().is_empty() {
+                            log.push(err.clone());
+                        }
+                        write_log("ERROR", "Setup failed: OpenClaw install failed");
+                        return log.join("\n");
+                    }
+                }
+                Err(e) => {
+                    log.push(format!("❌ Failed to run installer: {}", e));
+                    write_log("ERROR", &format!("Setup failed: installer error {}", e));
+                    return log.join("\n");
+                }
+            }
+        } else {
+            log.push("✅ OpenClaw is already installed.".to_string());
+        }
+
+        log.push("🧩 Running: openclaw onboard --install-daemon".to_string());
+        let onboard = Command::new("cmd")
+            .args(["/C", "openclaw onboard --install-daemon"])
             .output();
 
-        match install {
+        match onboard {
             Ok(res) => {
                 if res.status.success() {
-                    log.push("✅ OpenClaw installed successfully.".to_string());
-                    let out = clean_ansi(&res.stdout);
+                    log.push("✅ Onboarding completed.".to_string());
+                    let out = clean_output(&res.stdout);
                     if !out.trim().is_empty() {
                         log.push(out);
                     }
                 } else {
-                    log.push("❌ OpenClaw install failed.".to_string());
-                    let err = clean_ansi(&res.stderr);
+                    log.push("⚠️ Onboarding returned an error (may need permissions).".to_string());
+                    let err = clean_output(&res.stderr);
                     if !err.trim().is_empty() {
                         log.push(err.clone());
                     }
-                    write_log("ERROR", "Setup failed: OpenClaw install failed");
-                    return log.join("\n");
+                    write_log("WARN", "Onboarding returned error (may need permissions)");
                 }
             }
             Err(e) => {
-                log.push(format!("❌ Failed to run installer: {}", e));
-                write_log("ERROR", &format!("Setup failed: installer error {}", e));
-                return log.join("\n");
+                log.push(format!("❌ Failed to run onboarding: {}", e));
+                write_log("ERROR", &format!("Failed to run onboarding: {}", e));
             }
         }
-    } else {
-        log.push("✅ OpenClaw is already installed.".to_string());
-    }
 
-    log.push("🧩 Running: openclaw onboard --install-daemon".to_string());
-    let onboard = Command::new("cmd")
-        .args(["/C", "openclaw onboard --install-daemon"])
-        .output();
-
-    match onboard {
-        Ok(res) => {
-            if res.status.success() {
-                log.push("✅ Onboarding completed.".to_string());
-                let out = clean_ansi(&res.stdout);
-                if !out.trim().is_empty() {
-                    log.push(out);
-                }
-            } else {
-                log.push("⚠️ Onboarding returned an error (may need permissions).".to_string());
-                let err = clean_ansi(&res.stderr);
-                if !err.trim().is_empty() {
-                    log.push(err.clone());
-                }
-                write_log("WARN", "Onboarding returned error (may need permissions)");
-            }
-        }
-        Err(e) => {
-            log.push(format!("❌ Failed to run onboarding: {}", e));
-            write_log("ERROR", &format!("Failed to run onboarding: {}", e));
-        }
+        log.push("✅ Setup finished. Next: connect channels + create agents.".to_string());
+        write_log("INFO", "Setup OpenClaw finished");
+        log.join("\n")
     }
-
-    log.push("✅ Setup finished. Next: connect channels + create agents.".to_string());
-    write_log("INFO", "Setup OpenClaw finished");
-    log.join("\n")
 }
 
 // ------------------------
 // 3) Security audit command
 // ------------------------
-#[tauri::command]
-fn openclaw_security_audit() -> String {
-    write_log("INFO", "Security audit executed");
-
-    let output = Command::new("cmd")
-        .args(["/C", "openclaw security audit --deep"])
-        .output();
-
-    match output {
-        Ok(res) => {
-            if res.stdout.is_empty() {
-                let err = clean_ansi(&res.stderr);
-                if !err.trim().is_empty() {
-                    write_log("ERROR", &format!("Audit error output: {}", err));
+redacted! {
+    #[tauri::command]
+    fn openclaw_security_audit() -> String {
+        write_log("INFO", "Security audit executed");
+
+        let output = Command::new("cmd")
+            .args(["/C", "openclaw security audit --deep"])
+            .output();
+
+        match output {
+            Ok(res) => {
+                if res.stdout.is_empty() {
+                    let err = clean_output(&res.stderr);
+                    if !err.trim().is_empty() {
+                        write_log("ERROR", &format!("Audit error output: {}", err));
+                    }
+                    err
+                } else {
+                    let out = clean_output(&res.stdout);
+                    write_log("INFO", "Audit completed");
+                    out
                 }
+            }
+            Err(e) => {
+                let err = format!("Failed to run audit: {}", e);
+                write_log("ERROR", &err);
                 err
-            } else {
-                let out = clean_ansi(&res.stdout);
-                write_log("INFO", "Audit completed");
-                out
             }
         }
-        Err(e) => {
-            let err = format!("Failed to run audit: {}", e);
-            write_log("ERROR", &err);
-            err
-        }
     }
 }
 
 // ------------------------
 // 4) "Yes, continue" flow
 // ------------------------
-#[tauri::command]
-fn openclaw_finish_onboarding() -> String {
-    write_log("INFO", "User approved security continuation");
-
-    let mut out: Vec<String> = vec![];
-    out.push("✅ Consent recorded. Continuing with safe setup steps...".to_string());
-
-    out.push("🔍 Running: openclaw security audit --deep".to_string());
-    let audit = Command::new("cmd")
-        .args(["/C", "openclaw security audit --deep"])
-        .output();
-
-    match audit {
-        Ok(res) => {
-            if res.stdout.is_empty() {
-                let err = clean_ansi(&res.stderr);
-                if !err.trim().is_empty() {
-                    out.push(err.clone());
-                    write_log("ERROR", &format!("Finish onboarding audit error: {}", err));
+redacted! {
+    #[tauri::command]
+    fn openclaw_finish_onboarding() -> String {
+        write_log("INFO", "User approved security continuation");
+
+        let mut out: Vec<String> = vec![];
+        out.push("✅ Consent recorded. Continuing with safe setup steps...".to_string());
+
+        out.push("🔍 Running: openclaw security audit --deep".to_string());
+        let audit = Command::new("cmd")
+            .args(["/C", "openclaw security audit --deep"])
+            .output();
+
+        match audit {
+            Ok(res) => {
+                if res.stdout.is_empty() {
+                    let err = clean_output(&res.stderr);
+                    if !err.trim().is_empty() {
+                        out.push(err.clone());
+                        write_log("ERROR", &format!("Finish onboarding audit error: {}", err));
+                    }
+                } else {
+                    let out_clean = clean_output(&res.stdout);
+                    out.push(out_clean);
+                    write_log("INFO", "Finish onboarding audit completed");
                 }
-            } else {
-                let out_clean = clean_ansi(&res.stdout);
-                out.push(out_clean);
-                write_log("INFO", "Finish onboarding audit completed");
+            }
+            Err(e) => {
+                out.push(format!("⚠️ Could not run audit: {}", e));
+                write_log("ERROR", &format!("Could not run audit: {}", e));
             }
         }
-        Err(e) => {
-            out.push(format!("⚠️ Could not run audit: {}", e));
-            write_log("ERROR", &format!("Could not run audit: {}", e));
-        }
-    }
 
-    out.push("🪟 Windows detected: OpenClaw recommends running via WSL2 for best reliability.".to_string());
-    out.push("➡️ Run this once in terminal: wsl --install".to_string());
-    out.push("After reboot, rerun: setup openclaw".to_string());
+        out.push("🪟 Windows detected: OpenClaw recommends running via WSL2 for best reliability.".to_string());
+        out.push("➡️ Run this once in terminal: wsl --install".to_string());
+        out.push("After reboot, rerun: setup openclaw".to_string());
 
-    out.join("\n")
+        out.join("\n")
+    }
 }
 
 // ------------------------
-// ✅ LLM calls (Local + External)
+// ✅ LLM Router (THE IMPORTANT PART)
+// If the agent/active provider has a saved key => external provider
+// else => offline local Phi-3
 // ------------------------
-fn redact_secrets(s: &str) -> String {
-    // prevent leaking keys in UI logs/errors
-    let mut out = s.to_string();
-    if out.contains("AIza") {
-        out = out.replace("AIza", "AIza***REDACTED***");
-    }
-    if out.contains("sk-") {
-        out = out.replace("sk-", "sk-***REDACTED***");
+// conversation_id = None => one-off prompt (no history stored)
+// no_cache = true => always ask the provider, even if the response cache is on
+// request_id => cancel_llm(request_id) aborts just this reply
+redacted! {
+    #[tauri::command]
+    async fn llm_reply(
+        prompt: String,
+        conversation_id: Option<String>,
+        no_cache: Option<bool>,
+        request_id: Option<String>,
+    ) -> Result<String, String> {
+        let call = http::track(request_id.as_deref());
+        let req = llm::LlmRequest {
+            bypass_cache: no_cache.unwrap_or(false),
+            cancel: Some(call.token()),
+            ..llm::LlmRequest::prompt(&prompt)
+        };
+
+        match conversation_id {
+            Some(cid) => chat_reply(&cid, req).await,
+            None => route_llm_request(None, &req).await.map(|(label, resp)| (label, resp.text)),
+        }
+        .map(|(provider, ans)| format!("(LLM: {})\n{}", provider, ans))
     }
-    out
-}
-
-// ===== Gemini =====
-#[derive(Serialize)]
-struct GeminiRequest {
-    contents: Vec<GeminiContent>,
 }
 
-#[derive(Serialize)]
-struct GeminiContent {
-    parts: Vec<GeminiPart>,
-}
-
-#[derive(Serialize)]
-struct GeminiPart {
-    text: String,
-}
-
-#[derive(Deserialize)]
-struct GeminiResponse {
-    candidates: Option<Vec<GeminiCandidate>>,
-    error: Option<GeminiError>,
-}
-
-#[derive(Deserialize)]
-struct GeminiCandidate {
-    content: GeminiCandidateContent,
-}
-
-#[derive(Deserialize)]
-struct GeminiCandidateContent {
-    parts: Vec<GeminiCandidatePart>,
-}
+// req carries the new user message; the stored history replaces its messages
+async fn chat_reply(conversation_id: &str, req: llm::LlmRequest) -> Result<(String, String), String> {
+    let prompt = req.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+    let history = conversations::push_user_message(conversation_id, &prompt)?;
 
-#[derive(Deserialize)]
-struct GeminiCandidatePart {
-    text: Option<String>,
-}
+    let req = llm::LlmRequest {
+        messages: history,
+        ..req
+    };
+    let (label, resp) = route_llm_request(None, &req).await?;
 
-#[derive(Deserialize)]
-struct GeminiError {
-    message: Option<String>,
+    conversations::push_assistant_message(conversation_id, &resp.text)?;
+    Ok((label, resp.text))
 }
 
-async fn gemini_generate_with_key(key: &str, prompt: &str) -> Result<String, String> {
-    // You can change model later if needed
-    let model = "gemini-1.5-flash";
-    let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-        model, key
-    );
-
-    let body = GeminiRequest {
-        contents: vec![GeminiContent {
-            parts: vec![GeminiPart {
-                text: prompt.to_string(),
-            }],
-        }],
+// Cache lookup -> budget check -> provider call -> usage row -> cache store. Label is what the UI shows ("local_<model>" for Ollama, e.g. local_phi3).
+async fn route_llm_request(
+    agent_id: Option<&str>,
+    req: &llm::LlmRequest,
+) -> Result<(String, llm::LlmResponse), String> {
+    let (provider, label, route) = match get_saved_llm(agent_id)? {
+        Some((provider, key)) => {
+            let route = format!("external ({})", provider);
+            (llm::provider_for(&provider, Some(key)), provider, route)
+        }
+        None => {
+            let provider = llm::provider_for(LOCAL_PROVIDER, None);
+            let label = match &provider {
+                Ok(p) => format!("local_{}", p.model()),
+                Err(_) => LOCAL_PROVIDER.to_string(),
+            };
+            (provider, label.clone(), label)
+        }
     };
+    let provider = provider.map_err(|e| format!("(LLM: {}) Error: {}", label, e))?;
 
-    let client = reqwest::Client::new();
-
-    let resp = client
-        .post(url)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Gemini request failed: {}", e))?;
-
-    let status = resp.status();
-    let text_body = resp
-        .text()
-        .await
-        .map_err(|e| format!("Failed reading Gemini response: {}", e))?;
-
-    if !status.is_success() {
-        return Err(format!(
-            "Gemini HTTP {}: {}",
-            status.as_u16(),
-            redact_secrets(&text_body)
-        ));
-    }
-
-    let parsed: GeminiResponse = serde_json::from_str(&text_body).map_err(|e| {
-        format!(
-            "Failed parsing Gemini JSON: {} | body={}",
-            e,
-            redact_secrets(&text_body)
-        )
-    })?;
+    write_log(
+        "INFO",
+        &format!("LLM routing: {} model={}", route, provider.model()),
+    );
 
-    if let Some(err) = parsed.error {
-        return Err(format!(
-            "Gemini error: {}",
-            err.message.unwrap_or("Unknown error".to_string())
-        ));
+    let cache_key = cache::key_for(provider.name(), provider.model(), req);
+    if let Some(cached) = cache_key.as_deref().and_then(|k| cache::get(k, provider.name())) {
+        write_log("INFO", &format!("LLM cache hit: {} model={}", route, cached.model));
+        return Ok((format!("{} (cached)", label), cached));
     }
 
-    let answer = parsed
-        .candidates
-        .and_then(|mut c| c.pop())
-        .and_then(|c| c.content.parts.into_iter().find_map(|p| p.text))
-        .unwrap_or_else(|| "(No response from Gemini)".to_string());
-
-    Ok(answer)
-}
-
-// ===== OpenAI (Chat Completions API) =====
-#[derive(Serialize)]
-struct OpenAIChatRequest {
-    model: String,
-    messages: Vec<OpenAIChatMessage>,
-}
-
-#[derive(Serialize)]
-struct OpenAIChatMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Deserialize)]
-struct OpenAIChatResponse {
-    choices: Vec<OpenAIChatChoice>,
-}
-
-#[derive(Deserialize)]
-struct OpenAIChatChoice {
-    message: OpenAIChatChoiceMessage,
-}
+    usage::check_budget(agent_id, provider.name())?;
 
-#[derive(Deserialize)]
-struct OpenAIChatChoiceMessage {
-    content: Option<String>,
-}
+    // calls without a caller token are still tracked, so cancel_llm() reaches them
+    let call = req.cancel.is_none().then(|| http::track(None));
+    let cancel = req.cancel.clone().or_else(|| call.as_ref().map(|c| c.token())).unwrap_or_default();
 
-async fn openai_generate_with_key(key: &str, prompt: &str) -> Result<String, String> {
-    let url = "https://api.openai.com/v1/chat/completions";
-    let body = OpenAIChatRequest {
-        model: "gpt-4o-mini".to_string(),
-        messages: vec![OpenAIChatMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        }],
+    // dropping the provider future aborts the HTTP request
+    let resp = tokio::select! {
+        r = provider.generate(req) => r.map_err(|e| format!("(LLM: {}) Error: {}", label, e))?,
+        _ = cancel.cancelled() => {
+            write_log("INFO", &format!("LLM call cancelled: {}", route));
+            return Err(format!("(LLM: {}) ⛔ Cancelled.", label));
+        }
     };
 
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(url)
-        .bearer_auth(key)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("OpenAI request failed: {}", e))?;
-
-    let status = resp.status();
-    let text_body = resp
-        .text()
-        .await
-        .map_err(|e| format!("Failed reading OpenAI response: {}", e))?;
-
-    if !status.is_success() {
-        return Err(format!(
-            "OpenAI HTTP {}: {}",
-            status.as_u16(),
-            redact_secrets(&text_body)
-        ));
+    usage::record_usage(agent_id, provider.name(), &resp);
+    if let Some(key) = &cache_key {
+        cache::put(key, provider.name(), &resp);
     }
 
-    let parsed: OpenAIChatResponse = serde_json::from_str(&text_body).map_err(|e| {
-        format!(
-            "Failed parsing OpenAI JSON: {} | body={}",
-            e,
-            redact_secrets(&text_body)
-        )
-    })?;
-
-    let ans = parsed
-        .choices
-        .get(0)
-        .and_then(|c| c.message.content.clone())
-        .unwrap_or_else(|| "(No response from OpenAI)".to_string());
-
-    Ok(ans)
-}
-
-// ===== Anthropic (Claude Messages API) =====
-#[derive(Serialize)]
-struct AnthropicRequest {
-    model: String,
-    max_tokens: u32,
-    messages: Vec<AnthropicMessage>,
-}
-
-#[derive(Serialize)]
-struct AnthropicMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Deserialize)]
-struct AnthropicResponse {
-    content: Vec<AnthropicContentBlock>,
+    Ok((label, resp))
 }
 
-#[derive(Deserialize)]
-struct AnthropicContentBlock {
-    #[serde(rename = "type")]
-    block_type: String,
-    text: Option<String>,
+// `LlmProvider` view of route_llm_request, for code written against the trait
+// (draft step, evals). The route label of the last call is kept for audit.
+pub struct RoutedProvider {
+    agent_id: Option<String>,
+    label: std::sync::Mutex<Option<String>>,
 }
 
-async fn anthropic_generate_with_key(key: &str, prompt: &str) -> Result<String, String> {
-    let url = "https://api.anthropic.com/v1/messages";
-
-    let body = AnthropicRequest {
-        model: "claude-3-5-sonnet-20240620".to_string(),
-        max_tokens: 800,
-        messages: vec![AnthropicMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        }],
-    };
-
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(url)
-        .header("x-api-key", key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Anthropic request failed: {}", e))?;
-
-    let status = resp.status();
-    let text_body = resp
-        .text()
-        .await
-        .map_err(|e| format!("Failed reading Anthropic response: {}", e))?;
-
-    if !status.is_success() {
-        return Err(format!(
-            "Anthropic HTTP {}: {}",
-            status.as_u16(),
-            redact_secrets(&text_body)
-        ));
+impl RoutedProvider {
+    pub fn new(agent_id: Option<&str>) -> Self {
+        RoutedProvider {
+            agent_id: agent_id.map(|s| s.to_string()),
+            label: std::sync::Mutex::new(None),
+        }
     }
-
-    let parsed: AnthropicResponse = serde_json::from_str(&text_body).map_err(|e| {
-        format!(
-            "Failed parsing Anthropic JSON: {} | body={}",
-            e,
-            redact_secrets(&text_body)
-        )
-    })?;
-
-    let ans = parsed
-        .content
-        .into_iter()
-        .find_map(|b| b.text)
-        .unwrap_or_else(|| "(No response from Claude)".to_string());
-
-    Ok(ans)
 }
 
-// ===== Local Phi-3 (Ollama) =====
-async fn local_phi3(prompt: &str) -> Result<String, String> {
-    let client = reqwest::Client::new();
-
-    let body = json!({
-      "model": "phi3",
-      "prompt": prompt,
-      "stream": false
-    });
-
-    let res = client
-        .post("http://localhost:11434/api/generate")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Local LLM (Ollama) request failed: {}", e))?;
-
-    let status = res.status();
-    let raw_text = res
-        .text()
-        .await
-        .map_err(|e| format!("Local LLM read body failed: {}", e))?;
-
-    if !status.is_success() {
-        write_log("ERROR", &format!("Local LLM HTTP {}: {}", status, raw_text));
-        return Err(format!("Local LLM HTTP {}:\n{}", status, raw_text));
+#[async_trait::async_trait]
+impl llm::LlmProvider for RoutedProvider {
+    fn name(&self) -> &str {
+        "routed"
     }
 
-    let val: serde_json::Value =
-        serde_json::from_str(&raw_text).map_err(|e| format!("Parse failed: {}\nRaw:\n{}", e, raw_text))?;
-
-    let text = val["response"].as_str().unwrap_or("").to_string();
-
-    if text.trim().is_empty() {
-        write_log("ERROR", "Local LLM returned empty content");
-        return Err(format!("Local LLM returned empty content.\nRaw:\n{}", raw_text));
+    fn model(&self) -> &str {
+        ""
     }
 
-    Ok(text)
-}
-
-// ------------------------
-// ✅ LLM Router (THE IMPORTANT PART)
-// If user key exists => external provider
-// else => offline local Phi-3
-// ------------------------
-#[tauri::command]
-async fn llm_reply(prompt: String) -> Result<String, String> {
-    if let Some((provider, key)) = get_saved_llm() {
-        write_log("INFO", &format!("LLM routing: external ({})", provider));
-
-        let ans = match provider.as_str() {
-            "gemini" => gemini_generate_with_key(&key, &prompt).await,
-            "openai" => openai_generate_with_key(&key, &prompt).await,
-            "anthropic" => anthropic_generate_with_key(&key, &prompt).await,
-            other => Err(format!(
-                "Unknown provider '{}'. Use: gemini | openai | anthropic (claude).",
-                other
-            )),
+    async fn generate(&self, req: &llm::LlmRequest) -> Result<llm::LlmResponse, String> {
+        let (label, resp) = route_llm_request(self.agent_id.as_deref(), req).await?;
+        if let Ok(mut l) = self.label.lock() {
+            *l = Some(label);
         }
-        .map_err(|e| format!("(LLM: {}) Error: {}", provider, e))?;
-
-        return Ok(format!("(LLM: {})\n{}", provider, ans));
+        Ok(resp)
     }
 
-    write_log("INFO", "LLM routing: local_phi3");
-
-    let ans = local_phi3(&prompt)
-        .await
-        .map_err(|e| format!("(LLM: local_phi3) Error: {}", e))?;
-
-    Ok(format!("(LLM: local_phi3)\n{}", ans))
+    fn label(&self) -> String {
+        self.label
+            .lock()
+            .ok()
+            .and_then(|l| l.clone())
+            .unwrap_or_else(|| "routed".to_string())
+    }
 }
 
 // ------------------------
 // ✅ Commands used by UI
 // ------------------------
-#[tauri::command]
-fn set_llm_key(llm_api_key: String, llm_provider: String) -> Result<String, String> {
-    save_user_api_key(llm_api_key, llm_provider)
-}
-
-#[tauri::command]
-fn show_settings() -> Result<String, String> {
-    get_user_settings()
-}
-#[tauri::command]
-fn save_agent_config(
-    name: String,
-    role: String,
-    goal: String,
-    tools_json: String,
-    schedule: Option<String>,
-    triggers_json: Option<String>,
-    sandbox: bool,
-) -> Result<String, String> {
-    use rusqlite::params;
-    use uuid::Uuid;
+redacted! {
+    #[tauri::command]
+    fn set_llm_key(llm_api_key: String, llm_provider: String) -> Result<String, String> {
+        save_user_api_key(llm_api_key, llm_provider)
+    }
+}
 
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
+redacted! {
+    #[tauri::command]
+    fn show_settings() -> Result<String, String> {
+        get_user_settings()
+    }
+}
+redacted! {
+    #[tauri::command]
+    fn save_agent_config(
+        name: String,
+        role: String,
+        goal: String,
+        tools_json: String,
+        schedule: Option<String>,
+        triggers_json: Option<String>,
+        sandbox: bool,
+    ) -> Result<String, String> {
+        use rusqlite::params;
+        use uuid::Uuid;
+
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
 
-    let id = Uuid::new_v4().to_string();
+        let id = Uuid::new_v4().to_string();
 
-    conn.execute(
-        "INSERT INTO agents (id, name, role, goal, tools_json, schedule, triggers_json, sandbox, created_at)
+        conn.execute(
+            "INSERT INTO agents (id, name, role, goal, tools_json, schedule, triggers_json, sandbox, created_at)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))",
-        params![
-            id,
-            name,
-            role,
-            goal,
-            tools_json,
-            schedule,
-            triggers_json,
-            if sandbox { 1 } else { 0 }
-        ],
-    )
-    .map_err(|e| format!("DB insert failed: {}", e))?;
+            params![
+                id,
+                name,
+                role,
+                goal,
+                tools_json,
+                schedule,
+                triggers_json,
+                if sandbox { 1 } else { 0 }
+            ],
+        )
+        .map_err(|e| format!("DB insert failed: {}", e))?;
 
-    // ✅ THIS is why it wasn't showing agent creation in logs earlier.
-    // You were only logging "Saved agent config" (too generic).
-    write_log_agent(
-    "INFO",
-    &id,
-    &format!(
-        "Agent created: schedule={}, sandbox={}",
-        schedule.as_deref().unwrap_or("none"),
-        if sandbox { "ON" } else { "OFF" }
-    ),
-);
+        // ✅ THIS is why it wasn't showing agent creation in logs earlier.
+        // You were only logging "Saved agent config" (too generic).
+        write_log_agent(
+        "INFO",
+        &id,
+        &format!(
+            "Agent created: schedule={}, sandbox={}",
+            schedule.as_deref().unwrap_or("none"),
+            if sandbox { "ON" } else { "OFF" }
+        ),
+    );
 
-    Ok(format!("✅ Agent saved with id: {}", id))
+        Ok(format!("✅ Agent saved with id: {}", id))
+    }
 }
 
 
-#[tauri::command]
-fn list_agents() -> Result<String, String> {
-    let conn = open_db()?;
-    ensure_agents_table(&conn);
-
-    let mut stmt = conn
-        .prepare("SELECT id, name, goal, sandbox, created_at FROM agents ORDER BY created_at DESC")
-        .map_err(|e| format!("Query prepare failed: {}", e))?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            let id: String = row.get(0)?;
-            let name: String = row.get(1)?;
-            let goal: String = row.get(2)?;
-            let sandbox: i64 = row.get(3)?;
-            let created_at: String = row.get(4)?;
-            Ok((id, name, goal, sandbox, created_at))
-        })
-        .map_err(|e| format!("Query map failed: {}", e))?;
-
-    let mut out = String::from("🤖 Saved Agents:\n\n");
-    let mut count = 0;
-
-    for r in rows.flatten() {
-        count += 1;
-        out.push_str(&format!(
-            "{}. {}\n   id: {}\n   goal: {}\n   sandbox: {}\n   created: {}\n\n",
-            count,
-            r.1,
-            r.0,
-            r.2,
-            if r.3 == 1 { "✅ ON" } else { "❌ OFF" },
-            r.4
-        ));
-    }
+redacted! {
+    #[tauri::command]
+    fn list_agents() -> Result<String, String> {
+        let conn = open_db()?;
+        session_health::ensure_session_tables(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT a.id, a.name, a.goal, a.sandbox, a.created_at, a.llm_provider, a.paused_reason,
+                    acc.platform || '/' || acc.label
+             FROM agents a LEFT JOIN accounts acc ON acc.id = a.account_id
+             ORDER BY a.created_at DESC",
+            )
+            .map_err(|e| format!("Query prepare failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                let id: String = row.get(0)?;
+                let name: String = row.get(1)?;
+                let goal: String = row.get(2)?;
+                let sandbox: i64 = row.get(3)?;
+                let created_at: String = row.get(4)?;
+                let provider: Option<String> = row.get(5)?;
+                let paused: Option<String> = row.get(6)?;
+                let account: Option<String> = row.get(7)?;
+                Ok((id, name, goal, sandbox, created_at, provider, paused, account))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("🤖 Saved Agents:\n\n");
+        let mut count = 0;
+
+        for r in rows.flatten() {
+            count += 1;
+            out.push_str(&format!(
+                "{}. {}\n   id: {}\n   goal: {}\n   sandbox: {}\n   provider: {}\n   account: {}\n   created: {}\n{}\n",
+                count,
+                r.1,
+                r.0,
+                r.2,
+                if r.3 == 1 { "✅ ON" } else { "❌ OFF" },
+                r.5.as_deref().unwrap_or("(active)"),
+                r.7.as_deref().unwrap_or("(default)"),
+                r.4,
+                r.6.map(|p| format!("   ⏸ paused: {} (log in again to resume)\n", p)).unwrap_or_default()
+            ));
+        }
 
-    if count == 0 {
-        Ok("ℹ️ No agents saved yet.".to_string())
-    } else {
-        Ok(out)
+        if count == 0 {
+            Ok("ℹ️ No agents saved yet.".to_string())
+        } else {
+            Ok(out)
+        }
     }
 }
 use chrono::{Local, Timelike};
@@ -1569,78 +1888,104 @@ async fn scheduler_loop() {
         let minute = now.minute();
         let hour = now.hour();
 
-        let _ = tokio::task::spawn_blocking(move || {
+        // DB read off the async threads; drafting (LLM) + automation run below
+        let agents: Vec<(String, String, String)> = tokio::task::spawn_blocking(move || {
+            let mut out = vec![];
             if let Ok(conn) = open_db() {
                 ensure_agents_table(&conn);
 
+                // hourly: old run artifacts (traces, screenshots) + decrypted trace copies
+                if minute == 0 {
+                    artifacts::cleanup(&conn);
+                }
+
+                session_health::ensure_session_tables(&conn);
+
+                // agents paused on an expired session wait for the next login
                 if let Ok(mut stmt) = conn.prepare(
-                    "SELECT id, name, schedule, sandbox FROM agents WHERE schedule IS NOT NULL"
+                    "SELECT id, name, schedule FROM agents WHERE schedule IS NOT NULL AND paused_reason IS NULL"
                 ) {
                     if let Ok(rows) = stmt.query_map([], |row| {
                         let id: String = row.get(0)?;
                         let name: String = row.get(1)?;
-                        let schedule: Option<String> = row.get(2)?;
-                        let sandbox: i64 = row.get(3)?;
-                        Ok((id, name, schedule, sandbox))
+                        let schedule: String = row.get(2)?;
+                        Ok((id, name, schedule))
                     }) {
-                        for agent in rows.flatten() {
-                            if let Some(sched) = agent.2.as_deref() {
-                                let s = sched.to_lowercase();
-
-
-
-                              if s.contains("daily") && hour == 9 && minute == 0 {
-    write_log_agent(
-        "INFO",
-        &agent.0,
-        &format!("Scheduler executed daily agent '{}'", agent.1),
-    );
-
-    let topics = get_trending_topics();
-    let draft = build_demo1_post(&topics);
+                        out.extend(rows.flatten());
+                    }
+                }
+            }
+            out
+        })
+        .await
+        .unwrap_or_default();
 
-    match create_approval(&agent.0, "linkedin_post", &draft) {
-        Ok(id) => write_log_agent("INFO", &agent.0, &format!("Created approval id={}", id)),
-        Err(e) => write_log_agent("ERROR", &agent.0, &format!("Approval creation failed: {}", e)),
-    }
-}
+        for (agent_id, name, sched) in agents {
+            let s = sched.to_lowercase();
 
-if s.contains("hourly") && minute == 0 && agent.1.to_lowercase().contains("hashtag") {
-    write_log_agent("INFO", &agent.0, "Scheduler fired (hourly) -> Demo2");
-    let _ = run_demo2_once();
-}
+            if s.contains("daily") && hour == 9 && minute == 0 {
+                write_log_agent(
+                    "INFO",
+                    &agent_id,
+                    &format!("Scheduler executed daily agent '{}'", name),
+                );
 
-                            }
-                        }
-                    }
+                if let Err(e) = drafts::draft_post_for_agent(&agent_id).await {
+                    write_log_agent("ERROR", &agent_id, &format!("Approval creation failed: {}", e));
                 }
             }
-        }).await;
+
+            if s.contains("hourly") && minute == 0 && name.to_lowercase().contains("hashtag") {
+                write_log_agent("INFO", &agent_id, "Scheduler fired (hourly) -> Demo2");
+                let _ = run_demo2_once(Some(true)).await;
+            }
+        }
 
         sleep(Duration::from_secs(60)).await;
     }
 }
 
-
-
-
-
-
-
-
 fn main() {
     let _ = open_db().map(|conn| {
         ensure_logs_table(&conn);
         ensure_user_settings_table(&conn);
         ensure_agents_table(&conn);
         ensure_approvals_table(&conn);
+        // a publish cut off by a crash/quit never completed
+        let _ = conn.execute("UPDATE approvals SET status='pending' WHERE status='publishing'", []);
+        usage::ensure_usage_tables(&conn);
+        prompts::ensure_prompt_templates_table(&conn);
+        agent_loop::ensure_agent_run_tables(&conn);
+        ollama::ensure_ollama_settings(&conn);
+        cache::ensure_cache_tables(&conn);
+        memory::ensure_memory_tables(&conn);
+        http::ensure_http_settings(&conn);
+        runner::mark_interrupted_runs(&conn);
+        runner::ensure_runner_settings(&conn);
+        automations::ensure_automation_settings(&conn);
+        artifacts::cleanup(&conn);
+        browser::ensure_browser_settings(&conn);
+        session_health::ensure_session_tables(&conn);
+        queue::ensure_queue_settings(&conn);
+        conversations::ensure_conversation_tables(&conn);
     });
 
+    // Unlock via OS keyring / env passphrase and migrate plaintext secrets
+    secrets::auto_unlock();
+    secrets::remove_stale_session_copies();
+
     tauri::async_runtime::spawn(async {
         scheduler_loop().await;
     });
 
     tauri::Builder::default()
+        .setup(|app| {
+            use tauri::Manager;
+            // lets automation runs (also scheduler-triggered ones) stream output to the UI
+            runner::init(app.handle().clone());
+            automations::set_resource_dir(app.path().resource_dir().ok());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             send_message,
             setup_openclaw,
@@ -1649,7 +1994,68 @@ fn main() {
             llm_reply,
             save_user_api_key,
             clear_user_api_key,
+            set_active_provider,
+            set_agent_provider,
             set_llm_key,
+            secrets::unlock_vault,
+            secrets::lock_vault,
+            secrets::vault_status,
+            conversations::new_conversation,
+            conversations::list_conversations,
+            conversations::rename_conversation,
+            conversations::delete_conversation,
+            drafts::set_style_guide,
+            structured::llm_structured,
+            cache::set_llm_cache,
+            cache::clear_llm_cache,
+            guard::show_guard_settings,
+            guard::set_guard_setting,
+            guard::check_content,
+            eval::run_draft_eval,
+            memory::agent_memory,
+            memory::forget_agent_memory,
+            memory::set_memory_settings,
+            http::cancel_llm,
+            http::show_http_settings,
+            http::set_http_settings,
+            runner::cancel_run,
+            runner::list_runs,
+            runner::run_output,
+            runner::set_dry_run,
+            set_agent_sandbox,
+            automations::list_automations,
+            automations::set_scripts_dir,
+            artifacts::list_artifacts,
+            artifacts::open_artifact,
+            artifacts::set_artifact_retention,
+            browser::browser_settings,
+            browser::set_browser_settings,
+            session_health::check_linkedin_session,
+            accounts::list_accounts,
+            accounts::add_account,
+            accounts::remove_account,
+            accounts::set_agent_account,
+            queue::list_jobs,
+            queue::set_max_browsers,
+            ollama::ollama_status,
+            ollama::list_local_models,
+            ollama::pull_local_model,
+            ollama::delete_local_model,
+            ollama::set_local_model,
+            ollama::set_ollama_url,
+            agent_loop::run_agent,
+            agent_loop::list_agent_runs,
+            agent_loop::show_agent_run,
+            prompts::save_prompt_template,
+            prompts::list_prompt_templates,
+            prompts::show_prompt_template,
+            prompts::render_prompt_template,
+            prompts::set_agent_template,
+            drafts::show_style_guide,
+            drafts::get_approval_prompt,
+            usage::usage_report,
+            usage::set_llm_price,
+            usage::set_llm_budget,
             show_settings,
             save_agent_config,
             list_agents,
@@ -1659,7 +2065,8 @@ fn main() {
             create_demo_agents,
             demo1_run,
             run_demo1_once,
-            //run_demo2_once,
+            run_demo2_once,
+            set_repo_url,
             demo2_run,
             scheduler_tick_now,
             linkedin_post,
@@ -1669,4 +2076,93 @@ fn main() {
         .expect("error while running tauri application");
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::{ErrorCode, ScriptError, ScriptResult};
+
+    fn db_with_publishing_approval() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        ensure_approvals_table(&conn);
+        conn.execute(
+            "INSERT INTO approvals (id, agent_id, kind, draft_text, status, created_at)
+             VALUES ('ap1', 'a1', 'linkedin_post', 'Hello', 'publishing', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn status(conn: &rusqlite::Connection) -> (String, Option<String>, Option<String>) {
+        conn.query_row(
+            "SELECT status, decided_at, dry_run_note FROM approvals WHERE id='ap1'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .unwrap()
+    }
+
+    fn result(dry_run: Option<&str>) -> ScriptResult {
+        ScriptResult {
+            run_id: "run-1".to_string(),
+            message: "✅ Done.".to_string(),
+            data: serde_json::Value::Null,
+            warnings: vec![],
+            dry_run: dry_run.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn failed_dry_run_keeps_the_approval_pending() {
+        for code in [ErrorCode::SelectorNotFound, ErrorCode::Timeout, ErrorCode::Cancelled] {
+            let conn = db_with_publishing_approval();
+            settle_approval(&conn, "ap1", &Err(ScriptError::new(code, "rehearsal failed")));
+            let (status, decided, note) = status(&conn);
+            assert_eq!(status, "pending");
+            assert!(decided.is_none() && note.is_none());
+        }
+
+        let conn = db_with_publishing_approval();
+        settle_approval(&conn, "ap1", &Ok(result(Some("sandbox"))));
+        let (status, decided, note) = status(&conn);
+        assert_eq!(status, "pending");
+        assert!(decided.is_none());
+        assert_eq!(note.as_deref(), Some("run run-1 (sandbox)"));
+    }
 
+    #[test]
+    fn clearing_a_key_needs_a_known_provider() {
+        for p in ["", "  ", "mistral", "local"] {
+            let err = clear_user_api_key(Some(p.to_string())).err().unwrap_or_default();
+            assert!(err.contains("Unknown provider"), "{:?}: {}", p, err);
+        }
+        assert_eq!(external_provider(" Claude ").unwrap(), "anthropic");
+    }
+
+    #[test]
+    fn command_errors_are_masked() {
+        let err = clear_user_api_key(Some("sk-proj-AbCdEfGhIjKlMnOpQrSt".to_string())).err().unwrap_or_default();
+        assert!(err.contains("Unknown provider 'sk-****'"), "{}", err);
+    }
+
+    #[test]
+    fn demo2_needs_a_real_repo_url() {
+        let conn = open_db().unwrap();
+        assert!(demo_repo_url(&conn).unwrap_err().contains("set repo url"));
+
+        for bad in ["", "github.com/me/app", "https://github.com/<YOUR_USERNAME>/<YOUR_REPO>"] {
+            assert!(set_repo_url(bad.to_string()).is_err(), "{:?}", bad);
+        }
+        set_repo_url(" https://github.com/me/app ".to_string()).unwrap();
+        assert_eq!(demo_repo_url(&conn).unwrap(), "https://github.com/me/app");
+    }
+
+    #[test]
+    fn only_a_real_publish_approves() {
+        let conn = db_with_publishing_approval();
+        settle_approval(&conn, "ap1", &Ok(result(None)));
+        let (status, decided, _) = status(&conn);
+        assert_eq!(status, "approved");
+        assert!(decided.is_some());
+    }
+}
@@ -0,0 +1,341 @@
+// -------------------------
+// ✅ Agent memory (local semantic store)
+// Every published post/comment is embedded and kept per agent in SQLite.
+// Before a draft goes to approval it is compared (cosine similarity) with
+// what the agent published in the last N days; a near-repeat is redrafted
+// once with the similar posts listed as "do not repeat".
+// Only vectors from the same provider/model are compared.
+// -------------------------
+use rusqlite::{params, Connection};
+
+use crate::embeddings::{self, EmbeddingProvider};
+use crate::{ensure_agents_table, ensure_column, ensure_user_settings_table, open_db, write_log, write_log_agent};
+
+const DEFAULT_MEMORY_DAYS: i64 = 14;
+const DEFAULT_SIMILARITY: f64 = 0.85;
+
+pub struct Memory {
+    pub kind: String,
+    pub text: String,
+    pub created_at: String,
+    pub score: f32,
+}
+
+pub fn ensure_memory_tables(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_memories (
+            id TEXT PRIMARY KEY,
+            agent_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            text TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            source_id TEXT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    );
+    let _ = conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_agent_memories_agent ON agent_memories (agent_id, created_at)",
+        [],
+    );
+
+    ensure_user_settings_table(conn);
+    // 0 => memory check off (posts are still remembered)
+    ensure_column(conn, "user_settings", "memory_days INTEGER NULL");
+    ensure_column(conn, "user_settings", "memory_similarity REAL NULL");
+}
+
+// (days, similarity threshold)
+fn settings(conn: &Connection) -> (i64, f64) {
+    conn.query_row(
+        "SELECT memory_days, memory_similarity FROM user_settings WHERE id=1",
+        [],
+        |r| Ok((r.get::<_, Option<i64>>(0)?, r.get::<_, Option<f64>>(1)?)),
+    )
+    .map(|(d, s)| (d.unwrap_or(DEFAULT_MEMORY_DAYS), s.unwrap_or(DEFAULT_SIMILARITY)))
+    .unwrap_or((DEFAULT_MEMORY_DAYS, DEFAULT_SIMILARITY))
+}
+
+// f32 little-endian, 4 bytes per dimension
+fn to_blob(v: &[f32]) -> Vec<u8> {
+    v.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+fn from_blob(b: &[u8]) -> Vec<f32> {
+    b.chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        dot / (na * nb)
+    }
+}
+
+async fn embed_one(agent_id: &str, embedder: &dyn EmbeddingProvider, text: &str) -> Result<(String, Vec<f32>), String> {
+    let mut out = embeddings::embed(Some(agent_id), embedder, &[text.to_string()]).await?;
+    Ok((out.model, out.vectors.remove(0)))
+}
+
+// Stores a published text. source_id = approval id when there is one.
+pub async fn remember(
+    embedder: &dyn EmbeddingProvider,
+    agent_id: &str,
+    kind: &str,
+    text: &str,
+    source_id: Option<&str>,
+) -> Result<(), String> {
+    let (model, vector) = embed_one(agent_id, embedder, text).await?;
+
+    let conn = open_db()?;
+    ensure_memory_tables(&conn);
+    conn.execute(
+        "INSERT INTO agent_memories (id, agent_id, kind, text, provider, model, embedding, source_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            agent_id,
+            kind,
+            text,
+            embedder.name(),
+            model,
+            to_blob(&vector),
+            source_id
+        ],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+
+    write_log_agent("INFO", agent_id, &format!("Remembered {} ({} / {})", kind, embedder.name(), model));
+    Ok(())
+}
+
+// Publishing already happened; a memory failure is only logged
+pub async fn remember_published(agent_id: &str, kind: &str, text: &str, source_id: Option<&str>) {
+    let stored = match embeddings::routed_embedder(Some(agent_id)) {
+        Ok(embedder) => remember(embedder.as_ref(), agent_id, kind, text, source_id).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = stored {
+        write_log_agent("WARN", agent_id, &format!("Could not store agent memory: {}", e));
+    }
+}
+
+// Past texts of this agent within the memory window that are at least as
+// similar as the threshold, most similar first. Empty when the check is off.
+pub async fn similar_recent(
+    embedder: &dyn EmbeddingProvider,
+    agent_id: &str,
+    text: &str,
+) -> Result<Vec<Memory>, String> {
+    let (days, threshold, rows) = {
+        let conn = open_db()?;
+        ensure_memory_tables(&conn);
+        let (days, threshold) = settings(&conn);
+        if days <= 0 {
+            return Ok(vec![]);
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT kind, text, created_at, model, embedding FROM agent_memories
+                 WHERE agent_id=?1 AND provider=?2 AND created_at >= datetime('now', ?3)",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows: Vec<(String, String, String, String, Vec<u8>)> = stmt
+            .query_map(params![agent_id, embedder.name(), format!("-{} days", days)], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+        (days, threshold, rows)
+    };
+
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let (model, vector) = embed_one(agent_id, embedder, text).await?;
+
+    let mut hits: Vec<Memory> = rows
+        .into_iter()
+        .filter(|(_, _, _, m, _)| *m == model)
+        .map(|(kind, text, created_at, _, blob)| Memory {
+            score: cosine(&vector, &from_blob(&blob)),
+            kind,
+            text,
+            created_at,
+        })
+        .filter(|m| m.score as f64 >= threshold)
+        .collect();
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+    if !hits.is_empty() {
+        write_log_agent(
+            "INFO",
+            agent_id,
+            &format!(
+                "Draft is {:.0}% similar to a {} from {} (window {} days)",
+                hits[0].score * 100.0,
+                hits[0].kind,
+                hits[0].created_at,
+                days
+            ),
+        );
+    }
+    Ok(hits)
+}
+
+fn agent_id_by_name(conn: &Connection, agent_name: &str) -> Result<String, String> {
+    ensure_agents_table(conn);
+    conn.query_row(
+        "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+        [agent_name.trim()],
+        |r| r.get(0),
+    )
+    .map_err(|_| format!("❌ Agent '{}' not found.", agent_name.trim()))
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn agent_memory(agent_name: String) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_memory_tables(&conn);
+        let agent_id = agent_id_by_name(&conn, &agent_name)?;
+        let (days, threshold) = settings(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT kind, text, provider, model, created_at FROM agent_memories
+             WHERE agent_id=?1 ORDER BY created_at DESC LIMIT 20",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows: Vec<(String, String, String, String, String)> = stmt
+            .query_map([&agent_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+
+        let check = if days > 0 {
+            format!("repeat check: last {} days, ≥{:.0}% similar", days, threshold * 100.0)
+        } else {
+            "repeat check: off".to_string()
+        };
+
+        if rows.is_empty() {
+            return Ok(format!("ℹ️ '{}' has no memories yet ({}).", agent_name.trim(), check));
+        }
+
+        let mut out = format!("🧠 Memory of '{}' ({}):\n", agent_name.trim(), check);
+        for (kind, text, provider, model, created_at) in rows {
+            let preview: String = text.chars().take(100).collect();
+            out.push_str(&format!(
+                "\n{} | {} | {} / {}\n   {}\n",
+                created_at,
+                kind,
+                provider,
+                model,
+                preview.replace('\n', " ")
+            ));
+        }
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn forget_agent_memory(agent_name: String) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_memory_tables(&conn);
+        let agent_id = agent_id_by_name(&conn, &agent_name)?;
+
+        let n = conn
+            .execute("DELETE FROM agent_memories WHERE agent_id=?1", [&agent_id])
+            .map_err(|e| format!("DB delete failed: {}", e))?;
+
+        write_log_agent("INFO", &agent_id, &format!("Agent memory cleared ({} entries)", n));
+        Ok(format!("✅ Cleared {} memories of '{}'.", n, agent_name.trim()))
+    }
+}
+
+// days = 0 turns the repeat check off; similarity is 0.5..=1.0
+redacted! {
+    #[tauri::command]
+    pub fn set_memory_settings(days: Option<i64>, similarity: Option<f64>) -> Result<String, String> {
+        if let Some(d) = days {
+            if d < 0 {
+                return Err("❌ days must be 0 (off) or more.".to_string());
+            }
+        }
+        if let Some(s) = similarity {
+            if !(0.5..=1.0).contains(&s) {
+                return Err("❌ similarity must be between 0.5 and 1.0 (e.g. 0.85).".to_string());
+            }
+        }
+
+        let conn = open_db()?;
+        ensure_memory_tables(&conn);
+        conn.execute(
+            "UPDATE user_settings SET
+            memory_days = COALESCE(?1, memory_days),
+            memory_similarity = COALESCE(?2, memory_similarity),
+            updated_at = datetime('now')
+         WHERE id=1",
+            params![days, similarity],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        let (days, threshold) = settings(&conn);
+        write_log("INFO", &format!("Agent memory settings: days={} similarity={}", days, threshold));
+
+        Ok(if days > 0 {
+            format!(
+                "✅ Drafts ≥{:.0}% similar to posts from the last {} days will be redrafted.",
+                threshold * 100.0,
+                days
+            )
+        } else {
+            "✅ Repeat check off (published posts are still remembered).".to_string()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_roundtrip_keeps_values() {
+        let v = vec![0.25f32, -1.5, 3.0e-7, 42.0];
+        assert_eq!(from_blob(&to_blob(&v)), v);
+    }
+
+    #[test]
+    fn cosine_of_same_direction_is_one() {
+        assert!((cosine(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_of_mismatched_vectors_is_zero() {
+        assert_eq!(cosine(&[1.0, 2.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(cosine(&[], &[]), 0.0);
+    }
+}
@@ -0,0 +1,533 @@
+// -------------------------
+// ✅ Ollama (local LLM) management
+// Everything goes through Ollama's local HTTP API:
+// /api/version, /api/tags, /api/pull (streamed), /api/delete.
+// Base URL and default model live in user_settings.
+// -------------------------
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::Emitter;
+
+use crate::{ensure_column, ensure_user_settings_table, open_db, write_log};
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+pub const DEFAULT_MODEL: &str = "phi3";
+pub const PULL_PROGRESS_EVENT: &str = "ollama-pull-progress";
+
+pub fn ensure_ollama_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "ollama_base_url TEXT NULL");
+    ensure_column(conn, "user_settings", "local_model TEXT NULL");
+}
+
+pub struct LocalSettings {
+    pub base_url: String,
+    pub model: String,
+}
+
+// Never fails: unreadable settings fall back to the defaults
+pub fn local_settings() -> LocalSettings {
+    let saved: Option<(Option<String>, Option<String>)> = open_db().ok().and_then(|conn| {
+        ensure_ollama_settings(&conn);
+        conn.query_row(
+            "SELECT ollama_base_url, local_model FROM user_settings WHERE id=1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .ok()
+        .flatten()
+    });
+
+    let (url, model) = saved.unwrap_or((None, None));
+    LocalSettings {
+        base_url: url
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        model: model
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+    }
+}
+
+// "phi3" and "phi3:latest" are the same model
+pub fn same_model(a: &str, b: &str) -> bool {
+    let norm = |m: &str| {
+        let m = m.trim().to_lowercase();
+        if m.contains(':') {
+            m
+        } else {
+            format!("{}:latest", m)
+        }
+    };
+    norm(a) == norm(b)
+}
+
+#[derive(Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: String,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct PullProgress {
+    pub model: String,
+    pub status: String,
+    pub completed: Option<u64>,
+    pub total: Option<u64>,
+    pub percent: Option<u32>,
+}
+
+pub struct OllamaClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl OllamaClient {
+    pub fn new(base_url: &str) -> Self {
+        OllamaClient {
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            http: crate::http::client(),
+        }
+    }
+
+    pub fn from_settings() -> Self {
+        OllamaClient::new(&local_settings().base_url)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn unreachable(&self, e: reqwest::Error) -> String {
+        format!(
+            "❌ Ollama is not reachable at {} ({}). Install it from https://ollama.com and make sure it is running.",
+            self.base_url, e
+        )
+    }
+
+    // Ollama errors look like {"error":"model 'x' not found"}
+    async fn check(resp: reqwest::Response) -> Result<reqwest::Response, String> {
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+        let body = resp.text().await.unwrap_or_default();
+        let msg = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"].as_str().map(|s| s.to_string()))
+            .unwrap_or(body);
+        Err(format!("❌ Ollama HTTP {}: {}", status.as_u16(), msg))
+    }
+
+    pub async fn version(&self) -> Result<String, String> {
+        let resp = self
+            .http
+            .get(self.url("/api/version"))
+            .send()
+            .await
+            .map_err(|e| self.unreachable(e))?;
+        let v: serde_json::Value = Self::check(resp)
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("❌ Unexpected Ollama response: {}", e))?;
+        Ok(v["version"].as_str().unwrap_or("unknown").to_string())
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>, String> {
+        let resp = self
+            .http
+            .get(self.url("/api/tags"))
+            .send()
+            .await
+            .map_err(|e| self.unreachable(e))?;
+        let tags: TagsResponse = Self::check(resp)
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("❌ Unexpected Ollama response: {}", e))?;
+        Ok(tags.models)
+    }
+
+    // Ollama streams one JSON object per line; `on_progress` sees each of them.
+    pub async fn pull(&self, model: &str, mut on_progress: impl FnMut(&PullProgress) + Send) -> Result<(), String> {
+        let mut resp = self
+            .http
+            .post(self.url("/api/pull"))
+            .json(&json!({ "model": model, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| self.unreachable(e))
+            .map(Self::check)?
+            .await?;
+
+        let mut buf: Vec<u8> = vec![];
+        let mut success = false;
+
+        let mut handle_line = |line: &[u8], success: &mut bool| -> Result<(), String> {
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim();
+            if line.is_empty() {
+                return Ok(());
+            }
+            let v: serde_json::Value =
+                serde_json::from_str(line).map_err(|e| format!("❌ Unexpected pull output: {} ({})", line, e))?;
+
+            if let Some(err) = v["error"].as_str() {
+                return Err(format!("❌ Pull failed: {}", err));
+            }
+
+            let status = v["status"].as_str().unwrap_or("").to_string();
+            if status == "success" {
+                *success = true;
+            }
+            let completed = v["completed"].as_u64();
+            let total = v["total"].as_u64();
+            let percent = match (completed, total) {
+                (Some(c), Some(t)) if t > 0 => Some((c.min(t) * 100 / t) as u32),
+                _ => None,
+            };
+
+            on_progress(&PullProgress {
+                model: model.to_string(),
+                status,
+                completed,
+                total,
+                percent,
+            });
+            Ok(())
+        };
+
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| format!("❌ Pull stream interrupted: {}", e))?
+        {
+            buf.extend_from_slice(&chunk);
+            while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                handle_line(&line, &mut success)?;
+            }
+        }
+        handle_line(&buf, &mut success)?;
+
+        if !success {
+            return Err(format!("❌ Pull of '{}' ended without success.", model));
+        }
+        Ok(())
+    }
+
+    pub async fn delete(&self, model: &str) -> Result<(), String> {
+        let resp = self
+            .http
+            .delete(self.url("/api/delete"))
+            .json(&json!({ "model": model }))
+            .send()
+            .await
+            .map_err(|e| self.unreachable(e))?;
+        Self::check(resp).await?;
+        Ok(())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    let gb = bytes as f64 / 1_000_000_000.0;
+    if gb >= 1.0 {
+        format!("{:.1} GB", gb)
+    } else {
+        format!("{:.0} MB", bytes as f64 / 1_000_000.0)
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub async fn ollama_status() -> Result<String, String> {
+        let client = OllamaClient::from_settings();
+        let version = client.version().await?;
+        let model = local_settings().model;
+
+        let installed = client
+            .list_models()
+            .await
+            .map(|models| models.iter().any(|m| same_model(&m.name, &model)))
+            .unwrap_or(false);
+
+        Ok(format!(
+            "✅ Ollama {} is running at {}\nDefault local model: {} {}",
+            version,
+            client.base_url(),
+            model,
+            if installed {
+                "(installed)".to_string()
+            } else {
+                format!("(❌ not installed — run: pull model {})", model)
+            }
+        ))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub async fn list_local_models() -> Result<String, String> {
+        let models = OllamaClient::from_settings().list_models().await?;
+        let default_model = local_settings().model;
+
+        if models.is_empty() {
+            return Ok(format!(
+                "ℹ️ No local models installed.\nRun: pull model {}",
+                default_model
+            ));
+        }
+
+        let mut out = String::from("🦙 Local models:\n\n");
+        for m in &models {
+            out.push_str(&format!(
+                "- {}{} ({}, {})\n",
+                m.name,
+                if same_model(&m.name, &default_model) { " ⭐ default" } else { "" },
+                format_size(m.size),
+                m.modified_at.get(..10).unwrap_or(&m.modified_at)
+            ));
+        }
+        Ok(out)
+    }
+}
+
+// Emits PULL_PROGRESS_EVENT with a PullProgress payload while downloading
+redacted! {
+    #[tauri::command]
+    pub async fn pull_local_model(app: tauri::AppHandle, model: String) -> Result<String, String> {
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            return Err("❌ Usage: pull model <name>".to_string());
+        }
+
+        write_log("INFO", &format!("Ollama pull started: {}", model));
+
+        // cancel_llm("pull:<model>") or cancel_llm() stops the download
+        let call = crate::http::track(Some(&format!("pull:{}", model)));
+        let cancel = call.token();
+        let client = OllamaClient::from_settings();
+
+        // one event per status / percent change, not per stream line
+        let mut last: Option<(String, Option<u32>)> = None;
+        let pull = client.pull(&model, |p| {
+            let key = (p.status.clone(), p.percent);
+            if last.as_ref() != Some(&key) {
+                let _ = app.emit(PULL_PROGRESS_EVENT, p.clone());
+                last = Some(key);
+            }
+        });
+
+        let result = tokio::select! {
+            r = pull => r,
+            _ = cancel.cancelled() => Err(format!("⛔ Pull of '{}' cancelled.", model)),
+        };
+
+        match result {
+            Ok(()) => {
+                write_log("INFO", &format!("Ollama pull finished: {}", model));
+                Ok(format!("✅ Model '{}' is ready.", model))
+            }
+            Err(e) => {
+                write_log("ERROR", &format!("Ollama pull failed for {}: {}", model, e));
+                Err(e)
+            }
+        }
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub async fn delete_local_model(model: String) -> Result<String, String> {
+        let model = model.trim().to_string();
+        OllamaClient::from_settings().delete(&model).await?;
+        write_log("INFO", &format!("Ollama model deleted: {}", model));
+
+        let mut out = format!("✅ Deleted local model '{}'.", model);
+        if same_model(&model, &local_settings().model) {
+            out.push_str("\n⚠️ That was the default local model. Choose another: use local model <name>");
+        }
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub async fn set_local_model(model: String) -> Result<String, String> {
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            return Err("❌ Usage: use local model <name>".to_string());
+        }
+
+        let models = OllamaClient::from_settings().list_models().await?;
+        let installed = models
+            .iter()
+            .find(|m| same_model(&m.name, &model))
+            .ok_or_else(|| format!("❌ Model '{}' is not installed. Run: pull model {}", model, model))?;
+
+        let conn = open_db()?;
+        ensure_ollama_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET local_model=?1, updated_at=datetime('now') WHERE id=1",
+            params![model],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", &format!("Default local model set to {}", installed.name));
+        Ok(format!("✅ Local LLM now uses '{}'.", model))
+    }
+}
+
+// url=None resets to http://localhost:11434
+redacted! {
+    #[tauri::command]
+    pub fn set_ollama_url(url: Option<String>) -> Result<String, String> {
+        let url = url.map(|u| u.trim().trim_end_matches('/').to_string()).filter(|u| !u.is_empty());
+        if let Some(u) = &url {
+            if !(u.starts_with("http://") || u.starts_with("https://")) {
+                return Err("❌ Ollama URL must start with http:// or https://".to_string());
+            }
+        }
+
+        let conn = open_db()?;
+        ensure_ollama_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET ollama_base_url=?1, updated_at=datetime('now') WHERE id=1",
+            params![url],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        Ok(format!(
+            "✅ Ollama URL set to {}",
+            url.as_deref().unwrap_or(DEFAULT_BASE_URL)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn version_reports_running_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "version": "0.5.7" })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(&format!("{}/", server.uri()));
+        assert_eq!(client.version().await.unwrap(), "0.5.7");
+    }
+
+    #[tokio::test]
+    async fn unreachable_server_gives_install_hint() {
+        // nothing listens on port 9 (discard)
+        let err = OllamaClient::new("http://127.0.0.1:9").version().await.unwrap_err();
+        assert!(err.contains("not reachable at http://127.0.0.1:9"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn lists_installed_models() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "models": [
+                    { "name": "phi3:latest", "size": 2176178913u64, "modified_at": "2025-01-10T08:00:00Z" },
+                    { "name": "llama3.2:3b", "size": 2019393189u64, "modified_at": "2025-01-11T08:00:00Z" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let models = OllamaClient::new(&server.uri()).list_models().await.unwrap();
+        assert_eq!(models.len(), 2);
+        assert!(same_model(&models[0].name, "phi3"));
+        assert!(!same_model(&models[1].name, "llama3.2"));
+    }
+
+    #[tokio::test]
+    async fn pull_streams_progress_until_success() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "{\"status\":\"pulling manifest\"}\n",
+            "{\"status\":\"pulling abc\",\"digest\":\"sha256:abc\",\"total\":200,\"completed\":50}\n",
+            "{\"status\":\"pulling abc\",\"digest\":\"sha256:abc\",\"total\":200,\"completed\":200}\n",
+            "{\"status\":\"success\"}\n"
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .and(body_json(json!({ "model": "phi3", "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let mut seen = vec![];
+        OllamaClient::new(&server.uri())
+            .pull("phi3", |p| seen.push((p.status.clone(), p.percent)))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            seen,
+            vec![
+                ("pulling manifest".to_string(), None),
+                ("pulling abc".to_string(), Some(25)),
+                ("pulling abc".to_string(), Some(100)),
+                ("success".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pull_surfaces_stream_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("{\"status\":\"pulling manifest\"}\n{\"error\":\"pull model manifest: file does not exist\"}\n"),
+            )
+            .mount(&server)
+            .await;
+
+        let err = OllamaClient::new(&server.uri()).pull("nope", |_| {}).await.unwrap_err();
+        assert!(err.contains("file does not exist"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn delete_missing_model_reports_ollama_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/delete"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "error": "model 'nope' not found" })))
+            .mount(&server)
+            .await;
+
+        let err = OllamaClient::new(&server.uri()).delete("nope").await.unwrap_err();
+        assert_eq!(err, "❌ Ollama HTTP 404: model 'nope' not found");
+    }
+}
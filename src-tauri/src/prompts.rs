@@ -0,0 +1,720 @@
+// -------------------------
+// ✅ Prompt templates
+// Named, versioned templates stored in SQLite. Placeholders look like
+// {topic}, {repo_url} or {agent.goal}; write {{ and }} for literal braces.
+// Saving never overwrites: it adds the next version. "name@2" pins a version.
+// -------------------------
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+use crate::drafts::AgentProfile;
+use crate::{ensure_agents_table, open_db, write_log, write_log_agent};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VarType {
+    Text,
+    Url,
+    // rendered as a numbered list
+    List,
+    Number,
+}
+
+impl VarType {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "string" => Ok(VarType::Text),
+            "url" => Ok(VarType::Url),
+            "list" => Ok(VarType::List),
+            "number" => Ok(VarType::Number),
+            other => Err(format!("❌ Unknown variable type '{}'. Use: text, url, list, number", other)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            VarType::Text => "text",
+            VarType::Url => "url",
+            VarType::List => "list",
+            VarType::Number => "number",
+        }
+    }
+
+    // used when a template is saved without explicit variable types
+    fn infer(name: &str) -> Self {
+        if name.ends_with("url") {
+            VarType::Url
+        } else if name == "topics" {
+            VarType::List
+        } else {
+            VarType::Text
+        }
+    }
+}
+
+pub enum PromptValue {
+    Text(String),
+    List(Vec<String>),
+    Number(f64),
+}
+
+#[derive(Default)]
+pub struct PromptVars {
+    values: HashMap<String, PromptValue>,
+}
+
+impl PromptVars {
+    pub fn new() -> Self {
+        PromptVars::default()
+    }
+
+    pub fn text(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.values.insert(name.to_string(), PromptValue::Text(value.into()));
+        self
+    }
+
+    pub fn list(mut self, name: &str, items: &[String]) -> Self {
+        self.values.insert(name.to_string(), PromptValue::List(items.to_vec()));
+        self
+    }
+
+    pub fn agent(self, agent: &AgentProfile) -> Self {
+        self.text("agent.name", agent.name.as_str())
+            .text("agent.role", agent.role.as_str())
+            .text("agent.goal", agent.goal.as_str())
+    }
+
+    // {"topic": "x", "topics": ["a", "b"], "count": 3}
+    fn from_json(json: &str) -> Result<Self, String> {
+        let map: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(json).map_err(|e| format!("❌ Variables must be a JSON object: {}", e))?;
+
+        let mut vars = PromptVars::new();
+        for (name, value) in map {
+            let v = match value {
+                serde_json::Value::String(s) => PromptValue::Text(s),
+                serde_json::Value::Number(n) => PromptValue::Number(n.as_f64().unwrap_or_default()),
+                serde_json::Value::Array(items) => PromptValue::List(
+                    items
+                        .into_iter()
+                        .map(|i| match i {
+                            serde_json::Value::String(s) => s,
+                            other => other.to_string(),
+                        })
+                        .collect(),
+                ),
+                other => PromptValue::Text(other.to_string()),
+            };
+            vars.values.insert(name, v);
+        }
+        Ok(vars)
+    }
+}
+
+pub struct Template {
+    pub name: String,
+    pub version: i64,
+    pub body: String,
+    pub variables: Vec<(String, VarType)>,
+}
+
+impl Template {
+    fn var_type(&self, name: &str) -> VarType {
+        self.variables
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, t)| *t)
+            .unwrap_or_else(|| VarType::infer(name))
+    }
+
+    pub fn render(&self, vars: &PromptVars) -> Result<String, String> {
+        let mut missing: Vec<String> = vec![];
+        let mut invalid: Vec<String> = vec![];
+
+        let out = walk(&self.body, |name, out| {
+            match vars.values.get(name).map(|v| format_value(name, self.var_type(name), v)) {
+                Some(Ok(Some(s))) => out.push_str(&s),
+                Some(Err(e)) => invalid.push(e),
+                Some(Ok(None)) | None => {
+                    if !missing.iter().any(|m| m == name) {
+                        missing.push(name.to_string());
+                    }
+                }
+            }
+        });
+
+        if !missing.is_empty() {
+            return Err(format!(
+                "❌ Template '{}' v{} is missing variables: {}",
+                self.name,
+                self.version,
+                missing.join(", ")
+            ));
+        }
+        if !invalid.is_empty() {
+            return Err(format!(
+                "❌ Template '{}' v{}: {}",
+                self.name,
+                self.version,
+                invalid.join("; ")
+            ));
+        }
+        Ok(out)
+    }
+}
+
+// Ok(None) = empty value (treated as missing)
+fn format_value(name: &str, ty: VarType, value: &PromptValue) -> Result<Option<String>, String> {
+    let numbered = |items: &[String]| {
+        items
+            .iter()
+            .filter(|i| !i.trim().is_empty())
+            .enumerate()
+            .map(|(i, t)| format!("{}. {}", i + 1, t.trim()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let s = match (ty, value) {
+        (VarType::Url, PromptValue::Text(s)) => {
+            let s = s.trim();
+            if !s.is_empty()
+                && (!(s.starts_with("https://") || s.starts_with("http://")) || s.contains(char::is_whitespace))
+            {
+                return Err(format!("{} must be an http(s) URL (got '{}')", name, s));
+            }
+            s.to_string()
+        }
+        (VarType::Url, _) => return Err(format!("{} must be an http(s) URL", name)),
+        (VarType::Number, PromptValue::Number(n)) => n.to_string(),
+        (VarType::Number, PromptValue::Text(s)) => {
+            if s.trim().parse::<f64>().is_err() {
+                return Err(format!("{} must be a number (got '{}')", name, s.trim()));
+            }
+            s.trim().to_string()
+        }
+        (VarType::Number, PromptValue::List(_)) => return Err(format!("{} must be a number", name)),
+        (_, PromptValue::List(items)) => numbered(items),
+        (_, PromptValue::Text(s)) => s.trim().to_string(),
+        (_, PromptValue::Number(n)) => n.to_string(),
+    };
+
+    Ok(if s.is_empty() { None } else { Some(s) })
+}
+
+fn is_var_name(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('.')
+        && !s.ends_with('.')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+// Copies `body`, calling `on_var` for each {placeholder}. Braces that are not
+// a placeholder (e.g. JSON examples) are kept as-is.
+fn walk(body: &str, mut on_var: impl FnMut(&str, &mut String)) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        if tail.starts_with('{') {
+            if let Some(end) = tail.find('}') {
+                let name = &tail[1..end];
+                if is_var_name(name) {
+                    on_var(name, &mut out);
+                    rest = &tail[end + 1..];
+                    continue;
+                }
+            }
+        }
+
+        out.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn placeholders(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    walk(body, |name, _| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    });
+    names
+}
+
+// -------------------------
+// ✅ Built-in templates (seeded as version 1)
+// -------------------------
+const BUILTIN_TEMPLATES: [(&str, &str, &str); 10] = [
+    // LLM prompts used by drafts.rs
+    (
+        "draft_system",
+        r#"{"agent.name":"text","agent.role":"text","agent.goal":"text","style_guide":"text","kind":"text"}"#,
+        "You are \"{agent.name}\", acting as: {agent.role}.\nYour goal: {agent.goal}\n\nHouse style guide:\n{style_guide}\n\nReply with the {kind} text only: no preamble, no quotes, no explanations.",
+    ),
+    (
+        "agent_system",
+        r#"{"agent.name":"text","agent.role":"text","agent.goal":"text","style_guide":"text"}"#,
+        "You are \"{agent.name}\", acting as: {agent.role}.\nYour goal: {agent.goal}\n\nUse the tools to work toward the goal. Tools that publish (posts, comments) only queue the action for the user's approval, so do not ask for confirmation. Call tools one step at a time.\n\nHouse style guide for anything you write:\n{style_guide}\n\nWhen you are done, reply with a short summary of what you did (no tool call).",
+    ),
+    (
+        "draft_post",
+        r#"{"topics":"list"}"#,
+        "Trending topics today:\n{topics}\n\nWrite one LinkedIn post about the topic that best fits your goal. End with a question to invite comments.",
+    ),
+    (
+        "draft_comment",
+        r#"{"repo_url":"url"}"#,
+        "Write a short, genuine comment for LinkedIn posts under #openclaw that fits your goal.\nMention this repo once: {repo_url}",
+    ),
+    // Fixed post / comment texts (demo commands and LLM fallback)
+    (
+        "trend_digest_post",
+        r#"{"topics":"list"}"#,
+        "Today’s trends I’m watching 👇\n\n{topics}\n\nCurious: which one will dominate 2026?\n#openclaw #automation #ai",
+    ),
+    (
+        "trend_spotlight_post",
+        r#"{"topic":"text"}"#,
+        "🚀 Trending today: {topic}\n\nI’m building a Tauri desktop assistant that uses OpenClaw + browser automation to run daily workflows.\n\nWhat’s one automation you wish your desktop could do for you?",
+    ),
+    (
+        "trending_post",
+        r#"{"topic":"text"}"#,
+        "🚀 Today’s OpenClaw trend: **{topic}**
+
+What’s interesting is how fast “agentic workflows” are moving from experiments to real desktop automation:
+✅ local-first LLM routing
+✅ approvals before risky actions
+✅ browser automation (no APIs needed)
+✅ scheduled repeatability
+
+If you’re building with OpenClaw, what’s the most useful agent you’ve made so far?
+
+#openclaw #automation #aiagents #productivity",
+    ),
+    (
+        "repo_promo_comment",
+        r#"{"repo_url":"url"}"#,
+        "Just shipped a desktop automation assistant using OpenClaw + Tauri 🚀\nTry it if you’re non-technical too — it’s chat-first.\nRepo: {repo_url}\n#openclaw",
+    ),
+    (
+        "repo_share_comment",
+        r#"{"repo_url":"url"}"#,
+        "🚀 Quick share: I just shipped a desktop automation assistant built with Tauri + OpenClaw.\nRepo: {repo_url}\nIf you're non-technical, you can still use it — it’s chat-based + does browser automation for you.",
+    ),
+    (
+        "hashtag_comment",
+        r#"{"repo_url":"url"}"#,
+        "Hey! I just shipped a new OpenClaw-powered desktop assistant (Tauri) that automates LinkedIn actions via browser automation (not API). Repo: {repo_url} 🚀
+
+If you’re non-technical and want to try it, tell me — I’ll share a quick setup guide.",
+    ),
+];
+
+pub fn ensure_prompt_templates_table(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS prompt_templates (
+            name TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            body TEXT NOT NULL,
+            variables_json TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (name, version)
+        )",
+        [],
+    );
+
+    for (name, variables, body) in BUILTIN_TEMPLATES {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO prompt_templates (name, version, body, variables_json, created_at)
+             VALUES (?1, 1, ?2, ?3, datetime('now'))",
+            params![name, body, variables],
+        );
+    }
+}
+
+fn parse_variables(json: &str) -> Result<Vec<(String, VarType)>, String> {
+    let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)
+        .map_err(|e| format!("❌ Variables must be a JSON object like {{\"topic\":\"text\"}}: {}", e))?;
+
+    map.into_iter()
+        .map(|(name, ty)| {
+            if !is_var_name(&name) {
+                return Err(format!("❌ Invalid variable name '{}'", name));
+            }
+            let ty = ty.as_str().ok_or_else(|| format!("❌ Type of '{}' must be a string", name))?;
+            Ok((name, VarType::parse(ty)?))
+        })
+        .collect()
+}
+
+fn variables_to_json(vars: &[(String, VarType)]) -> String {
+    let map: serde_json::Map<String, serde_json::Value> = vars
+        .iter()
+        .map(|(n, t)| (n.clone(), serde_json::Value::String(t.as_str().to_string())))
+        .collect();
+    serde_json::Value::Object(map).to_string()
+}
+
+fn describe_variables(vars: &[(String, VarType)]) -> String {
+    if vars.is_empty() {
+        return "(none)".to_string();
+    }
+    vars.iter()
+        .map(|(n, t)| format!("{}:{}", n, t.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// "name" => latest version, "name@3" => version 3
+fn split_reference(reference: &str) -> Result<(String, Option<i64>), String> {
+    let reference = reference.trim();
+    match reference.split_once('@') {
+        Some((name, v)) => {
+            let v = v
+                .trim()
+                .trim_start_matches('v')
+                .parse::<i64>()
+                .map_err(|_| format!("❌ Invalid template version in '{}'", reference))?;
+            Ok((name.trim().to_string(), Some(v)))
+        }
+        None => Ok((reference.to_string(), None)),
+    }
+}
+
+pub fn load_template(conn: &Connection, reference: &str) -> Result<Template, String> {
+    ensure_prompt_templates_table(conn);
+    let (name, version) = split_reference(reference)?;
+
+    let row: Option<(i64, String, String)> = match version {
+        Some(v) => conn.query_row(
+            "SELECT version, body, variables_json FROM prompt_templates WHERE name=?1 AND version=?2",
+            params![name, v],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        ),
+        None => conn.query_row(
+            "SELECT version, body, variables_json FROM prompt_templates WHERE name=?1
+             ORDER BY version DESC LIMIT 1",
+            [&name],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        ),
+    }
+    .optional()
+    .map_err(|e| format!("DB query failed: {}", e))?;
+
+    let (version, body, variables_json) =
+        row.ok_or_else(|| format!("❌ Prompt template '{}' not found.", reference.trim()))?;
+
+    Ok(Template {
+        name,
+        version,
+        body,
+        variables: parse_variables(&variables_json)?,
+    })
+}
+
+pub fn render_template(reference: &str, vars: &PromptVars) -> Result<String, String> {
+    let conn = open_db()?;
+    load_template(&conn, reference)?.render(vars)
+}
+
+// Renders the built-in (version 1) text without the DB, for offline evals
+#[cfg(test)]
+pub fn render_builtin(name: &str, vars: &PromptVars) -> Result<String, String> {
+    let (name, variables, body) = BUILTIN_TEMPLATES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .ok_or_else(|| format!("❌ Built-in template '{}' not found.", name))?;
+
+    Template {
+        name: name.to_string(),
+        version: 1,
+        body: body.to_string(),
+        variables: parse_variables(variables)?,
+    }
+    .render(vars)
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// Adds a new version. `variables` is optional JSON like {"topic":"text","repo_url":"url"};
+// undeclared placeholders get a type from their name (…url => url, topics => list).
+redacted! {
+    #[tauri::command]
+    pub fn save_prompt_template(name: String, body: String, variables: Option<String>) -> Result<String, String> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err("❌ Template name may only use letters, digits, '_' and '-'.".to_string());
+        }
+        if body.trim().is_empty() {
+            return Err("❌ Template body is empty.".to_string());
+        }
+
+        let used = placeholders(&body);
+        let explicit = variables.as_deref().map(str::trim).filter(|v| !v.is_empty());
+        let mut declared = match explicit {
+            Some(json) => parse_variables(json)?,
+            None => vec![],
+        };
+
+        let undeclared: Vec<String> = used
+            .iter()
+            .filter(|u| !declared.iter().any(|(n, _)| n == *u))
+            .cloned()
+            .collect();
+        if explicit.is_some() && !undeclared.is_empty() {
+            return Err(format!("❌ Placeholders not declared in variables: {}", undeclared.join(", ")));
+        }
+        for name in undeclared {
+            let ty = VarType::infer(&name);
+            declared.push((name, ty));
+        }
+
+        let conn = open_db()?;
+        ensure_prompt_templates_table(&conn);
+
+        let version: i64 = conn
+            .query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM prompt_templates WHERE name=?1",
+                [&name],
+                |r| r.get(0),
+            )
+            .map_err(|e| format!("DB query failed: {}", e))?;
+
+        conn.execute(
+            "INSERT INTO prompt_templates (name, version, body, variables_json, created_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![name, version, body, variables_to_json(&declared)],
+        )
+        .map_err(|e| format!("DB insert failed: {}", e))?;
+
+        write_log("INFO", &format!("Prompt template saved: {} v{}", name, version));
+        Ok(format!(
+            "✅ Saved template '{}' v{}\nVariables: {}",
+            name,
+            version,
+            describe_variables(&declared)
+        ))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn list_prompt_templates() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_prompt_templates_table(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT name, MAX(version), MAX(created_at) FROM prompt_templates
+             GROUP BY name ORDER BY name",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, i64>(1)?,
+                    r.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = String::from("🧾 Prompt templates:\n\n");
+        for (name, version, updated) in rows.flatten() {
+            let vars = load_template(&conn, &name)
+                .map(|t| describe_variables(&t.variables))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "- {} (v{}, {})\n   variables: {}\n",
+                name, version, updated, vars
+            ));
+        }
+
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn show_prompt_template(name: String) -> Result<String, String> {
+        let conn = open_db()?;
+        let t = load_template(&conn, &name)?;
+
+        let versions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM prompt_templates WHERE name=?1", [&t.name], |r| r.get(0))
+            .unwrap_or(1);
+
+        Ok(format!(
+            "🧾 {} v{} ({} version(s))\nVariables: {}\n\n{}",
+            t.name,
+            t.version,
+            versions,
+            describe_variables(&t.variables),
+            t.body
+        ))
+    }
+}
+
+// Preview: renders with JSON values, e.g. {"topic":"AI agents"}
+redacted! {
+    #[tauri::command]
+    pub fn render_prompt_template(name: String, variables: Option<String>) -> Result<String, String> {
+        let vars = match variables.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            Some(json) => PromptVars::from_json(json)?,
+            None => PromptVars::new(),
+        };
+        render_template(&name, &vars)
+    }
+}
+
+// template=None => agent uses the default prompt for the draft kind
+redacted! {
+    #[tauri::command]
+    pub fn set_agent_template(agent_name: String, template: Option<String>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_agents_table(&conn);
+
+        let template = template.map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty());
+        if let Some(reference) = &template {
+            load_template(&conn, reference)?;
+        }
+
+        // latest agent with that name, like every other agent command
+        let agent_id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [agent_name.trim()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+
+        conn.execute(
+            "UPDATE agents SET prompt_template=?1 WHERE id=?2",
+            params![template, agent_id],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log_agent(
+            "INFO",
+            &agent_id,
+            &format!(
+                "Agent '{}' prompt template set to {}",
+                agent_name.trim(),
+                template.as_deref().unwrap_or("default")
+            ),
+        );
+
+        Ok(match template {
+            Some(t) => format!("✅ Agent '{}' now drafts with template '{}'", agent_name.trim(), t),
+            None => format!("✅ Agent '{}' uses the default prompt templates", agent_name.trim()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(body: &str, variables: &str) -> Template {
+        Template {
+            name: "t".to_string(),
+            version: 1,
+            body: body.to_string(),
+            variables: parse_variables(variables).unwrap(),
+        }
+    }
+
+    #[test]
+    fn missing_and_empty_variables_are_reported_once() {
+        let t = template("{topic} and {topic} by {author}", r#"{"topic":"text","author":"text"}"#);
+        let err = t.render(&PromptVars::new().text("author", "   ")).unwrap_err();
+        assert!(err.contains("missing variables: topic, author"), "{}", err);
+
+        let ok = t.render(&PromptVars::new().text("topic", " Rust ").text("author", "Ana")).unwrap();
+        assert_eq!(ok, "Rust and Rust by Ana");
+    }
+
+    #[test]
+    fn typed_variables_are_checked_and_formatted() {
+        let t = template("{repo_url} | {count} | {topics}", r#"{"repo_url":"url","count":"number","topics":"list"}"#);
+        let topics = vec!["AI agents".to_string(), " ".to_string(), "Tauri".to_string()];
+
+        let ok = t
+            .render(
+                &PromptVars::new()
+                    .text("repo_url", "https://github.com/x/y")
+                    .text("count", "3")
+                    .list("topics", &topics),
+            )
+            .unwrap();
+        assert_eq!(ok, "https://github.com/x/y | 3 | 1. AI agents\n2. Tauri");
+
+        let err = t
+            .render(
+                &PromptVars::new()
+                    .text("repo_url", "github.com/x/y")
+                    .text("count", "three")
+                    .list("topics", &topics),
+            )
+            .unwrap_err();
+        assert!(err.contains("repo_url must be an http(s) URL"), "{}", err);
+        assert!(err.contains("count must be a number"), "{}", err);
+
+        // undeclared placeholders are typed by name
+        let inferred = template("{homepage_url}", "{}");
+        assert!(inferred.render(&PromptVars::new().text("homepage_url", "not a url")).is_err());
+    }
+
+    #[test]
+    fn agent_paths_and_literal_braces() {
+        let agent = AgentProfile {
+            id: "a1".to_string(),
+            name: "Growth".to_string(),
+            role: "marketer".to_string(),
+            goal: "grow the repo".to_string(),
+            prompt_template: None,
+        };
+        let t = template(r#"Goal: {agent.goal} {{literal}} {"json": 1} {.bad}"#, "{}");
+        let out = t.render(&PromptVars::new().agent(&agent)).unwrap();
+        assert_eq!(out, r#"Goal: grow the repo {literal} {"json": 1} {.bad}"#);
+        assert_eq!(placeholders("{agent.goal} {{x}} {topic}"), vec!["agent.goal", "topic"]);
+    }
+
+    #[test]
+    fn references_resolve_latest_or_pinned_versions() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_prompt_templates_table(&conn);
+        conn.execute(
+            "INSERT INTO prompt_templates (name, version, body, variables_json, created_at)
+             VALUES ('draft_post', 2, 'v2: {topics}', '{\"topics\":\"list\"}', datetime('now'))",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(load_template(&conn, "draft_post").unwrap().version, 2);
+        assert_eq!(load_template(&conn, " draft_post@1 ").unwrap().version, 1);
+        let pinned = load_template(&conn, "draft_post@v2").unwrap();
+        assert_eq!(pinned.body, "v2: {topics}");
+        assert!(pinned.variables.iter().any(|(n, t)| n == "topics" && *t == VarType::List));
+
+        assert!(load_template(&conn, "draft_post@9").err().unwrap_or_default().contains("not found"));
+        assert!(load_template(&conn, "draft_post@x").err().unwrap_or_default().contains("Invalid template version"));
+        assert!(load_template(&conn, "nope").is_err());
+    }
+}
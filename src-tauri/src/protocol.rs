@@ -0,0 +1,305 @@
+// -------------------------
+// ✅ Script protocol (automation/protocol.js)
+// Scripts print one JSON object per stdout line: progress, warning, result
+// or error (with a code). Other lines are plain log output. A run ends as a
+// ScriptResult or a ScriptError whose code callers can react to, e.g. ask
+// for `linkedin login` on SESSION_EXPIRED.
+// -------------------------
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::redact;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    SessionExpired,
+    SelectorNotFound,
+    Timeout,
+    InvalidArgs,
+    // set by the runner, never sent by scripts
+    Cancelled,
+    #[serde(other)]
+    Failed,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::SessionExpired => "SESSION_EXPIRED",
+            ErrorCode::SelectorNotFound => "SELECTOR_NOT_FOUND",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::InvalidArgs => "INVALID_ARGS",
+            ErrorCode::Cancelled => "CANCELLED",
+            ErrorCode::Failed => "FAILED",
+        }
+    }
+
+    // what the user can do about it
+    fn hint(&self) -> Option<&'static str> {
+        match self {
+            ErrorCode::SessionExpired => Some("🔑 Log in again with: linkedin login"),
+            ErrorCode::SelectorNotFound => Some("LinkedIn's page layout may have changed; the script needs an update."),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Progress {
+        message: String,
+        #[serde(default)]
+        step: Option<String>,
+    },
+    Warning {
+        message: String,
+    },
+    Result {
+        #[serde(default)]
+        message: Option<String>,
+        #[serde(default)]
+        data: Value,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+impl Message {
+    // (kind, text) as streamed / stored in the run transcript
+    pub fn display(&self) -> (&'static str, String) {
+        match self {
+            Message::Progress { message, .. } => ("progress", message.clone()),
+            Message::Warning { message } => ("warning", message.clone()),
+            Message::Result { message, .. } => ("result", message.clone().unwrap_or_default()),
+            Message::Error { code, message } => ("error", format!("{}: {}", code.as_str(), message)),
+        }
+    }
+
+    // Secrets masked in every text the run stores or streams; parse first, then
+    // redact, so a masked cookie never breaks the JSON
+    pub fn redacted(self) -> Self {
+        match self {
+            Message::Progress { message, step } => Message::Progress {
+                message: redact::redact(&message),
+                step: step.map(|s| redact::redact(&s)),
+            },
+            Message::Warning { message } => Message::Warning {
+                message: redact::redact(&message),
+            },
+            Message::Result { message, data } => Message::Result {
+                message: message.map(|m| redact::redact(&m)),
+                data: redact::redact_value(&data),
+            },
+            Message::Error { code, message } => Message::Error {
+                code,
+                message: redact::redact(&message),
+            },
+        }
+    }
+}
+
+// None => not a protocol line (plain script output)
+pub fn parse_line(line: &str) -> Option<Message> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+#[derive(Debug)]
+pub struct ScriptResult {
+    pub run_id: String,
+    pub message: String,
+    pub data: Value,
+    pub warnings: Vec<String>,
+    // why nothing was published (set by the runner)
+    pub dry_run: Option<String>,
+}
+
+impl ScriptResult {
+    // dry-run screenshot reported by the script
+    pub fn screenshot(&self) -> Option<&str> {
+        self.data["screenshot"].as_str()
+    }
+}
+
+impl fmt::Display for ScriptResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(reason) = &self.dry_run {
+            writeln!(f, "🧪 DRY RUN ({}) — nothing was published.", reason)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(s) = self.screenshot() {
+            write!(f, "\n📸 {}", s)?;
+        }
+        for w in &self.warnings {
+            write!(f, "\n⚠️ {}", w)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ScriptError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ScriptError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ScriptError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Failed, message)
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(h) = self.code.hint() {
+            write!(f, "\n{}", h)?;
+        }
+        Ok(())
+    }
+}
+
+// commands keep returning Result<String, String>
+impl From<ScriptError> for String {
+    fn from(e: ScriptError) -> String {
+        e.to_string()
+    }
+}
+
+// Exit status + protocol messages + plain output => typed outcome.
+// Without a result line the plain stdout is the message (older scripts).
+pub fn outcome(
+    run_id: &str,
+    success: bool,
+    messages: Vec<Message>,
+    stdout: &str,
+    stderr: &str,
+) -> Result<ScriptResult, ScriptError> {
+    let mut warnings = vec![];
+    let mut result = None;
+    let mut error = None;
+
+    for m in messages {
+        match m {
+            Message::Warning { message } => warnings.push(message),
+            Message::Result { message, data } => result = Some((message, data)),
+            Message::Error { code, message } => error = Some(ScriptError::new(code, message)),
+            Message::Progress { .. } => {}
+        }
+    }
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if !success {
+        let detail = if stderr.trim().is_empty() { stdout } else { stderr };
+        return Err(ScriptError::failed(format!("Node script failed:\n{}", detail.trim())));
+    }
+
+    let (message, data) = result.unwrap_or((None, Value::Null));
+    let message = message
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| Some(stdout.trim().to_string()).filter(|s| !s.is_empty()))
+        .unwrap_or_else(|| "✅ Done.".to_string());
+
+    Ok(ScriptResult {
+        run_id: run_id.to_string(),
+        message,
+        data,
+        warnings,
+        dry_run: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_protocol_lines_and_skips_plain_output() {
+        assert_eq!(
+            parse_line(r#"{"type":"progress","message":"Typing","step":"type"}"#),
+            Some(Message::Progress {
+                message: "Typing".to_string(),
+                step: Some("type".to_string())
+            })
+        );
+        assert_eq!(
+            parse_line(r#"{"type":"error","code":"SESSION_EXPIRED","message":"gone"}"#),
+            Some(Message::Error {
+                code: ErrorCode::SessionExpired,
+                message: "gone".to_string()
+            })
+        );
+        assert_eq!(parse_line("✅ Posted to LinkedIn successfully."), None);
+        assert_eq!(parse_line("{not json"), None);
+    }
+
+    #[test]
+    fn unknown_error_codes_become_failed() {
+        let m = parse_line(r#"{"type":"error","code":"CAPTCHA","message":"blocked"}"#).unwrap();
+        assert!(matches!(m, Message::Error { code: ErrorCode::Failed, .. }));
+    }
+
+    #[test]
+    fn error_line_wins_over_exit_code() {
+        let messages = vec![
+            parse_line(r#"{"type":"warning","message":"slow"}"#).unwrap(),
+            parse_line(r#"{"type":"error","code":"SELECTOR_NOT_FOUND","message":"locator.click: Timeout"}"#).unwrap(),
+        ];
+        let err = outcome("r1", false, messages, "", "stack trace").unwrap_err();
+        assert_eq!(err.code, ErrorCode::SelectorNotFound);
+        assert!(err.to_string().contains("layout may have changed"));
+    }
+
+    #[test]
+    fn result_message_and_warnings_on_success() {
+        let messages = vec![
+            parse_line(r#"{"type":"warning","message":"No comment buttons found."}"#).unwrap(),
+            parse_line(r#"{"type":"result","message":"Done","data":{"commented":0}}"#).unwrap(),
+        ];
+        let ok = outcome("r1", true, messages, "", "").unwrap();
+        assert_eq!(ok.data["commented"], 0);
+        assert_eq!(ok.to_string(), "Done\n⚠️ No comment buttons found.");
+    }
+
+    #[test]
+    fn dry_run_result_says_nothing_was_published() {
+        let messages = vec![parse_line(
+            r#"{"type":"result","message":"Ready","data":{"dry_run":true,"screenshot":"/tmp/a.png"}}"#,
+        )
+        .unwrap()];
+        let mut ok = outcome("r1", true, messages, "", "").unwrap();
+        ok.dry_run = Some("agent sandbox".to_string());
+        assert_eq!(
+            ok.to_string(),
+            "🧪 DRY RUN (agent sandbox) — nothing was published.\nReady\n📸 /tmp/a.png"
+        );
+    }
+
+    #[test]
+    fn plain_scripts_still_work() {
+        let ok = outcome("r1", true, vec![], "✅ Posted.\n", "").unwrap();
+        assert_eq!(ok.message, "✅ Posted.");
+
+        let err = outcome("r1", false, vec![], "", "boom").unwrap_err();
+        assert_eq!(err.code, ErrorCode::Failed);
+        assert!(err.message.ends_with("boom"));
+    }
+}
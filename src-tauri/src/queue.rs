@@ -0,0 +1,307 @@
+// -------------------------
+// ✅ Browser job queue
+// Every automation run waits here for a slot before node starts:
+// - at most user_settings.max_browsers runs at once (default 2)
+// - one run per account at a time (two browsers on one session corrupt it)
+// - waiting jobs start by priority (login > started by the user > scheduler),
+//   then in arrival order; a job whose account is busy doesn't hold up others
+// Cancelling a queued run (cancel_run) drops it without starting.
+// -------------------------
+use std::sync::{Mutex, OnceLock};
+
+use rusqlite::{params, Connection};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::{ensure_column, ensure_user_settings_table, open_db, write_log};
+
+const DEFAULT_MAX_BROWSERS: usize = 2;
+const MAX_BROWSERS_LIMIT: i64 = 8;
+
+// declared low -> high; higher starts first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Scheduled,
+    User,
+    Interactive,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Scheduled => "scheduled",
+            Priority::User => "user",
+            Priority::Interactive => "interactive",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub run_id: String,
+    pub script: String,
+    // lock key; None => no session, only the global limit applies
+    pub account_id: Option<String>,
+    // "linkedin/default", for list_jobs
+    pub account_name: Option<String>,
+    pub priority: Priority,
+}
+
+struct Entry {
+    job: Job,
+    seq: u64,
+    since: String,
+}
+
+struct Queue {
+    waiting: Vec<Entry>,
+    running: Vec<Entry>,
+    next_seq: u64,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue::new());
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+impl Queue {
+    const fn new() -> Self {
+        Queue {
+            waiting: Vec::new(),
+            running: Vec::new(),
+            next_seq: 0,
+        }
+    }
+
+    fn push(&mut self, job: Job) {
+        self.next_seq += 1;
+        self.waiting.push(Entry {
+            job,
+            seq: self.next_seq,
+            since: chrono::Local::now().format("%H:%M:%S").to_string(),
+        });
+        // start order: priority, then arrival
+        self.waiting
+            .sort_by(|a, b| b.job.priority.cmp(&a.job.priority).then(a.seq.cmp(&b.seq)));
+    }
+
+    // run ids of the waiting jobs that may start now, in start order
+    fn startable(&self, max: usize) -> Vec<String> {
+        let mut busy: Vec<&str> = self
+            .running
+            .iter()
+            .filter_map(|e| e.job.account_id.as_deref())
+            .collect();
+        let mut free = max.saturating_sub(self.running.len());
+        let mut out = vec![];
+
+        for e in &self.waiting {
+            if free == 0 {
+                break;
+            }
+            if let Some(account) = e.job.account_id.as_deref() {
+                if busy.contains(&account) {
+                    continue;
+                }
+                busy.push(account);
+            }
+            out.push(e.job.run_id.clone());
+            free -= 1;
+        }
+        out
+    }
+
+    fn start(&mut self, run_id: &str, max: usize) -> bool {
+        if !self.startable(max).iter().any(|id| id == run_id) {
+            return false;
+        }
+        let Some(i) = self.waiting.iter().position(|e| e.job.run_id == run_id) else {
+            return false;
+        };
+        let mut entry = self.waiting.remove(i);
+        entry.since = chrono::Local::now().format("%H:%M:%S").to_string();
+        self.running.push(entry);
+        true
+    }
+
+    fn remove(&mut self, run_id: &str) {
+        self.waiting.retain(|e| e.job.run_id != run_id);
+        self.running.retain(|e| e.job.run_id != run_id);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Queue> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn ensure_queue_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    ensure_column(conn, "user_settings", "max_browsers INTEGER NULL");
+}
+
+fn max_browsers() -> usize {
+    open_db()
+        .ok()
+        .and_then(|conn| {
+            ensure_queue_settings(&conn);
+            conn.query_row("SELECT max_browsers FROM user_settings WHERE id=1", [], |r| {
+                r.get::<_, Option<i64>>(0)
+            })
+            .ok()
+            .flatten()
+        })
+        .filter(|n| (1..=MAX_BROWSERS_LIMIT).contains(n))
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_MAX_BROWSERS)
+}
+
+// Held while the browser runs; dropping it frees the slot and the account
+pub struct Slot {
+    run_id: String,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        lock().remove(&self.run_id);
+        changed().notify_waiters();
+    }
+}
+
+// Waits for a slot; None => cancelled while queued
+pub async fn acquire(job: Job, cancel: &CancellationToken) -> Option<Slot> {
+    let run_id = job.run_id.clone();
+    lock().push(job);
+
+    loop {
+        // registered before checking, so a slot freed in between still wakes us
+        let notified = changed().notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        let max = max_browsers();
+        if lock().start(&run_id, max) {
+            return Some(Slot { run_id });
+        }
+
+        tokio::select! {
+            _ = &mut notified => {}
+            _ = cancel.cancelled() => {
+                lock().remove(&run_id);
+                changed().notify_waiters();
+                return None;
+            }
+        }
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+redacted! {
+    #[tauri::command]
+    pub fn list_jobs() -> Result<String, String> {
+        let max = max_browsers();
+        let q = lock();
+
+        let line = |e: &Entry| {
+            format!(
+                "{} | {} | {}{} | since {}",
+                e.job.run_id,
+                e.job.script,
+                e.job.priority.as_str(),
+                e.job.account_name.as_deref().map(|a| format!(" | {}", a)).unwrap_or_default(),
+                e.since
+            )
+        };
+
+        let mut out = format!("🧵 Browser jobs ({}/{} running):\n", q.running.len(), max);
+        if q.running.is_empty() && q.waiting.is_empty() {
+            out.push_str("\n(idle)");
+            return Ok(out);
+        }
+        for e in &q.running {
+            out.push_str(&format!("\n▶️ {}", line(e)));
+        }
+        if !q.waiting.is_empty() {
+            out.push_str("\n\nQueued (start order):");
+            for (i, e) in q.waiting.iter().enumerate() {
+                out.push_str(&format!("\n{}. ⏳ {}", i + 1, line(e)));
+            }
+        }
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn set_max_browsers(max: i64) -> Result<String, String> {
+        if !(1..=MAX_BROWSERS_LIMIT).contains(&max) {
+            return Err(format!("❌ Max browsers must be between 1 and {}.", MAX_BROWSERS_LIMIT));
+        }
+        let conn = open_db()?;
+        ensure_queue_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET max_browsers=?1, updated_at=datetime('now') WHERE id=1",
+            params![max],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        // a higher limit may let queued jobs start now
+        changed().notify_waiters();
+        write_log("INFO", &format!("Max concurrent browsers: {}", max));
+        Ok(format!("✅ Up to {} browser(s) at once (one per account).", max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, account: Option<&str>, priority: Priority) -> Job {
+        Job {
+            run_id: id.to_string(),
+            script: "linkedin_post".to_string(),
+            account_id: account.map(|a| a.to_string()),
+            account_name: None,
+            priority,
+        }
+    }
+
+    #[test]
+    fn one_run_per_account_and_priority_first() {
+        let mut q = Queue::new();
+        q.push(job("sched", Some("acc-a"), Priority::Scheduled));
+        q.push(job("user-a", Some("acc-a"), Priority::User));
+        q.push(job("user-b", Some("acc-b"), Priority::User));
+        q.push(job("login-a", Some("acc-a"), Priority::Interactive));
+
+        // login first; acc-a is then taken, so user-b goes next
+        assert_eq!(q.startable(4), vec!["login-a", "user-b"]);
+        assert_eq!(q.startable(1), vec!["login-a"]);
+
+        assert!(!q.start("user-a", 4));
+        assert!(q.start("login-a", 4));
+        assert_eq!(q.startable(4), vec!["user-b"]);
+
+        // finished => the next acc-a job in priority order
+        q.remove("login-a");
+        assert_eq!(q.startable(4), vec!["user-a", "user-b"]);
+    }
+
+    #[test]
+    fn global_limit_counts_running_jobs() {
+        let mut q = Queue::new();
+        q.push(job("a", Some("acc-a"), Priority::User));
+        q.push(job("b", Some("acc-b"), Priority::User));
+        q.push(job("c", None, Priority::User));
+
+        assert!(q.start("a", 2));
+        assert_eq!(q.startable(2), vec!["b"]);
+        assert!(q.start("b", 2));
+        assert!(q.startable(2).is_empty());
+        assert!(!q.start("c", 2));
+    }
+}
@@ -0,0 +1,856 @@
+// -------------------------
+// ✅ Automation runner (node / Playwright scripts)
+// Async tokio child process with the timeout from the script's manifest. Timeout or
+// `cancel_run` kills the whole process tree (node + the browser it started).
+// Every run is a row in `runs`: pid, start/end, exit code, outcome.
+// Outcomes: running | success | failed | timeout | cancelled | interrupted
+// Output follows the line protocol in protocol.rs (automation/protocol.js).
+// stdout/stderr are streamed line by line as RUN_OUTPUT_EVENT (tagged with
+// the run id) and kept in `run_output`, so the UI can reattach to a run.
+// Dry run (global setting, agent sandbox, or per run): the script does
+// everything except the final submit and reports a screenshot instead.
+// Screenshots and failure traces go to a per-run folder (artifacts.rs).
+// Browser engine / headless / viewport come from browser.rs.
+// Scripts that use a session run with one account's session (accounts.rs)
+// and get a pre-flight check first (session_health.rs).
+// Runs then wait in the job queue (queue.rs) for a browser slot and their
+// account: status queued -> running.
+// -------------------------
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::protocol::{self, ErrorCode, Message, ScriptError, ScriptResult};
+use crate::queue::Priority;
+use crate::{
+    accounts, artifacts, automations, browser, queue, session_health, clean_ansi, ensure_agents_table, ensure_column, ensure_user_settings_table, open_db, redact,
+    secrets, write_log, write_log_with_agent,
+};
+
+pub const RUN_STATUS_EVENT: &str = "automation-run";
+pub const RUN_OUTPUT_EVENT: &str = "automation-output";
+
+static ACTIVE: Mutex<Option<HashMap<String, CancellationToken>>> = Mutex::new(None);
+// set once in main's setup; runs started before that are simply not streamed
+static APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub agent_id: Option<String>,
+    // Some(true) => dry run for this run (global / sandbox settings still apply)
+    pub dry_run: Option<bool>,
+    // started by the scheduler => headless in browser mode "auto"
+    pub scheduled: bool,
+    // account whose session is used (None => the agent's account, else the default)
+    pub account_id: Option<String>,
+    // queue order; None => interactive scripts, then user runs, then scheduled
+    pub priority: Option<Priority>,
+}
+
+impl RunOptions {
+    pub fn agent(agent_id: &str) -> Self {
+        RunOptions {
+            agent_id: Some(agent_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn dry_run(mut self, dry_run: Option<bool>) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn scheduled(mut self, scheduled: bool) -> Self {
+        self.scheduled = scheduled;
+        self
+    }
+
+    pub fn account(mut self, account_id: Option<String>) -> Self {
+        self.account_id = account_id;
+        self
+    }
+
+    pub fn priority(mut self, priority: Option<Priority>) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct RunStatus {
+    pub run_id: String,
+    pub script: String,
+    pub agent_id: Option<String>,
+    pub status: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RunOutput {
+    pub run_id: String,
+    // 1-based, per run; the UI skips lines it already has after reattaching
+    pub seq: i64,
+    pub stream: &'static str,
+    pub line: String,
+}
+
+pub fn init(app: tauri::AppHandle) {
+    let _ = APP.set(app);
+}
+
+pub fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+fn emit_status(run_id: &str, script: &str, agent_id: Option<&str>, status: &str) {
+    emit(
+        RUN_STATUS_EVENT,
+        RunStatus {
+            run_id: run_id.to_string(),
+            script: script.to_string(),
+            agent_id: agent_id.map(|s| s.to_string()),
+            status: status.to_string(),
+        },
+    );
+}
+
+pub fn ensure_runs_table(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS runs (
+            id TEXT PRIMARY KEY,
+            script TEXT NOT NULL,
+            agent_id TEXT NULL,
+            pid INTEGER NULL,
+            status TEXT NOT NULL,
+            exit_code INTEGER NULL,
+            error TEXT NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT NULL
+        )",
+        [],
+    );
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_runs_started ON runs (started_at)", []);
+    ensure_column(conn, "runs", "dry_run INTEGER NULL");
+    // protocol outcome: error code on failure, result data on success
+    ensure_column(conn, "runs", "error_code TEXT NULL");
+    ensure_column(conn, "runs", "result_json TEXT NULL");
+    // artifacts folder + JSON list of the files in it when the run ended
+    ensure_column(conn, "runs", "artifacts_dir TEXT NULL");
+    ensure_column(conn, "runs", "artifacts_json TEXT NULL");
+    ensure_column(conn, "runs", "account_id TEXT NULL");
+    // started_at is reset when a queued run gets its browser slot
+    ensure_column(conn, "runs", "queued_at TEXT NULL");
+
+    // transcript: one row per output line
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS run_output (
+            run_id TEXT NOT NULL,
+            seq INTEGER NOT NULL,
+            stream TEXT NOT NULL,
+            line TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (run_id, seq)
+        )",
+        [],
+    );
+}
+
+pub fn ensure_runner_settings(conn: &Connection) {
+    ensure_user_settings_table(conn);
+    // 1 => every automation run is a dry run
+    ensure_column(conn, "user_settings", "dry_run INTEGER NULL");
+}
+
+fn global_dry_run(conn: &Connection) -> bool {
+    ensure_runner_settings(conn);
+    conn.query_row("SELECT dry_run FROM user_settings WHERE id=1", [], |r| r.get::<_, Option<i64>>(0))
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+        != 0
+}
+
+// Why this run is a dry run (None => it publishes for real)
+fn dry_run_reason(opts: &RunOptions) -> Option<&'static str> {
+    if opts.dry_run == Some(true) {
+        return Some("this run");
+    }
+    let conn = open_db().ok()?;
+    if global_dry_run(&conn) {
+        return Some("global dry-run mode");
+    }
+    let agent_id = opts.agent_id.as_deref()?;
+    ensure_agents_table(&conn);
+    conn.query_row("SELECT sandbox FROM agents WHERE id=?1", [agent_id], |r| r.get::<_, i64>(0))
+        .ok()
+        .filter(|s| *s != 0)
+        .map(|_| "agent sandbox")
+}
+
+// A run still marked running (or queued) at startup died with the previous app process
+pub fn mark_interrupted_runs(conn: &Connection) {
+    ensure_runs_table(conn);
+    let _ = conn.execute(
+        "UPDATE runs SET status='interrupted', ended_at=datetime('now') WHERE status IN ('running', 'queued')",
+        [],
+    );
+}
+
+fn register(run_id: &str) -> CancellationToken {
+    let token = CancellationToken::new();
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(run_id.to_string(), token.clone());
+    token
+}
+
+fn unregister(run_id: &str) {
+    if let Some(map) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        map.remove(run_id);
+    }
+}
+
+fn start_row(
+    run_id: &str,
+    script: &str,
+    agent_id: Option<&str>,
+    account_id: Option<&str>,
+    dry_run: bool,
+    artifacts_dir: Option<&Path>,
+) -> Result<(), String> {
+    let conn = open_db()?;
+    ensure_runs_table(&conn);
+    conn.execute(
+        "INSERT INTO runs (id, script, agent_id, account_id, dry_run, artifacts_dir, status, queued_at, started_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'queued', datetime('now'), datetime('now'))",
+        params![
+            run_id,
+            script,
+            agent_id,
+            account_id,
+            dry_run,
+            artifacts_dir.map(|d| d.to_string_lossy().to_string())
+        ],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+    Ok(())
+}
+
+fn set_running(run_id: &str) {
+    if let Ok(conn) = open_db() {
+        let _ = conn.execute(
+            "UPDATE runs SET status='running', started_at=datetime('now') WHERE id=?1",
+            params![run_id],
+        );
+    }
+}
+
+fn set_pid(run_id: &str, pid: Option<u32>) {
+    if let Ok(conn) = open_db() {
+        let _ = conn.execute("UPDATE runs SET pid=?1 WHERE id=?2", params![pid, run_id]);
+    }
+}
+
+// node starts a browser; killing only node would leave it running
+async fn kill_tree(child: &mut tokio::process::Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(target_os = "windows")]
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output()
+            .await;
+
+        // the child leads its own process group (see run_script)
+        #[cfg(unix)]
+        let _ = Command::new("kill")
+            .args(["-TERM", &format!("-{}", pid)])
+            .output()
+            .await;
+    }
+    let _ = child.kill().await;
+}
+
+type Line = (&'static str, String);
+
+async fn pump(pipe: Option<impl tokio::io::AsyncRead + Unpin>, stream: &'static str, tx: mpsc::UnboundedSender<Line>) {
+    let Some(pipe) = pipe else { return };
+    let mut reader = BufReader::new(pipe);
+    let mut buf = vec![];
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                // redacted in collect, after protocol lines are parsed
+                let line = clean_ansi(&buf).trim_end_matches(['\r', '\n']).to_string();
+                if tx.send((stream, line)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Collected {
+    stdout: String,
+    stderr: String,
+    messages: Vec<Message>,
+}
+
+// Emits + stores every line until both pipes close. Protocol lines are
+// streamed as their kind (progress/warning/result/error) and message.
+// Secrets are masked here, in plain lines and in parsed messages alike.
+async fn collect(run_id: String, mut rx: mpsc::UnboundedReceiver<Line>) -> Collected {
+    let conn = open_db().ok();
+    let mut out = Collected::default();
+    let mut seq = 0;
+
+    while let Some((stream, line)) = rx.recv().await {
+        let (stream, line) = match (stream, protocol::parse_line(&line)) {
+            ("stdout", Some(m)) => {
+                let m = m.redacted();
+                let shown = m.display();
+                out.messages.push(m);
+                shown
+            }
+            ("stderr", _) => {
+                let line = redact::redact(&line);
+                out.stderr.push_str(&line);
+                out.stderr.push('\n');
+                (stream, line)
+            }
+            _ => {
+                let line = redact::redact(&line);
+                out.stdout.push_str(&line);
+                out.stdout.push('\n');
+                (stream, line)
+            }
+        };
+
+        seq += 1;
+        if let Some(conn) = &conn {
+            let _ = conn.execute(
+                "INSERT INTO run_output (run_id, seq, stream, line, created_at) VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+                params![run_id, seq, stream, line],
+            );
+        }
+
+        emit(
+            RUN_OUTPUT_EVENT,
+            RunOutput {
+                run_id: run_id.clone(),
+                seq,
+                stream,
+                line,
+            },
+        );
+    }
+    out
+}
+
+// Runs a registered automation (see automations.rs) with node and maps its
+// output to a typed outcome. Arguments are checked against the manifest first.
+pub async fn run_script(name: &str, args: Vec<String>, opts: &RunOptions) -> Result<ScriptResult, ScriptError> {
+    let agent_id = opts.agent_id.as_deref();
+    let registry = automations::load_registry();
+    let manifest = registry.get(name).map_err(ScriptError::failed)?.clone();
+    manifest
+        .validate_args(&args)
+        .map_err(|e| ScriptError::new(ErrorCode::InvalidArgs, format!("❌ {}", e)))?;
+
+    // scripts without side effects (login) simply run
+    let dry_run = dry_run_reason(opts).filter(|_| manifest.side_effects);
+    if let (Some(reason), false) = (dry_run, manifest.dry_run) {
+        return Err(ScriptError::new(
+            ErrorCode::InvalidArgs,
+            format!("⛔ Dry run is on ({}) but '{}' has no dry-run mode; not started.", reason, manifest.name),
+        ));
+    }
+
+    let account = match manifest.session.as_deref() {
+        Some(platform) => Some(
+            open_db()
+                .and_then(|conn| accounts::for_run(&conn, platform, opts.account_id.as_deref(), agent_id))
+                .map_err(ScriptError::failed)?,
+        ),
+        None => None,
+    };
+
+    let priority = opts.priority.unwrap_or(if manifest.interactive {
+        Priority::Interactive
+    } else if opts.scheduled {
+        Priority::Scheduled
+    } else {
+        Priority::User
+    });
+
+    // login writes the session and the check script is the pre-flight itself
+    // (before queueing: the check takes a slot of its own)
+    let preflight = account
+        .as_ref()
+        .filter(|_| !manifest.captures_session && manifest.name != session_health::CHECK_SCRIPT);
+    if let Some(account) = preflight {
+        if let Err(e) = session_health::preflight(account, priority).await {
+            write_log_with_agent("WARN", agent_id, &format!("{} not started: {}", manifest.name, e.message));
+            return Err(e);
+        }
+    }
+
+    let browser = browser::load_settings();
+    let headless = browser
+        .headless(manifest.interactive, opts.scheduled, browser::has_display())
+        .map_err(ScriptError::failed)?;
+
+    let script_path = registry.entry_path(&manifest);
+    if !script_path.exists() {
+        return Err(ScriptError::failed(format!("❌ Script not found: {}", script_path.display())));
+    }
+    let script = manifest.name.as_str();
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let artifacts_dir = artifacts::create_run_dir(&run_id);
+    start_row(
+        &run_id,
+        script,
+        agent_id,
+        account.as_ref().map(|a| a.id.as_str()),
+        dry_run.is_some(),
+        artifacts_dir.as_deref(),
+    )
+    .map_err(ScriptError::failed)?;
+
+    // wait for a browser slot + the account (cancel_run works while queued)
+    let cancel = register(&run_id);
+    emit_status(&run_id, script, agent_id, "queued");
+    let job = queue::Job {
+        run_id: run_id.clone(),
+        script: script.to_string(),
+        account_id: account.as_ref().map(|a| a.id.clone()),
+        account_name: account.as_ref().map(|a| a.display()),
+        priority,
+    };
+    let Some(_slot) = queue::acquire(job, &cancel).await else {
+        unregister(&run_id);
+        let err = ScriptError::new(ErrorCode::Cancelled, format!("⛔ Run {} cancelled while queued ({}).", run_id, script));
+        return finish(&run_id, script, agent_id, None, Err(err));
+    };
+    set_running(&run_id);
+
+    // Decrypted copy of the account's session, removed when this function returns.
+    // Taken with the account lock held, so a login in between can't be missed.
+    let session = match &account {
+        Some(a) => match secrets::SessionFile::prepare(&a.session_path) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                unregister(&run_id);
+                return finish(&run_id, script, agent_id, None, Err(ScriptError::failed(e)));
+            }
+        },
+        None => None,
+    };
+
+    let mut cmd = Command::new("node");
+    // ✅ Don't set current_dir at all (avoid Windows 267 issues)
+    cmd.arg(&script_path)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(session) = &session {
+        cmd.env("PERSONALIZ_AUTH_PATH", session.path());
+    }
+    if dry_run.is_some() {
+        cmd.env("PERSONALIZ_DRY_RUN", "1");
+    }
+    if let Some(dir) = &artifacts_dir {
+        cmd.env("PERSONALIZ_ARTIFACTS_DIR", dir);
+    }
+    cmd.envs(browser.env(headless));
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            unregister(&run_id);
+            let err = ScriptError::failed(format!("Failed running node: {}", e));
+            return finish(&run_id, script, agent_id, None, Err(err));
+        }
+    };
+
+    set_pid(&run_id, child.id());
+    emit_status(&run_id, script, agent_id, "running");
+    let timeout = manifest.timeout();
+    write_log_with_agent(
+        "INFO",
+        agent_id,
+        &format!(
+            "Run {} started: {}{} (pid {:?}, timeout {}s, {}){}",
+            run_id,
+            script,
+            account.as_ref().map(|a| format!(" as {}", a.display())).unwrap_or_default(),
+            child.id(),
+            timeout.as_secs(),
+            browser.describe(headless),
+            dry_run.map(|r| format!(" — DRY RUN ({})", r)).unwrap_or_default()
+        ),
+    );
+
+    // read both pipes while waiting, or a chatty script blocks on a full pipe
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(pump(child.stdout.take(), "stdout", tx.clone()));
+    tokio::spawn(pump(child.stderr.take(), "stderr", tx));
+    let output = tokio::spawn(collect(run_id.clone(), rx));
+
+    let waited = tokio::select! {
+        status = child.wait() => Some(status),
+        _ = tokio::time::sleep(timeout) => None,
+        _ = cancel.cancelled() => None,
+    };
+    unregister(&run_id);
+
+    let mut exit_code = None;
+    let result = match waited {
+        None => {
+            kill_tree(&mut child).await;
+            Err(if cancel.is_cancelled() {
+                ScriptError::new(ErrorCode::Cancelled, format!("⛔ Run {} cancelled ({}).", run_id, script))
+            } else {
+                ScriptError::new(
+                    ErrorCode::Timeout,
+                    format!("⏱ {} timed out after {}s and was stopped (run {}).", script, timeout.as_secs(), run_id),
+                )
+            })
+        }
+        Some(Err(e)) => Err(ScriptError::failed(format!("Failed waiting for node: {}", e))),
+        Some(Ok(status)) => {
+            exit_code = status.code();
+            // a leftover grandchild could keep the pipes open; don't wait on it forever
+            let out = tokio::time::timeout(Duration::from_secs(5), output)
+                .await
+                .ok()
+                .and_then(|r| r.ok())
+                .unwrap_or_default();
+            let result = protocol::outcome(&run_id, status.success(), out.messages, &out.stdout, &out.stderr);
+
+            // login writes a fresh session => encrypt it before the temp file goes away
+            match (result, &session) {
+                (Ok(r), Some(session)) if manifest.captures_session => {
+                    session.capture().map(|_| r).map_err(ScriptError::failed)
+                }
+                (other, _) => other,
+            }
+            .map(|r| ScriptResult {
+                dry_run: dry_run.map(|d| d.to_string()),
+                ..r
+            })
+        }
+    };
+
+    if let Some(account) = &account {
+        session_health::record_outcome(account, &result);
+    }
+
+    finish(&run_id, script, agent_id, exit_code, result)
+}
+
+// Files the script left in its artifacts folder; an empty folder is removed
+fn record_artifacts(conn: &Connection, run_id: &str) -> usize {
+    let dir = artifacts::run_dir(run_id);
+    artifacts::seal_trace(&dir);
+    let files = artifacts::files(&dir);
+    if files.is_empty() {
+        let _ = std::fs::remove_dir(&dir);
+        let _ = conn.execute("UPDATE runs SET artifacts_dir=NULL WHERE id=?1", params![run_id]);
+        return 0;
+    }
+    let list: Vec<String> = files.iter().map(|f| f.to_string_lossy().to_string()).collect();
+    let _ = conn.execute(
+        "UPDATE runs SET artifacts_json=?1 WHERE id=?2",
+        params![serde_json::to_string(&list).unwrap_or_default(), run_id],
+    );
+    files.len()
+}
+
+// runs row + artifacts + status event + log for the final outcome
+fn finish(
+    run_id: &str,
+    script: &str,
+    agent_id: Option<&str>,
+    exit_code: Option<i32>,
+    mut result: Result<ScriptResult, ScriptError>,
+) -> Result<ScriptResult, ScriptError> {
+    let (status, code, error, data) = match &result {
+        Ok(r) => ("success", None, None, Some(r.data.to_string())),
+        Err(e) => (
+            match e.code {
+                ErrorCode::Cancelled => "cancelled",
+                ErrorCode::Timeout => "timeout",
+                _ => "failed",
+            },
+            Some(e.code.as_str()),
+            Some(redact::redact(&e.message)),
+            None,
+        ),
+    };
+
+    let mut artifact_count = 0;
+    if let Ok(conn) = open_db() {
+        let _ = conn.execute(
+            "UPDATE runs SET status=?1, exit_code=?2, error_code=?3, error=?4, result_json=?5, ended_at=datetime('now')
+             WHERE id=?6",
+            params![status, exit_code, code, error, data, run_id],
+        );
+        artifact_count = record_artifacts(&conn, run_id);
+    }
+    // point at the screenshots / trace of a failed run
+    if let (Err(e), true) = (&mut result, artifact_count > 0) {
+        e.message.push_str(&format!("\n🗂 Screenshots/trace: artifacts {}", run_id));
+    }
+    emit_status(run_id, script, agent_id, status);
+
+    match &result {
+        Ok(r) => write_log_with_agent(
+            "INFO",
+            agent_id,
+            &format!(
+                "Run {} finished: {}{} ({} artifacts)",
+                run_id,
+                script,
+                if r.dry_run.is_some() { " (DRY RUN, nothing published)" } else { "" },
+                artifact_count
+            ),
+        ),
+        Err(e) => write_log_with_agent(
+            if e.code == ErrorCode::Cancelled { "WARN" } else { "ERROR" },
+            agent_id,
+            &format!(
+                "Run {} {} ({}): {} {} ({} artifacts)",
+                run_id,
+                status,
+                script,
+                e.code.as_str(),
+                e.message,
+                artifact_count
+            ),
+        ),
+    }
+    result
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// Cancels the registered run (None => all of them); returns how many were hit
+fn cancel_registered(id: Option<&str>) -> usize {
+    let guard = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let tokens: Vec<&CancellationToken> = match (guard.as_ref(), id) {
+        (Some(map), Some(id)) => map.get(id).into_iter().collect(),
+        (Some(map), None) => map.values().collect(),
+        (None, _) => vec![],
+    };
+
+    for t in &tokens {
+        t.cancel();
+    }
+    tokens.len()
+}
+
+// run_id = None stops every running script
+redacted! {
+    #[tauri::command]
+    pub fn cancel_run(run_id: Option<String>) -> Result<String, String> {
+        let id = run_id.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+        let count = cancel_registered(id.as_deref());
+        if count == 0 {
+            return Ok(match id {
+                Some(id) => format!("ℹ️ Run {} is not running.", id),
+                None => "ℹ️ No scripts are running.".to_string(),
+            });
+        }
+
+        write_log("INFO", &format!("Cancel requested for {} run(s)", count));
+        Ok(format!("⛔ Stopping {} run(s)…", count))
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn set_dry_run(enabled: bool) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_runner_settings(&conn);
+        conn.execute(
+            "UPDATE user_settings SET dry_run=?1, updated_at=datetime('now') WHERE id=1",
+            params![enabled as i64],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", &format!("Global dry-run mode: {}", if enabled { "ON" } else { "OFF" }));
+        Ok(if enabled {
+            "🧪 Dry-run mode ON: automations stop before submitting and take a screenshot.".to_string()
+        } else {
+            "✅ Dry-run mode OFF (agents with sandbox ON still dry-run).".to_string()
+        })
+    }
+}
+
+// Transcript so far; the UI then follows RUN_OUTPUT_EVENT for the same id
+redacted! {
+    #[tauri::command]
+    pub fn run_output(run_id: String) -> Result<String, String> {
+        let run_id = run_id.trim();
+        let conn = open_db()?;
+        ensure_runs_table(&conn);
+
+        let (script, status): (String, String) = conn
+            .query_row("SELECT script, status FROM runs WHERE id=?1", [run_id], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|_| format!("❌ Run '{}' not found.", run_id))?;
+
+        let mut stmt = conn
+            .prepare("SELECT seq, stream, line FROM run_output WHERE run_id=?1 ORDER BY seq")
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let lines: Vec<(i64, String, String)> = stmt
+            .query_map([run_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+
+        let mut out = format!("🏃 {} ({}) — {}, {} lines\n", script, run_id, status, lines.len());
+        if lines.is_empty() {
+            out.push_str("\n(no output yet)");
+        }
+        for (_, stream, line) in &lines {
+            out.push('\n');
+            out.push_str(match stream.as_str() {
+                "stderr" | "warning" => "⚠️ ",
+                "progress" => "… ",
+                "error" => "❌ ",
+                _ => "",
+            });
+            out.push_str(line);
+        }
+        Ok(out)
+    }
+}
+
+// id, script, pid, status, exit code, started, ended
+type RunRow = (String, String, Option<i64>, String, Option<i64>, String, Option<String>);
+
+redacted! {
+    #[tauri::command]
+    pub fn list_runs(limit: Option<i64>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_runs_table(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, script, pid, status, exit_code, started_at, ended_at FROM runs
+             ORDER BY started_at DESC LIMIT ?1",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows: Vec<RunRow> = stmt
+            .query_map([limit.unwrap_or(10).clamp(1, 100)], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+
+        if rows.is_empty() {
+            return Ok("ℹ️ No automation runs yet.".to_string());
+        }
+
+        let mut out = String::from("🏃 Automation runs:\n");
+        for (id, script, pid, status, code, started, ended) in rows {
+            out.push_str(&format!(
+                "\n{} | {} | {}{}\n   {} → {} | pid {}\n",
+                id,
+                script,
+                status,
+                code.map(|c| format!(" (exit {})", c)).unwrap_or_default(),
+                started,
+                ended.unwrap_or_else(|| "…".to_string()),
+                pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string())
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pump_splits_lines_and_strips_ansi() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        pump(Some(&b"\x1b[32mstep 1\x1b[0m\r\nstep 2\nno newline"[..]), "stdout", tx).await;
+
+        let mut lines = vec![];
+        while let Some((stream, line)) = rx.recv().await {
+            assert_eq!(stream, "stdout");
+            lines.push(line);
+        }
+        assert_eq!(lines, vec!["step 1", "step 2", "no newline"]);
+    }
+
+    #[tokio::test]
+    async fn protocol_lines_with_cookies_stay_parsed_and_masked() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let lines = [
+            ("stdout", r#"{"type":"progress","message":"sent cookie: li_at=AQEDAT123; JSESSIONID=x"}"#),
+            ("stdout", r#"{"type":"result","message":"posted","data":{"session":"li_at=AQEDAT123"}}"#),
+            ("stdout", r#"{"type":"error","code":"SESSION_EXPIRED","message":"sessionid=abc987 rejected"}"#),
+            ("stdout", "plain Cookie: li_at=AQEDAT123"),
+            ("stderr", "warn sessionid=abc987"),
+        ];
+        for (stream, line) in lines {
+            tx.send((stream, line.to_string())).unwrap();
+        }
+        drop(tx);
+
+        let out = collect("run-cookies".to_string(), rx).await;
+        assert_eq!(out.messages.len(), 3);
+        assert!(matches!(&out.messages[0], Message::Progress { message, .. } if message == "sent cookie: ****"));
+        assert!(matches!(&out.messages[1], Message::Result { data, .. } if data["session"] == "li_at=****"));
+        assert!(matches!(
+            &out.messages[2],
+            Message::Error { code: ErrorCode::SessionExpired, message } if message == "sessionid=**** rejected"
+        ));
+        assert_eq!(out.stdout, "plain Cookie: ****\n");
+        assert_eq!(out.stderr, "warn sessionid=****\n");
+    }
+
+    #[test]
+    fn cancel_hits_only_the_registered_run() {
+        let a = register("run-a");
+        let b = register("run-b");
+
+        assert_eq!(cancel_registered(Some("run-a")), 1);
+        assert!(a.is_cancelled());
+        assert!(!b.is_cancelled());
+
+        unregister("run-a");
+        unregister("run-b");
+        assert_eq!(cancel_registered(Some("run-a")), 0);
+    }
+}
@@ -0,0 +1,641 @@
+// -------------------------
+// ✅ Secrets vault
+// - API keys + browser session files are encrypted at rest (AES-256-GCM)
+// - Vault key = Argon2id(passphrase, salt) or a random key kept in the OS keyring
+// - Decrypted values only live in memory; the key is never written to SQLite
+// -------------------------
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, AeadCore, Nonce};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::{app_data_dir, automations, open_db, write_log};
+
+const ENC_PREFIX: &str = "enc:v1:";
+const VERIFIER_PLAINTEXT: &str = "personaliz-vault-ok";
+const PASSPHRASE_ENV: &str = "PERSONALIZ_VAULT_PASSPHRASE";
+
+// In-memory vault key (None = locked)
+static VAULT_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+fn ensure_vault_table(conn: &rusqlite::Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS vault_meta (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            mode TEXT NOT NULL,
+            salt TEXT NULL,
+            verifier TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    );
+}
+
+// (mode, salt, verifier)
+fn read_vault_meta(conn: &rusqlite::Connection) -> Option<(String, Option<String>, String)> {
+    use rusqlite::OptionalExtension;
+
+    conn.query_row(
+        "SELECT mode, salt, verifier FROM vault_meta WHERE id=1",
+        [],
+        |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+fn current_key() -> Option<[u8; 32]> {
+    VAULT_KEY.lock().ok().and_then(|k| *k)
+}
+
+fn set_key(key: Option<[u8; 32]>) {
+    if let Ok(mut slot) = VAULT_KEY.lock() {
+        if let Some(old) = slot.as_mut() {
+            old.fill(0);
+        }
+        *slot = key;
+    }
+}
+
+pub fn is_unlocked() -> bool {
+    current_key().is_some()
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENC_PREFIX)
+}
+
+fn locked_error() -> String {
+    "🔒 Vault is locked. Run: unlock vault <passphrase>".to_string()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt_with(key: &[u8; 32], plaintext: &[u8]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ct = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ct);
+    Ok(format!("{}{}", ENC_PREFIX, B64.encode(blob)))
+}
+
+fn decrypt_with(key: &[u8; 32], value: &str) -> Result<Vec<u8>, String> {
+    let encoded = value
+        .strip_prefix(ENC_PREFIX)
+        .ok_or_else(|| "Value is not encrypted".to_string())?;
+    let blob = B64
+        .decode(encoded)
+        .map_err(|e| format!("Corrupt secret: {}", e))?;
+    if blob.len() < 12 {
+        return Err("Corrupt secret: too short".to_string());
+    }
+
+    let (nonce, ct) = blob.split_at(12);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ct)
+        .map_err(|_| "Decryption failed (wrong vault key?)".to_string())
+}
+
+pub fn encrypt_str(plaintext: &str) -> Result<String, String> {
+    encrypt_bytes(plaintext.as_bytes())
+}
+
+fn encrypt_bytes(bytes: &[u8]) -> Result<String, String> {
+    let key = current_key().ok_or_else(locked_error)?;
+    encrypt_with(&key, bytes)
+}
+
+// Plaintext values (not migrated yet) pass through unchanged
+pub fn decrypt_str(value: &str) -> Result<String, String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    let key = current_key().ok_or_else(locked_error)?;
+    let bytes = decrypt_with(&key, value)?;
+    String::from_utf8(bytes).map_err(|_| "Corrupt secret: not UTF-8".to_string())
+}
+
+fn verify_key(key: &[u8; 32], verifier: &str) -> bool {
+    decrypt_with(key, verifier)
+        .map(|v| v == VERIFIER_PLAINTEXT.as_bytes())
+        .unwrap_or(false)
+}
+
+fn create_vault(conn: &rusqlite::Connection, mode: &str, key: &[u8; 32], salt: Option<&[u8]>) -> Result<(), String> {
+    use rusqlite::params;
+
+    let verifier = encrypt_with(key, VERIFIER_PLAINTEXT.as_bytes())?;
+    conn.execute(
+        "INSERT INTO vault_meta (id, mode, salt, verifier, created_at)
+         VALUES (1, ?1, ?2, ?3, datetime('now'))",
+        params![mode, salt.map(|s| B64.encode(s)), verifier],
+    )
+    .map_err(|e| format!("DB insert failed: {}", e))?;
+    Ok(())
+}
+
+// -------------------------
+// OS keyring (Windows Credential Manager / macOS Keychain)
+// Other platforms: not available => passphrase only
+// -------------------------
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+mod keyring_store {
+    use super::B64;
+    use base64::Engine;
+
+    fn entry() -> Option<keyring::Entry> {
+        keyring::Entry::new("personaliz-desktop", "vault-key").ok()
+    }
+
+    pub fn available() -> bool {
+        entry().is_some()
+    }
+
+    pub fn load() -> Option<[u8; 32]> {
+        let encoded = entry()?.get_password().ok()?;
+        let bytes = B64.decode(encoded).ok()?;
+        bytes.try_into().ok()
+    }
+
+    pub fn store(key: &[u8; 32]) -> Result<(), String> {
+        entry()
+            .ok_or_else(|| "OS keyring not available".to_string())?
+            .set_password(&B64.encode(key))
+            .map_err(|e| format!("OS keyring write failed: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod keyring_store {
+    pub fn available() -> bool {
+        false
+    }
+
+    pub fn load() -> Option<[u8; 32]> {
+        None
+    }
+
+    pub fn store(_key: &[u8; 32]) -> Result<(), String> {
+        Err("OS keyring not available on this platform".to_string())
+    }
+}
+
+// Called once at startup:
+// - keyring key (if stored) or PERSONALIZ_VAULT_PASSPHRASE unlocks the vault
+// - brand new install + keyring available => create a keyring-backed vault
+// - once unlocked, plaintext keys/sessions are migrated
+pub fn auto_unlock() {
+    let conn = match open_db() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    ensure_vault_table(&conn);
+
+    match read_vault_meta(&conn) {
+        Some((_, salt, verifier)) => {
+            if let Some(key) = keyring_store::load().filter(|k| verify_key(k, &verifier)) {
+                set_key(Some(key));
+            } else if let (Ok(pass), Some(salt)) = (std::env::var(PASSPHRASE_ENV), salt) {
+                if let Ok(key) = B64
+                    .decode(salt)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| derive_key(&pass, &s))
+                {
+                    if verify_key(&key, &verifier) {
+                        set_key(Some(key));
+                    }
+                }
+            }
+        }
+        None => {
+            if let Ok(pass) = std::env::var(PASSPHRASE_ENV) {
+                let _ = init_passphrase_vault(&conn, &pass);
+            } else if keyring_store::available() {
+                let mut key = [0u8; 32];
+                OsRng.fill_bytes(&mut key);
+                if keyring_store::store(&key).is_ok()
+                    && create_vault(&conn, "keyring", &key, None).is_ok()
+                {
+                    set_key(Some(key));
+                    write_log("INFO", "Vault created (OS keyring)");
+                }
+            }
+        }
+    }
+
+    if is_unlocked() {
+        migrate_plaintext(&conn);
+    } else {
+        write_log("WARN", "Vault is locked; external LLM keys and sessions unavailable until unlocked");
+    }
+}
+
+fn init_passphrase_vault(conn: &rusqlite::Connection, passphrase: &str) -> Result<[u8; 32], String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    create_vault(conn, "passphrase", &key, Some(&salt))?;
+    set_key(Some(key));
+    write_log("INFO", "Vault created (passphrase)");
+    Ok(key)
+}
+
+// Encrypts plaintext API keys in llm_credentials; returns how many
+fn migrate_api_keys(conn: &rusqlite::Connection, key: &[u8; 32]) -> usize {
+    use rusqlite::params;
+
+    crate::ensure_llm_credentials_table(conn);
+
+    let rows: Vec<(String, String)> = match conn.prepare("SELECT provider, api_key FROM llm_credentials") {
+        Ok(mut stmt) => stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map(|it| it.flatten().collect())
+            .unwrap_or_default(),
+        Err(_) => vec![],
+    };
+
+    let mut migrated = 0;
+    for (provider, api_key) in rows {
+        if is_encrypted(&api_key) {
+            continue;
+        }
+        if let Ok(enc) = encrypt_with(key, api_key.as_bytes()) {
+            if conn
+                .execute(
+                    "UPDATE llm_credentials SET api_key=?1 WHERE provider=?2",
+                    params![enc, provider],
+                )
+                .is_ok()
+            {
+                migrated += 1;
+            }
+        }
+    }
+    migrated
+}
+
+// Encrypt anything still stored in plaintext (pre-vault installs)
+fn migrate_plaintext(conn: &rusqlite::Connection) {
+    let migrated = match current_key() {
+        Some(key) => migrate_api_keys(conn, &key),
+        None => 0,
+    };
+    if migrated > 0 {
+        write_log("INFO", &format!("Vault: encrypted {} plaintext API key(s)", migrated));
+    }
+
+    // Legacy Playwright session next to the scripts
+    let legacy = automations::scripts_dir().0.join("auth.json");
+    if legacy.exists() {
+        match std::fs::read(&legacy)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| store_session_bytes(&default_session_path(), &bytes))
+        {
+            Ok(()) => {
+                let _ = std::fs::remove_file(&legacy);
+                write_log("INFO", "Vault: encrypted LinkedIn session (auth.json removed)");
+            }
+            Err(e) => write_log("ERROR", &format!("Vault: session migration failed: {}", e)),
+        }
+    }
+}
+
+// -------------------------
+// Encrypted browser sessions
+// One file per account (accounts.rs keeps the path); the LinkedIn session
+// from before accounts existed is the default account's file.
+// -------------------------
+pub fn sessions_dir() -> PathBuf {
+    let dir = app_data_dir().join("sessions");
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+pub fn default_session_path() -> PathBuf {
+    sessions_dir().join("linkedin.json.enc")
+}
+
+fn store_session_bytes(stored: &Path, bytes: &[u8]) -> Result<(), String> {
+    let enc = encrypt_bytes(bytes)?;
+    std::fs::write(stored, enc).map_err(|e| format!("Failed writing session: {}", e))
+}
+
+// Decrypted session for the lifetime of one script run.
+// The plaintext file is removed on drop; `capture` re-encrypts what the script wrote.
+pub struct SessionFile {
+    path: PathBuf,
+    stored: PathBuf,
+}
+
+impl SessionFile {
+    // Decrypts the stored session (if any) into a private temp file
+    pub fn prepare(stored: &Path) -> Result<SessionFile, String> {
+        let path = sessions_dir().join(format!("run-{}.json", uuid::Uuid::new_v4()));
+
+        if stored.exists() {
+            let key = current_key().ok_or_else(locked_error)?;
+            let enc = std::fs::read_to_string(stored).map_err(|e| format!("Failed reading session: {}", e))?;
+            let plain = decrypt_with(&key, enc.trim())?;
+            write_private(&path, &plain)?;
+        }
+
+        Ok(SessionFile {
+            path,
+            stored: stored.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    // After a login run: encrypt the session the script saved
+    pub fn capture(&self) -> Result<(), String> {
+        let bytes = std::fs::read(&self.path).map_err(|e| format!("Session file missing after login: {}", e))?;
+        store_session_bytes(&self.stored, &bytes)
+    }
+}
+
+impl Drop for SessionFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// Encrypts a file the app must keep (e.g. a run's Playwright trace, which holds
+// the session cookies) to <file>.enc and removes the plaintext
+pub fn seal_file(path: &Path) -> Result<PathBuf, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed reading {}: {}", path.display(), e))?;
+    let sealed = PathBuf::from(format!("{}.enc", path.display()));
+    std::fs::write(&sealed, encrypt_bytes(&bytes)?).map_err(|e| format!("Failed writing {}: {}", sealed.display(), e))?;
+    std::fs::remove_file(path).map_err(|e| format!("Failed removing {}: {}", path.display(), e))?;
+    Ok(sealed)
+}
+
+// Decrypted copy of a sealed file, readable only by the user
+pub fn unseal_file(sealed: &Path, dest: &Path) -> Result<(), String> {
+    let key = current_key().ok_or_else(locked_error)?;
+    let enc = std::fs::read_to_string(sealed).map_err(|e| format!("Failed reading {}: {}", sealed.display(), e))?;
+    write_private(dest, &decrypt_with(&key, enc.trim())?)
+}
+
+// Created with mode 0600 on unix, so the plaintext is never readable by others
+fn write_private(path: &std::path::Path, bytes: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+        .open(path)
+        .and_then(|mut f| f.write_all(bytes))
+        .map_err(|e| format!("Failed writing {}: {}", path.display(), e))
+}
+
+// Decrypted run-<uuid>.json copies are removed when the run ends; after a
+// crash they would stay. Called at startup, before any run.
+pub fn remove_stale_session_copies() {
+    let removed = remove_run_copies(&sessions_dir());
+    if removed > 0 {
+        write_log("WARN", &format!("Removed {} decrypted session file(s) left by a crash", removed));
+    }
+}
+
+fn remove_run_copies(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
+    entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.starts_with("run-") && name.ends_with(".json")
+        })
+        .filter(|e| std::fs::remove_file(e.path()).is_ok())
+        .count()
+}
+
+// Key of an existing vault: keyring vaults (no salt) reload it from the keyring,
+// passphrase vaults derive it
+fn existing_vault_key(
+    salt: Option<&str>,
+    verifier: &str,
+    passphrase: &str,
+    keyring: impl FnOnce() -> Option<[u8; 32]>,
+) -> Result<[u8; 32], String> {
+    let Some(salt) = salt else {
+        return keyring()
+            .filter(|k| verify_key(k, verifier))
+            .ok_or_else(|| "❌ Vault uses the OS keyring, but its key could not be read from it.".to_string());
+    };
+
+    if passphrase.trim().is_empty() {
+        return Err("❌ Passphrase is empty.".to_string());
+    }
+    let salt = B64.decode(salt).map_err(|e| format!("Corrupt vault salt: {}", e))?;
+    let key = derive_key(passphrase, &salt)?;
+    if !verify_key(&key, verifier) {
+        write_log("WARN", "Vault unlock failed: wrong passphrase");
+        return Err("❌ Wrong passphrase.".to_string());
+    }
+    Ok(key)
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn unlock_vault(passphrase: String, remember: Option<bool>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_vault_table(&conn);
+
+        let key = match read_vault_meta(&conn) {
+            None if passphrase.trim().is_empty() => return Err("❌ Passphrase is empty.".to_string()),
+            None => init_passphrase_vault(&conn, &passphrase)?,
+            Some((_, salt, verifier)) => {
+                let key = existing_vault_key(salt.as_deref(), &verifier, &passphrase, keyring_store::load)?;
+                set_key(Some(key));
+                key
+            }
+        };
+
+        migrate_plaintext(&conn);
+
+        let mut out = "🔓 Vault unlocked.".to_string();
+        if remember.unwrap_or(false) {
+            match keyring_store::store(&key) {
+                Ok(()) => out.push_str("\nKey saved to the OS keyring (auto-unlock on start)."),
+                Err(e) => out.push_str(&format!("\n⚠️ {}", e)),
+            }
+        }
+
+        write_log("INFO", "Vault unlocked");
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn lock_vault() -> Result<String, String> {
+        set_key(None);
+        write_log("INFO", "Vault locked");
+        Ok("🔒 Vault locked.".to_string())
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn vault_status() -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_vault_table(&conn);
+
+        let mode = read_vault_meta(&conn)
+            .map(|(mode, _, _)| mode)
+            .unwrap_or_else(|| "not initialized".to_string());
+
+        Ok(format!(
+            "vault: {}\nmode: {}\nos_keyring: {}\nlinkedin_session (default account): {}",
+            if is_unlocked() { "🔓 unlocked" } else { "🔒 locked" },
+            mode,
+            if keyring_store::available() { "available" } else { "not available" },
+            if default_session_path().exists() { "✅ saved (encrypted)" } else { "❌ none" }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase_vault(conn: &rusqlite::Connection, passphrase: &str) -> [u8; 32] {
+        ensure_vault_table(conn);
+        let salt = [7u8; 16];
+        let key = derive_key(passphrase, &salt).unwrap();
+        create_vault(conn, "passphrase", &key, Some(&salt)).unwrap();
+        key
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let key = derive_key("correct horse", &[1u8; 16]).unwrap();
+        let enc = encrypt_with(&key, b"sk-secret").unwrap();
+        assert!(is_encrypted(&enc));
+        assert!(!enc.contains("sk-secret"));
+        assert_eq!(decrypt_with(&key, &enc).unwrap(), b"sk-secret");
+        // fresh nonce per value
+        assert_ne!(enc, encrypt_with(&key, b"sk-secret").unwrap());
+
+        let other = derive_key("battery staple", &[1u8; 16]).unwrap();
+        assert!(decrypt_with(&other, &enc).is_err());
+        assert!(decrypt_with(&key, "sk-secret").is_err());
+        assert!(decrypt_with(&key, &format!("{}AAAA", ENC_PREFIX)).is_err());
+    }
+
+    #[test]
+    fn lock_then_unlock_round_trip() {
+        // the vault lives in this test thread's DB (see app_data_dir)
+        unlock_vault("right one".to_string(), None).unwrap();
+        let enc = encrypt_str("sk-secret").unwrap();
+
+        lock_vault().unwrap();
+        assert!(!is_unlocked());
+        assert!(decrypt_str(&enc).is_err());
+        assert!(unlock_vault("wrong one".to_string(), None).is_err());
+        assert!(!is_unlocked());
+
+        unlock_vault("right one".to_string(), None).unwrap();
+        assert_eq!(decrypt_str(&enc).unwrap(), "sk-secret");
+    }
+
+    #[test]
+    fn keyring_vaults_unlock_from_the_keyring() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        ensure_vault_table(&conn);
+        let key = derive_key("random", &[3u8; 16]).unwrap();
+        create_vault(&conn, "keyring", &key, None).unwrap();
+        let (mode, salt, verifier) = read_vault_meta(&conn).unwrap();
+        assert_eq!(mode, "keyring");
+
+        // no passphrase needed once the keyring has the key again
+        assert_eq!(existing_vault_key(salt.as_deref(), &verifier, "", || Some(key)).unwrap(), key);
+        assert!(existing_vault_key(salt.as_deref(), &verifier, "", || None).is_err());
+        assert!(existing_vault_key(salt.as_deref(), &verifier, "", || Some([0u8; 32])).is_err());
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_verification() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let key = passphrase_vault(&conn, "right one");
+        let (mode, salt, verifier) = read_vault_meta(&conn).unwrap();
+        assert_eq!(mode, "passphrase");
+        assert!(verify_key(&key, &verifier));
+
+        let salt = B64.decode(salt.unwrap()).unwrap();
+        assert!(verify_key(&derive_key("right one", &salt).unwrap(), &verifier));
+        assert!(!verify_key(&derive_key("wrong one", &salt).unwrap(), &verifier));
+    }
+
+    #[test]
+    fn plaintext_api_keys_are_migrated_once() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let key = passphrase_vault(&conn, "pass");
+        crate::ensure_llm_credentials_table(&conn);
+        let already = encrypt_with(&key, b"sk-ant-old").unwrap();
+        conn.execute(
+            "INSERT INTO llm_credentials (provider, api_key, updated_at)
+             VALUES ('openai', 'sk-plain', datetime('now')), ('anthropic', ?1, datetime('now'))",
+            [&already],
+        )
+        .unwrap();
+
+        assert_eq!(migrate_api_keys(&conn, &key), 1);
+        assert_eq!(migrate_api_keys(&conn, &key), 0);
+
+        let stored = |provider: &str| -> String {
+            conn.query_row("SELECT api_key FROM llm_credentials WHERE provider=?1", [provider], |r| r.get(0))
+                .unwrap()
+        };
+        let openai = stored("openai");
+        assert!(is_encrypted(&openai));
+        assert_eq!(decrypt_with(&key, &openai).unwrap(), b"sk-plain");
+        assert_eq!(stored("anthropic"), already);
+    }
+
+    #[test]
+    fn stale_run_copies_are_removed_and_private() {
+        let dir = std::env::temp_dir().join(format!("sessions-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_private(&dir.join("run-1234.json"), b"{\"cookies\":[]}").unwrap();
+        std::fs::write(dir.join("linkedin.json.enc"), b"enc:v1:x").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("run-1234.json")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert_eq!(remove_run_copies(&dir), 1);
+        assert!(!dir.join("run-1234.json").exists());
+        assert!(dir.join("linkedin.json.enc").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+// -------------------------
+// ✅ Session health (per account, see accounts.rs)
+// Before a script that needs a session runs, the runner calls preflight():
+// a session verified in the last FRESH_MINUTES is trusted, otherwise the
+// headless check script (automation/linkedin_session_check.js) opens the feed.
+// Every run that uses the session updates the account: success => verified
+// now, SESSION_EXPIRED => expired. On expiry SESSION_EVENT is emitted and the
+// agents using that account whose tools need the session are paused
+// (agents.paused_reason) until a login run saves a fresh session.
+// -------------------------
+use std::future::Future;
+use std::pin::Pin;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::accounts::{self, Account};
+use crate::protocol::{ErrorCode, ScriptError, ScriptResult};
+use crate::queue::Priority;
+use crate::runner::{self, RunOptions};
+use crate::{automations, ensure_column, open_db, write_log, write_log_agent};
+
+pub const SESSION_EVENT: &str = "session-status";
+pub const CHECK_SCRIPT: &str = "linkedin_session_check";
+const FRESH_MINUTES: i64 = 30;
+
+#[derive(Clone, Serialize)]
+pub struct SessionStatus {
+    pub platform: String,
+    pub account: String,
+    // valid | expired
+    pub status: String,
+    pub paused_agents: Vec<String>,
+    pub resumed_agents: Vec<String>,
+}
+
+pub fn ensure_session_tables(conn: &Connection) {
+    accounts::ensure_accounts_table(conn);
+    ensure_column(conn, "accounts", "session_status TEXT NULL");
+    ensure_column(conn, "accounts", "last_verified_at TEXT NULL");
+    ensure_column(conn, "accounts", "checked_at TEXT NULL");
+    ensure_column(conn, "accounts", "session_detail TEXT NULL");
+    // e.g. "session:<account id>" => skipped by the scheduler until re-login
+    ensure_column(conn, "agents", "paused_reason TEXT NULL");
+
+    // per-platform state from before accounts => the default account
+    let _ = conn.execute(
+        "UPDATE agents SET paused_reason='session:linkedin-default' WHERE paused_reason='session:linkedin'",
+        [],
+    );
+    let _ = conn.execute(
+        "UPDATE accounts SET (session_status, last_verified_at, checked_at, session_detail) =
+             (SELECT status, last_verified_at, checked_at, detail FROM platform_sessions WHERE platform='linkedin')
+         WHERE id='linkedin-default' AND session_status IS NULL",
+        [],
+    );
+    let _ = conn.execute("DROP TABLE IF EXISTS platform_sessions", []);
+}
+
+fn pause_reason(account: &Account) -> String {
+    format!("session:{}", account.id)
+}
+
+// (status, last verified, verified within FRESH_MINUTES)
+fn stored_state(conn: &Connection, account: &Account) -> Option<(String, Option<String>, bool)> {
+    ensure_session_tables(conn);
+    conn.query_row(
+        "SELECT session_status, last_verified_at,
+                last_verified_at IS NOT NULL AND last_verified_at >= datetime('now', ?2)
+         FROM accounts WHERE id=?1 AND session_status IS NOT NULL",
+        params![account.id, format!("-{} minutes", FRESH_MINUTES)],
+        |r| Ok((r.get(0)?, r.get(1)?, r.get::<_, bool>(2)?)),
+    )
+    .ok()
+}
+
+// Agents on this account with at least one tool that is an automation using its platform's session
+fn agents_requiring(conn: &Connection, account: &Account) -> Vec<(String, String)> {
+    let registry = automations::load_registry();
+    let scripts: Vec<&str> = registry
+        .manifests
+        .iter()
+        .filter(|m| m.session.as_deref() == Some(account.platform.as_str()))
+        .map(|m| m.name.as_str())
+        .collect();
+
+    accounts::agents_using(conn, account)
+        .into_iter()
+        .filter(|(_, _, tools)| {
+            serde_json::from_str::<Vec<String>>(tools)
+                .unwrap_or_default()
+                .iter()
+                .any(|t| scripts.contains(&t.as_str()))
+        })
+        .map(|(id, name, _)| (id, name))
+        .collect()
+}
+
+fn mark_valid(conn: &Connection, account: &Account) {
+    let was_expired = stored_state(conn, account).is_some_and(|(s, _, _)| s == "expired");
+    let _ = conn.execute(
+        "UPDATE accounts SET session_status='valid', last_verified_at=datetime('now'),
+             checked_at=datetime('now'), session_detail=NULL
+         WHERE id=?1",
+        params![account.id],
+    );
+    if !was_expired {
+        return;
+    }
+
+    let reason = pause_reason(account);
+    let resumed: Vec<(String, String)> = conn
+        .prepare("SELECT id, name FROM agents WHERE paused_reason=?1")
+        .and_then(|mut stmt| {
+            stmt.query_map([&reason], |r| Ok((r.get(0)?, r.get(1)?)))
+                .map(|rows| rows.flatten().collect())
+        })
+        .unwrap_or_default();
+    let _ = conn.execute("UPDATE agents SET paused_reason=NULL WHERE paused_reason=?1", params![reason]);
+
+    for (id, _) in &resumed {
+        write_log_agent("INFO", id, &format!("Agent resumed: {} session is valid again", account.display()));
+    }
+    write_log(
+        "INFO",
+        &format!("{} session valid again ({} agent(s) resumed)", account.display(), resumed.len()),
+    );
+    runner::emit(
+        SESSION_EVENT,
+        SessionStatus {
+            platform: account.platform.clone(),
+            account: account.label.clone(),
+            status: "valid".to_string(),
+            paused_agents: vec![],
+            resumed_agents: resumed.into_iter().map(|(_, name)| name).collect(),
+        },
+    );
+}
+
+fn mark_expired(conn: &Connection, account: &Account, detail: &str) {
+    let was_expired = stored_state(conn, account).is_some_and(|(s, _, _)| s == "expired");
+    let _ = conn.execute(
+        "UPDATE accounts SET session_status='expired', checked_at=datetime('now'), session_detail=?2 WHERE id=?1",
+        params![account.id, detail],
+    );
+    if was_expired {
+        return;
+    }
+
+    let reason = pause_reason(account);
+    let agents = agents_requiring(conn, account);
+    for (id, _) in &agents {
+        let _ = conn.execute(
+            "UPDATE agents SET paused_reason=?1 WHERE id=?2 AND paused_reason IS NULL",
+            params![reason, id],
+        );
+        write_log_agent("WARN", id, &format!("Agent paused: {} session expired", account.display()));
+    }
+    write_log(
+        "WARN",
+        &format!("{} session expired: {} ({} agent(s) paused)", account.display(), detail, agents.len()),
+    );
+    runner::emit(
+        SESSION_EVENT,
+        SessionStatus {
+            platform: account.platform.clone(),
+            account: account.label.clone(),
+            status: "expired".to_string(),
+            paused_agents: agents.into_iter().map(|(_, name)| name).collect(),
+            resumed_agents: vec![],
+        },
+    );
+}
+
+// Called by the runner with the outcome of every run that used the account's session
+pub fn record_outcome(account: &Account, result: &Result<ScriptResult, ScriptError>) {
+    let Ok(conn) = open_db() else { return };
+    match result {
+        Ok(_) => mark_valid(&conn, account),
+        Err(e) if e.code == ErrorCode::SessionExpired => mark_expired(&conn, account, &e.message),
+        Err(_) => {}
+    }
+}
+
+fn login_hint(account: &Account) -> String {
+    if account.is_default() {
+        format!("{} login", account.platform)
+    } else {
+        format!("{} login {}", account.platform, account.label)
+    }
+}
+
+fn expired_error(account: &Account, since: Option<&str>) -> ScriptError {
+    ScriptError::new(
+        ErrorCode::SessionExpired,
+        match since {
+            Some(at) => format!(
+                "⛔ The {} session has expired (last verified {}); not started. Log in with: {}",
+                account.display(),
+                at,
+                login_hint(account)
+            ),
+            None => format!(
+                "⛔ No session for {}; not started. Log in with: {}",
+                account.display(),
+                login_hint(account)
+            ),
+        },
+    )
+}
+
+// runner -> preflight -> runner (check script): boxed to break the async recursion
+type CheckRun = Pin<Box<dyn Future<Output = Result<ScriptResult, ScriptError>> + Send>>;
+
+// priority: queued like the run it checks for
+fn run_check(account_id: String, priority: Priority) -> CheckRun {
+    Box::pin(async move {
+        // scheduled => headless unless the user chose "headed"
+        let opts = RunOptions::default()
+            .account(Some(account_id))
+            .scheduled(true)
+            .priority(Some(priority));
+        runner::run_script(CHECK_SCRIPT, vec![], &opts).await
+    })
+}
+
+// Ok => go ahead. Errors other than an expired session (network, missing check
+// script) don't block the run; the script reports SESSION_EXPIRED itself.
+pub async fn preflight(account: &Account, priority: Priority) -> Result<(), ScriptError> {
+    if !account.has_session() {
+        return Err(expired_error(account, None));
+    }
+
+    let state = open_db().ok().and_then(|conn| stored_state(&conn, account));
+    match state {
+        Some((status, since, _)) if status == "expired" => return Err(expired_error(account, since.as_deref())),
+        Some((_, _, true)) => return Ok(()),
+        _ => {}
+    }
+
+    if automations::load_registry().get(CHECK_SCRIPT).is_err() {
+        return Ok(());
+    }
+    match run_check(account.id.clone(), priority).await {
+        Err(e) if e.code == ErrorCode::SessionExpired => Err(e),
+        Err(e) => {
+            write_log(
+                "WARN",
+                &format!("{} session check inconclusive: {}", account.display(), e.message),
+            );
+            Ok(())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// account = label of a LinkedIn account (None => default)
+redacted! {
+    #[tauri::command]
+    pub async fn check_linkedin_session(account: Option<String>) -> Result<String, String> {
+        let account = {
+            let conn = open_db()?;
+            ensure_session_tables(&conn);
+            accounts::find_or_default(&conn, "linkedin", account.as_deref())?
+        };
+        if !account.has_session() {
+            return Ok(format!(
+                "🔑 No session saved for {} yet. Log in with: {}",
+                account.display(),
+                login_hint(&account)
+            ));
+        }
+
+        let outcome = run_check(account.id.clone(), Priority::User).await;
+
+        let state = open_db().ok().and_then(|conn| stored_state(&conn, &account));
+        let verified = state.and_then(|(_, at, _)| at).unwrap_or_else(|| "never".to_string());
+        match outcome {
+            Ok(_) => Ok(format!("✅ {} session is valid (verified {}).", account.display(), verified)),
+            Err(e) if e.code == ErrorCode::SessionExpired => Ok(format!(
+                "🔑 {} session expired (last verified {}). Agents using it are paused.\nLog in again with: {}",
+                account.display(),
+                verified,
+                login_hint(&account)
+            )),
+            Err(e) => Err(format!("⚠️ Session check failed: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_keeps_the_last_verified_time() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_session_tables(&conn);
+        let account = accounts::find_or_default(&conn, "linkedin", None).unwrap();
+        assert!(stored_state(&conn, &account).is_none());
+
+        mark_valid(&conn, &account);
+        let (status, verified, fresh) = stored_state(&conn, &account).unwrap();
+        assert_eq!(status, "valid");
+        assert!(verified.is_some() && fresh);
+
+        mark_expired(&conn, &account, "redirected to /login");
+        mark_expired(&conn, &account, "still expired");
+        let (status, verified, _) = stored_state(&conn, &account).unwrap();
+        assert_eq!(status, "expired");
+        // last verified time survives the expiry
+        assert!(verified.is_some());
+
+        let detail: String = conn
+            .query_row("SELECT session_detail FROM accounts WHERE id=?1", [&account.id], |r| r.get(0))
+            .unwrap();
+        assert_eq!(detail, "still expired");
+    }
+}
@@ -0,0 +1,391 @@
+// -------------------------
+// ✅ Structured (JSON) output
+// generate_structured = provider JSON mode + our own schema check.
+// Invalid output is sent back to the model with the errors, a bounded
+// number of times. The validator covers the JSON Schema subset we use:
+// type, enum, properties, required, additionalProperties, items,
+// min/maxItems, min/maxLength, minimum/maximum, nullable.
+// -------------------------
+use serde_json::Value;
+
+use crate::llm::{ChatMessage, JsonSchema, LlmProvider, LlmRequest};
+use crate::{write_log, RoutedProvider};
+
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+const MAX_RETRIES_LIMIT: u32 = 5;
+// keep the re-prompt short; the first few errors are enough to fix the output
+const MAX_REPORTED_ERRORS: usize = 8;
+
+fn type_matches(expected: &str, v: &Value) -> bool {
+    match expected {
+        "object" => v.is_object(),
+        "array" => v.is_array(),
+        "string" => v.is_string(),
+        "boolean" => v.is_boolean(),
+        "null" => v.is_null(),
+        "number" => v.is_number(),
+        "integer" => v.is_i64() || v.is_u64() || v.as_f64().map(|f| f.fract() == 0.0).unwrap_or(false),
+        _ => true,
+    }
+}
+
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+        Value::Number(_) => "number",
+    }
+}
+
+fn check(v: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    if v.is_null() && schema["nullable"] == Value::Bool(true) {
+        return;
+    }
+
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|t| type_matches(t, v)) {
+        errors.push(format!("{}: expected {}, got {}", path, types.join(" | "), type_name(v)));
+        return;
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(v) {
+            let list = allowed.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+            errors.push(format!("{}: must be one of {}", path, list));
+        }
+    }
+
+    match v {
+        Value::Object(map) => {
+            if let Some(required) = schema["required"].as_array() {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{}: missing required property '{}'", path, key));
+                    }
+                }
+            }
+
+            let props = schema["properties"].as_object();
+            for (key, value) in map {
+                let child = format!("{}.{}", path, key);
+                match props.and_then(|p| p.get(key)) {
+                    Some(prop_schema) => check(value, prop_schema, &child, errors),
+                    None => match &schema["additionalProperties"] {
+                        Value::Bool(false) => errors.push(format!("{}: unexpected property", child)),
+                        extra @ Value::Object(_) => check(value, extra, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema["minItems"].as_u64() {
+                if len < min {
+                    errors.push(format!("{}: expected at least {} items, got {}", path, min, len));
+                }
+            }
+            if let Some(max) = schema["maxItems"].as_u64() {
+                if len > max {
+                    errors.push(format!("{}: expected at most {} items, got {}", path, max, len));
+                }
+            }
+            if schema["items"].is_object() {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, &schema["items"], &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema["minLength"].as_u64() {
+                if len < min {
+                    errors.push(format!("{}: shorter than {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema["maxLength"].as_u64() {
+                if len > max {
+                    errors.push(format!("{}: longer than {} characters", path, max));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema["minimum"].as_f64() {
+                if n < min {
+                    errors.push(format!("{}: must be >= {}", path, min));
+                }
+            }
+            if let Some(max) = schema["maximum"].as_f64() {
+                if n > max {
+                    errors.push(format!("{}: must be <= {}", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    let mut errors = vec![];
+    check(value, schema, "$", &mut errors);
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let more = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+    errors.truncate(MAX_REPORTED_ERRORS);
+    if more > 0 {
+        errors.push(format!("(+{} more)", more));
+    }
+    Err(errors.join("\n"))
+}
+
+// Models sometimes wrap JSON in code fences or add a sentence around it
+pub fn parse_json(text: &str) -> Result<Value, String> {
+    let t = text.trim();
+    let t = t
+        .strip_prefix("```json")
+        .or_else(|| t.strip_prefix("```"))
+        .map(|s| s.trim_end().trim_end_matches("```"))
+        .unwrap_or(t)
+        .trim();
+
+    if let Ok(v) = serde_json::from_str(t) {
+        return Ok(v);
+    }
+
+    let start = t.find(['{', '[']);
+    let end = t.rfind(['}', ']']);
+    match (start, end) {
+        (Some(s), Some(e)) if e > s => {
+            serde_json::from_str(&t[s..=e]).map_err(|e| format!("$: not valid JSON ({})", e))
+        }
+        _ => Err("$: reply contains no JSON".to_string()),
+    }
+}
+
+// provider: usually RoutedProvider (the agent's or the active LLM)
+pub async fn generate_structured(
+    provider: &dyn LlmProvider,
+    req: &LlmRequest,
+    schema: &JsonSchema,
+    max_retries: u32,
+) -> Result<(String, Value), String> {
+    if !schema.schema.is_object() {
+        return Err("❌ Schema must be a JSON object.".to_string());
+    }
+
+    // native JSON mode where the provider has one; the instruction covers the rest
+    let instruction = format!(
+        "Respond with JSON only (no prose, no code fences) matching this JSON schema:\n{}",
+        schema.schema
+    );
+    let mut req = LlmRequest {
+        system: Some(match &req.system {
+            Some(s) => format!("{}\n\n{}", s, instruction),
+            None => instruction,
+        }),
+        response_schema: Some(schema.clone()),
+        ..req.clone()
+    };
+
+    let max_retries = max_retries.min(MAX_RETRIES_LIMIT);
+    let mut last_error = String::new();
+
+    for attempt in 0..=max_retries {
+        // provider / budget errors are not retried here
+        let resp = provider.generate(&req).await?;
+
+        match parse_json(&resp.text).and_then(|v| validate(&v, &schema.schema).map(|_| v)) {
+            Ok(v) => return Ok((provider.label(), v)),
+            Err(errors) => {
+                write_log(
+                    "WARN",
+                    &format!(
+                        "Structured output '{}' invalid (attempt {}/{}):\n{}",
+                        schema.name,
+                        attempt + 1,
+                        max_retries + 1,
+                        errors
+                    ),
+                );
+
+                req.messages.push(ChatMessage::assistant(&resp.text));
+                req.messages.push(ChatMessage::user(&format!(
+                    "Your reply does not match the JSON schema:\n{}\n\nReply again with only the corrected JSON.",
+                    errors
+                )));
+                last_error = errors;
+            }
+        }
+    }
+
+    Err(format!(
+        "❌ Structured output still invalid after {} attempt(s):\n{}",
+        max_retries + 1,
+        last_error
+    ))
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+
+// schema: JSON Schema text, e.g. {"type":"object","properties":{"topics":{"type":"array","items":{"type":"string"}}},"required":["topics"]}
+redacted! {
+    #[tauri::command]
+    pub async fn llm_structured(prompt: String, schema: String, max_retries: Option<u32>) -> Result<String, String> {
+        let schema: Value =
+            serde_json::from_str(&schema).map_err(|e| format!("❌ Schema is not valid JSON: {}", e))?;
+
+        generate_structured(
+            &RoutedProvider::new(None),
+            &LlmRequest::prompt(&prompt),
+            &JsonSchema::new("structured_response", schema),
+            max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+        )
+        .await
+        .map(|(label, v)| {
+            format!(
+                "(LLM: {})\n{}",
+                label,
+                serde_json::to_string_pretty(&v).unwrap_or_else(|_| v.to_string())
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::{reply, MockProvider};
+    use serde_json::json;
+
+    fn post_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["text", "tone", "hashtags"],
+            "additionalProperties": false,
+            "properties": {
+                "text": {"type": "string", "minLength": 5, "maxLength": 20},
+                "tone": {"type": "string", "enum": ["friendly", "formal"]},
+                "hashtags": {"type": "array", "items": {"type": "string"}, "minItems": 1, "maxItems": 2},
+                "score": {"type": "integer", "minimum": 0, "maximum": 10},
+                "note": {"type": "string", "nullable": true}
+            }
+        })
+    }
+
+    #[test]
+    fn parses_fenced_json_and_json_inside_prose() {
+        assert_eq!(parse_json("```json\n{\"a\": 1}\n```").unwrap(), json!({"a": 1}));
+        assert_eq!(parse_json("```\n[1, 2]\n```").unwrap(), json!([1, 2]));
+        assert_eq!(
+            parse_json("Sure! Here it is: {\"a\": {\"b\": true}} Hope that helps.").unwrap(),
+            json!({"a": {"b": true}})
+        );
+        assert!(parse_json("no json here").unwrap_err().contains("no JSON"));
+        assert!(parse_json("broken {\"a\": } end").unwrap_err().contains("not valid JSON"));
+    }
+
+    #[test]
+    fn accepts_a_matching_value() {
+        let v = json!({"text": "Hello world", "tone": "friendly", "hashtags": ["rust"], "score": 7, "note": null});
+        assert_eq!(validate(&v, &post_schema()), Ok(()));
+    }
+
+    #[test]
+    fn reports_type_required_enum_and_bounds() {
+        let errors = |v: Value| validate(&v, &post_schema()).unwrap_err();
+
+        let e = errors(json!({"text": "Hello world", "hashtags": ["a"]}));
+        assert!(e.contains("$: missing required property 'tone'"), "{}", e);
+
+        let e = errors(json!({"text": 42, "tone": "angry", "hashtags": "rust"}));
+        assert!(e.contains("$.text: expected string, got number"), "{}", e);
+        assert!(e.contains("$.tone: must be one of \"friendly\", \"formal\""), "{}", e);
+        assert!(e.contains("$.hashtags: expected array, got string"), "{}", e);
+
+        let e = errors(json!({"text": "Hi", "tone": "formal", "hashtags": [], "score": 11, "extra": 1}));
+        assert!(e.contains("$.text: shorter than 5 characters"), "{}", e);
+        assert!(e.contains("$.hashtags: expected at least 1 items, got 0"), "{}", e);
+        assert!(e.contains("$.score: must be <= 10"), "{}", e);
+        assert!(e.contains("$.extra: unexpected property"), "{}", e);
+
+        let e = errors(json!({"text": "x".repeat(21), "tone": "formal", "hashtags": ["a", "b", 3], "score": 1.5}));
+        assert!(e.contains("$.text: longer than 20 characters"), "{}", e);
+        assert!(e.contains("$.hashtags: expected at most 2 items, got 3"), "{}", e);
+        assert!(e.contains("$.hashtags[2]: expected string, got number"), "{}", e);
+        assert!(e.contains("$.score: expected integer, got number"), "{}", e);
+    }
+
+    fn post_request() -> (LlmRequest, JsonSchema) {
+        (LlmRequest::prompt("Write a post"), JsonSchema::new("post", post_schema()))
+    }
+
+    #[tokio::test]
+    async fn invalid_output_is_fixed_on_retry() {
+        let provider = MockProvider::scripted(vec![
+            reply("Here you go: {\"text\": \"Hello world\", \"hashtags\": [\"rust\"]}", vec![]),
+            reply("{\"text\": \"Hello world\", \"tone\": \"friendly\", \"hashtags\": [\"rust\"]}", vec![]),
+        ]);
+        let (req, schema) = post_request();
+
+        let (label, v) = generate_structured(&provider, &req, &schema, 2).await.unwrap();
+        assert_eq!(label, "mock");
+        assert_eq!(v["tone"], "friendly");
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn the_repair_prompt_carries_the_schema_errors() {
+        let provider = MockProvider::scripted(vec![
+            reply("{\"text\": \"Hello world\", \"tone\": \"angry\", \"hashtags\": [\"rust\"]}", vec![]),
+            reply("{\"text\": \"Hello world\", \"tone\": \"formal\", \"hashtags\": [\"rust\"]}", vec![]),
+        ]);
+        let (req, schema) = post_request();
+        generate_structured(&provider, &req, &schema, 2).await.unwrap();
+
+        let requests = provider.requests();
+        let first = &requests[0];
+        assert!(first.system.as_deref().unwrap().contains("JSON schema"));
+        assert!(first.response_schema.is_some());
+
+        let retry = &requests[1].messages;
+        assert_eq!(retry[retry.len() - 2].role, "assistant");
+        let repair = &retry[retry.len() - 1];
+        assert_eq!(repair.role, "user");
+        assert!(repair.content.contains("$.tone: must be one of \"friendly\", \"formal\""), "{}", repair.content);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries_with_the_last_error() {
+        let provider = MockProvider::scripted(vec![
+            reply("not json", vec![]),
+            reply("{\"text\": \"Hi\", \"tone\": \"formal\", \"hashtags\": [\"rust\"]}", vec![]),
+        ]);
+        let (req, schema) = post_request();
+
+        let e = generate_structured(&provider, &req, &schema, 1).await.err().unwrap_or_default();
+        assert!(e.contains("after 2 attempt(s)"), "{}", e);
+        assert!(e.contains("$.text: shorter than 5 characters"), "{}", e);
+        assert!(!e.contains("no JSON"), "{}", e);
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[test]
+    fn long_error_lists_are_truncated() {
+        let schema = json!({"type": "array", "items": {"type": "string"}});
+        let e = validate(&json!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]), &schema).unwrap_err();
+        assert_eq!(e.lines().count(), MAX_REPORTED_ERRORS + 1);
+        assert!(e.ends_with("(+2 more)"));
+    }
+}
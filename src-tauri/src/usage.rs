@@ -0,0 +1,477 @@
+// -------------------------
+// ✅ LLM usage + cost accounting
+// - every routed call writes an llm_usage row linked to its log entry
+// - cost = tokens x llm_prices (USD per 1M tokens, editable)
+// - llm_budgets caps monthly spend (global / per provider / per agent)
+// -------------------------
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::llm::{LlmResponse, Usage};
+use crate::{ensure_agents_table, normalize_provider, open_db, write_log, write_log_entry};
+
+// (provider, model, input $/1M, output $/1M). model "*" = provider fallback.
+const DEFAULT_PRICES: [(&str, &str, f64, f64); 8] = [
+    ("gemini", "gemini-1.5-flash", 0.075, 0.30),
+    ("gemini", "text-embedding-004", 0.0, 0.0),
+    ("gemini", "*", 0.35, 1.05),
+    ("openai", "gpt-4o-mini", 0.15, 0.60),
+    ("openai", "text-embedding-3-small", 0.02, 0.0),
+    ("openai", "*", 2.50, 10.00),
+    ("anthropic", "*", 3.00, 15.00),
+    ("local", "*", 0.0, 0.0),
+];
+
+pub fn ensure_usage_tables(conn: &Connection) {
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_usage (
+            id TEXT PRIMARY KEY,
+            agent_id TEXT NULL,
+            log_id TEXT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL,
+            cost_usd REAL NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_prices (
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            input_per_mtok REAL NOT NULL,
+            output_per_mtok REAL NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (provider, model)
+        )",
+        [],
+    );
+
+    let _ = conn.execute(
+        "CREATE TABLE IF NOT EXISTS llm_budgets (
+            scope TEXT PRIMARY KEY,
+            monthly_usd REAL NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    );
+
+    // Seed defaults; user edits (set_llm_price) are never overwritten
+    for (provider, model, input, output) in DEFAULT_PRICES {
+        let _ = conn.execute(
+            "INSERT OR IGNORE INTO llm_prices (provider, model, input_per_mtok, output_per_mtok, updated_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![provider, model, input, output],
+        );
+    }
+}
+
+fn price_for(conn: &Connection, provider: &str, model: &str) -> (f64, f64) {
+    conn.query_row(
+        "SELECT input_per_mtok, output_per_mtok FROM llm_prices
+         WHERE provider=?1 AND (model=?2 OR model='*')
+         ORDER BY model='*' ASC
+         LIMIT 1",
+        params![provider, model],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .unwrap_or((0.0, 0.0))
+}
+
+pub fn cost_usd(conn: &Connection, provider: &str, model: &str, usage: &Usage) -> f64 {
+    let (input_price, output_price) = price_for(conn, provider, model);
+    (usage.input_tokens as f64 * input_price + usage.output_tokens as f64 * output_price) / 1_000_000.0
+}
+
+fn month_to_date_spend(conn: &Connection, scope: &str) -> f64 {
+    let base = "SELECT COALESCE(SUM(cost_usd), 0) FROM llm_usage
+                WHERE created_at >= datetime('now', 'start of month')";
+
+    let res = if scope == "global" {
+        conn.query_row(base, [], |r| r.get(0))
+    } else if let Some(provider) = scope.strip_prefix("provider:") {
+        conn.query_row(&format!("{} AND provider=?1", base), [provider], |r| r.get(0))
+    } else if let Some(agent_id) = scope.strip_prefix("agent:") {
+        conn.query_row(&format!("{} AND agent_id=?1", base), [agent_id], |r| r.get(0))
+    } else {
+        Ok(0.0)
+    };
+
+    res.unwrap_or(0.0)
+}
+
+// Blocks the call when any applicable monthly cap is already used up
+pub fn check_budget(agent_id: Option<&str>, provider: &str) -> Result<(), String> {
+    let conn = open_db()?;
+    ensure_usage_tables(&conn);
+
+    let Some(msg) = exceeded_budget(&conn, agent_id, provider)? else {
+        return Ok(());
+    };
+    match agent_id {
+        Some(id) => crate::write_log_agent("WARN", id, &msg),
+        None => write_log("WARN", &msg),
+    }
+    Err(msg)
+}
+
+// Some(message) for the first scope (global, provider, agent) whose cap is used up
+fn exceeded_budget(conn: &Connection, agent_id: Option<&str>, provider: &str) -> Result<Option<String>, String> {
+    let mut scopes = vec!["global".to_string(), format!("provider:{}", provider)];
+    if let Some(id) = agent_id {
+        scopes.push(format!("agent:{}", id));
+    }
+
+    for scope in scopes {
+        let cap: Option<f64> = conn
+            .query_row(
+                "SELECT monthly_usd FROM llm_budgets WHERE scope=?1",
+                [&scope],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("DB read failed: {}", e))?;
+
+        let Some(cap) = cap else { continue };
+        let spent = month_to_date_spend(conn, &scope);
+
+        if spent >= cap {
+            return Ok(Some(format!(
+                "💸 Monthly LLM budget reached ({}): ${:.2} of ${:.2} spent.",
+                scope, spent, cap
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+// Called after every successful LLM call
+pub fn record_usage(agent_id: Option<&str>, provider: &str, resp: &LlmResponse) {
+    let conn = match open_db() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    ensure_usage_tables(&conn);
+
+    let usage = resp.usage.unwrap_or_default();
+    let cost = cost_usd(&conn, provider, &resp.model, &usage);
+
+    let log_id = write_log_entry(
+        "INFO",
+        agent_id,
+        &format!(
+            "LLM usage: {} / {} in={} out={} cost=${:.6}{}",
+            provider,
+            resp.model,
+            usage.input_tokens,
+            usage.output_tokens,
+            cost,
+            if resp.usage.is_none() { " (usage not reported)" } else { "" }
+        ),
+        Some(provider),
+    );
+
+    let _ = conn.execute(
+        "INSERT INTO llm_usage (id, agent_id, log_id, provider, model, input_tokens, output_tokens, cost_usd, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            agent_id,
+            log_id,
+            provider,
+            resp.model,
+            usage.input_tokens,
+            usage.output_tokens,
+            cost
+        ],
+    );
+}
+
+// "global" | "provider:<name>" | "agent:<agent name>" => stored scope key
+fn parse_scope(conn: &Connection, scope: &str) -> Result<String, String> {
+    let s = scope.trim();
+    if s.eq_ignore_ascii_case("global") {
+        return Ok("global".to_string());
+    }
+    if let Some(p) = s.strip_prefix("provider:") {
+        return Ok(format!("provider:{}", normalize_provider(p)));
+    }
+    if let Some(name) = s.strip_prefix("agent:") {
+        ensure_agents_table(conn);
+        let id: String = conn
+            .query_row(
+                "SELECT id FROM agents WHERE name=?1 ORDER BY created_at DESC LIMIT 1",
+                [name.trim()],
+                |r| r.get(0),
+            )
+            .map_err(|_| "❌ Agent not found by that name.".to_string())?;
+        return Ok(format!("agent:{}", id));
+    }
+    Err("❌ Scope must be: global | provider:<name> | agent:<agent name>".to_string())
+}
+
+fn scope_label(conn: &Connection, scope: &str) -> String {
+    match scope.strip_prefix("agent:") {
+        Some(id) => {
+            let name: Option<String> = conn
+                .query_row("SELECT name FROM agents WHERE id=?1", [id], |r| r.get(0))
+                .optional()
+                .ok()
+                .flatten();
+            format!("agent:{}", name.unwrap_or_else(|| id.to_string()))
+        }
+        None => scope.to_string(),
+    }
+}
+
+// -------------------------
+// ✅ Commands
+// -------------------------
+redacted! {
+    #[tauri::command]
+    pub fn usage_report(days: Option<i64>) -> Result<String, String> {
+        let days = days.unwrap_or(30).max(1);
+
+        let conn = open_db()?;
+        ensure_usage_tables(&conn);
+        ensure_agents_table(&conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT date(u.created_at) AS day, COALESCE(a.name, '(chat)'), u.provider,
+                    COUNT(*), SUM(u.input_tokens), SUM(u.output_tokens), SUM(u.cost_usd)
+             FROM llm_usage u
+             LEFT JOIN agents a ON u.agent_id = a.id
+             WHERE u.created_at >= datetime('now', ?1)
+             GROUP BY day, u.agent_id, u.provider
+             ORDER BY day DESC, SUM(u.cost_usd) DESC",
+            )
+            .map_err(|e| format!("Query failed: {}", e))?;
+
+        let rows = stmt
+            .query_map([format!("-{} days", days)], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                    r.get::<_, i64>(3)?,
+                    r.get::<_, i64>(4)?,
+                    r.get::<_, i64>(5)?,
+                    r.get::<_, f64>(6)?,
+                ))
+            })
+            .map_err(|e| format!("Query map failed: {}", e))?;
+
+        let mut out = format!("📊 LLM usage (last {} days):\n\n", days);
+        let mut total_cost = 0.0;
+        let mut total_tokens = 0;
+        let mut count = 0;
+
+        for (day, agent, provider, calls, input, output, cost) in rows.flatten() {
+            count += 1;
+            total_cost += cost;
+            total_tokens += input + output;
+            out.push_str(&format!(
+                "{} | {} | {} | calls {} | in {} | out {} | ${:.4}\n",
+                day, agent, provider, calls, input, output, cost
+            ));
+        }
+
+        if count == 0 {
+            out.push_str("ℹ️ No LLM calls recorded yet.\n");
+        } else {
+            out.push_str(&format!(
+                "\nTotal: {} tokens, ${:.4}\n",
+                total_tokens, total_cost
+            ));
+        }
+
+        // Budgets (month to date)
+        let mut stmt = conn
+            .prepare("SELECT scope, monthly_usd FROM llm_budgets ORDER BY scope")
+            .map_err(|e| format!("Query failed: {}", e))?;
+        let budgets: Vec<(String, f64)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| format!("Query map failed: {}", e))?
+            .flatten()
+            .collect();
+
+        if !budgets.is_empty() {
+            out.push_str("\n💰 Monthly budgets:\n");
+            for (scope, cap) in budgets {
+                let spent = month_to_date_spend(&conn, &scope);
+                out.push_str(&format!(
+                    "{}: ${:.2} / ${:.2}{}\n",
+                    scope_label(&conn, &scope),
+                    spent,
+                    cap,
+                    if spent >= cap { " ⛔ blocked" } else { "" }
+                ));
+            }
+        }
+
+        out.push_str(&crate::cache::report(&conn, days));
+
+        Ok(out)
+    }
+}
+
+redacted! {
+    #[tauri::command]
+    pub fn set_llm_price(
+        provider: String,
+        model: String,
+        input_per_mtok: f64,
+        output_per_mtok: f64,
+    ) -> Result<String, String> {
+        if input_per_mtok < 0.0 || output_per_mtok < 0.0 {
+            return Err("❌ Prices must be >= 0.".to_string());
+        }
+
+        let provider = normalize_provider(&provider);
+        let model = if model.trim().is_empty() { "*".to_string() } else { model.trim().to_string() };
+
+        let conn = open_db()?;
+        ensure_usage_tables(&conn);
+
+        conn.execute(
+            "INSERT INTO llm_prices (provider, model, input_per_mtok, output_per_mtok, updated_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))
+         ON CONFLICT(provider, model) DO UPDATE SET
+            input_per_mtok = excluded.input_per_mtok,
+            output_per_mtok = excluded.output_per_mtok,
+            updated_at = excluded.updated_at",
+            params![provider, model, input_per_mtok, output_per_mtok],
+        )
+        .map_err(|e| format!("DB update failed: {}", e))?;
+
+        write_log("INFO", &format!("LLM price updated: {} / {}", provider, model));
+        Ok(format!(
+            "✅ Price for {} / {}: ${} in, ${} out (per 1M tokens)",
+            provider, model, input_per_mtok, output_per_mtok
+        ))
+    }
+}
+
+// monthly_usd = None removes the cap
+redacted! {
+    #[tauri::command]
+    pub fn set_llm_budget(scope: String, monthly_usd: Option<f64>) -> Result<String, String> {
+        let conn = open_db()?;
+        ensure_usage_tables(&conn);
+
+        let key = parse_scope(&conn, &scope)?;
+
+        match monthly_usd {
+            Some(cap) if cap >= 0.0 => {
+                conn.execute(
+                    "INSERT INTO llm_budgets (scope, monthly_usd, updated_at)
+                 VALUES (?1, ?2, datetime('now'))
+                 ON CONFLICT(scope) DO UPDATE SET monthly_usd = excluded.monthly_usd, updated_at = excluded.updated_at",
+                    params![key, cap],
+                )
+                .map_err(|e| format!("DB update failed: {}", e))?;
+
+                write_log("INFO", &format!("LLM budget set: {} = ${:.2}/month", key, cap));
+                Ok(format!("✅ Budget {}: ${:.2}/month", scope_label(&conn, &key), cap))
+            }
+            Some(_) => Err("❌ Budget must be >= 0.".to_string()),
+            None => {
+                conn.execute("DELETE FROM llm_budgets WHERE scope=?1", [&key])
+                    .map_err(|e| format!("DB update failed: {}", e))?;
+
+                write_log("INFO", &format!("LLM budget removed: {}", key));
+                Ok(format!("✅ Budget removed for {}", scope_label(&conn, &key)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_usage_tables(&conn);
+        conn
+    }
+
+    fn usage(input_tokens: i64, output_tokens: i64) -> Usage {
+        Usage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    fn spend(conn: &Connection, agent_id: Option<&str>, provider: &str, cost: f64) {
+        conn.execute(
+            "INSERT INTO llm_usage (id, agent_id, provider, model, input_tokens, output_tokens, cost_usd, created_at)
+             VALUES (?1, ?2, ?3, 'm', 0, 0, ?4, datetime('now'))",
+            params![uuid::Uuid::new_v4().to_string(), agent_id, provider, cost],
+        )
+        .unwrap();
+    }
+
+    fn cap(conn: &Connection, scope: &str, usd: f64) {
+        conn.execute(
+            "INSERT OR REPLACE INTO llm_budgets (scope, monthly_usd, updated_at) VALUES (?1, ?2, datetime('now'))",
+            params![scope, usd],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn cost_uses_the_model_price_then_the_provider_fallback() {
+        let conn = db();
+        let million = usage(1_000_000, 1_000_000);
+
+        // exact model row
+        assert!((cost_usd(&conn, "openai", "gpt-4o-mini", &million) - 0.75).abs() < 1e-9);
+        // "*" row for unknown models of a known provider
+        assert!((cost_usd(&conn, "openai", "gpt-4o", &million) - 12.5).abs() < 1e-9);
+        assert!((cost_usd(&conn, "anthropic", "claude-3-5-sonnet-20240620", &usage(2000, 1000)) - 0.021).abs() < 1e-9);
+        assert_eq!(cost_usd(&conn, "local", "phi3", &million), 0.0);
+        assert_eq!(cost_usd(&conn, "unknown", "x", &million), 0.0);
+
+        // user edits are kept over the seeded defaults
+        conn.execute(
+            "UPDATE llm_prices SET input_per_mtok=1.0, output_per_mtok=2.0 WHERE provider='openai' AND model='gpt-4o-mini'",
+            [],
+        )
+        .unwrap();
+        ensure_usage_tables(&conn);
+        assert!((cost_usd(&conn, "openai", "gpt-4o-mini", &million) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn budgets_apply_per_scope() {
+        let conn = db();
+        spend(&conn, Some("a1"), "openai", 4.0);
+        spend(&conn, None, "gemini", 1.0);
+        assert_eq!(exceeded_budget(&conn, Some("a1"), "openai"), Ok(None));
+
+        cap(&conn, "provider:gemini", 1.0);
+        assert!(exceeded_budget(&conn, None, "openai").unwrap().is_none());
+        let msg = exceeded_budget(&conn, None, "gemini").unwrap().unwrap();
+        assert!(msg.contains("(provider:gemini): $1.00 of $1.00"), "{}", msg);
+
+        cap(&conn, "agent:a1", 5.0);
+        assert!(exceeded_budget(&conn, Some("a1"), "openai").unwrap().is_none());
+        spend(&conn, Some("a1"), "openai", 1.5);
+        assert!(exceeded_budget(&conn, Some("a1"), "openai").unwrap().unwrap().contains("agent:a1"));
+        // other agents are not capped by a1's budget
+        assert!(exceeded_budget(&conn, Some("a2"), "openai").unwrap().is_none());
+
+        cap(&conn, "global", 6.0);
+        assert!(exceeded_budget(&conn, Some("a2"), "openai").unwrap().unwrap().contains("(global)"));
+
+        // last month's spend does not count
+        conn.execute("UPDATE llm_usage SET created_at=datetime('now', 'start of month', '-1 days')", [])
+            .unwrap();
+        assert!(exceeded_budget(&conn, Some("a1"), "gemini").unwrap().is_none());
+    }
+}
@@ -1,7 +1,11 @@
-import { useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
+// automation-output streams: stdout | stderr | progress | warning | result | error
+const RUN_LINE_PREFIX = { stderr: "⚠️ ", progress: "… ", warning: "⚠️ ", error: "❌ " };
+
 function App() {
   const [isOpen, setIsOpen] = useState(false);
   const [messages, setMessages] = useState([
@@ -10,12 +14,60 @@ function App() {
   const [input, setInput] = useState("");
   const [pendingAction, setPendingAction] = useState(null); // kept (not used much)
   const [pendingAgent, setPendingAgent] = useState(null);
+  const [conversationId, setConversationId] = useState(null);
+  const chatRequestId = useRef(null); // in-flight chat reply ("stop" cancels it)
+
+  // Live automation output: every run gets a message that grows line by line
+  // (runFor = run id, lastSeq = last line shown; "attach <run id>" reattaches)
+  useEffect(() => {
+    const appendToRun = (runId, fn) =>
+      setMessages((prev) => prev.map((m) => (m.runFor === runId ? fn(m) : m)));
+
+    const subs = [
+      listen("automation-run", (event) => {
+        const r = event.payload;
+        if (r.status === "queued") {
+          setMessages((prev) => [
+            ...prev,
+            { role: "assistant", content: `⏳ ${r.script} queued (run ${r.run_id})`, runFor: r.run_id, lastSeq: 0 },
+          ]);
+        } else if (r.status === "running") {
+          appendToRun(r.run_id, (m) => ({ ...m, content: `${m.content}\n🏃 started` }));
+        } else {
+          appendToRun(r.run_id, (m) => ({ ...m, content: `${m.content}\n— ${r.status}` }));
+        }
+      }),
+      listen("automation-output", (event) => {
+        const o = event.payload;
+        appendToRun(o.run_id, (m) =>
+          o.seq <= m.lastSeq
+            ? m
+            : { ...m, lastSeq: o.seq, content: `${m.content}\n${RUN_LINE_PREFIX[o.stream] ?? ""}${o.line}` }
+        );
+      }),
+      // session expired (agents paused) / valid again after login (agents resumed)
+      listen("session-status", (event) => {
+        const s = event.payload;
+        const content =
+          s.status === "expired"
+            ? `🔑 The ${s.platform}/${s.account} session has expired.` +
+              (s.paused_agents.length ? `\nPaused agents: ${s.paused_agents.join(", ")}` : "") +
+              `\nLog in again with: ${s.platform} login${s.account === "default" ? "" : ` ${s.account}`}`
+            : `✅ ${s.platform}/${s.account} session is valid again.` +
+              (s.resumed_agents.length ? `\nResumed agents: ${s.resumed_agents.join(", ")}` : "");
+        setMessages((prev) => [...prev, { role: "assistant", content }]);
+      }),
+    ];
+    return () => subs.forEach((p) => p.then((unlisten) => unlisten()));
+  }, []);
 
   const sendMessage = async () => {
     if (!input.trim()) return;
 
     const userMessage = input.trim();
-    setMessages((prev) => [...prev, { role: "user", content: userMessage }]);
+    // never echo the vault passphrase into the chat history
+    const shown = /^unlock vault /i.test(userMessage) ? "unlock vault ••••••" : userMessage;
+    setMessages((prev) => [...prev, { role: "user", content: shown }]);
     setInput("");
 
     try {
@@ -120,6 +172,34 @@ function App() {
       else if (lowerMsg === "clear key" || lowerMsg === "remove key") {
         response = await invoke("clear_user_api_key");
       }
+      else if (lowerMsg.startsWith("clear key ")) {
+        const provider = userMessage.slice(10).trim();
+        response = await invoke("clear_user_api_key", { llmProvider: provider });
+      }
+
+      // ----------------------------
+      // ✅ ACTIVE PROVIDER (use gemini | openai | claude | local)
+      // ----------------------------
+      else if (lowerMsg.startsWith("use provider ")) {
+        const provider = userMessage.slice(13).trim();
+        response = await invoke("set_active_provider", { llmProvider: provider });
+      }
+
+      // ✅ AGENT PROVIDER <name>: <provider|default>
+      else if (lowerMsg.startsWith("agent provider ")) {
+        const rest = userMessage.slice(15);
+        const idx = rest.lastIndexOf(":");
+        if (idx === -1) {
+          response = "❌ Format:\nagent provider <agent name>: <gemini|openai|claude|local|default>";
+        } else {
+          const agentName = rest.slice(0, idx).trim();
+          const provider = rest.slice(idx + 1).trim();
+          response = await invoke("set_agent_provider", {
+            agentName,
+            llmProvider: provider.toLowerCase() === "default" ? null : provider,
+          });
+        }
+      }
 
       // ----------------------------
       // ✅ SET KEY
@@ -146,6 +226,46 @@ function App() {
         }
       }
 
+      // ----------------------------
+      // ✅ USAGE + BUDGETS
+      // ----------------------------
+      else if (lowerMsg === "usage" || lowerMsg.startsWith("usage report")) {
+        const days = parseInt(userMessage.replace(/^usage( report)?/i, "").trim(), 10);
+        response = await invoke("usage_report", { days: Number.isNaN(days) ? null : days });
+      }
+      // set budget <global|provider:gemini|agent:Name> <usd|none>
+      else if (lowerMsg.startsWith("set budget ")) {
+        const rest = userMessage.slice(11).trim();
+        const idx = rest.lastIndexOf(" ");
+        const scope = idx === -1 ? "" : rest.slice(0, idx).trim();
+        const amount = idx === -1 ? "" : rest.slice(idx + 1).trim().toLowerCase();
+        if (!scope || !amount) {
+          response = "❌ Format:\nset budget global 20\nset budget provider:openai 10\nset budget agent:Trending Agent none";
+        } else {
+          response = await invoke("set_llm_budget", {
+            scope,
+            monthlyUsd: amount === "none" ? null : parseFloat(amount),
+          });
+        }
+      }
+
+      // ----------------------------
+      // ✅ SECRETS VAULT
+      // ----------------------------
+      else if (lowerMsg.startsWith("unlock vault ")) {
+        // "unlock vault remember <passphrase>" also stores the key in the OS keyring
+        let passphrase = userMessage.slice(13).trim();
+        const remember = /^remember\s+/i.test(passphrase);
+        if (remember) passphrase = passphrase.replace(/^remember\s+/i, "");
+        response = await invoke("unlock_vault", { passphrase, remember });
+      }
+      else if (lowerMsg === "lock vault") {
+        response = await invoke("lock_vault");
+      }
+      else if (lowerMsg === "vault status" || lowerMsg === "vault") {
+        response = await invoke("vault_status");
+      }
+
       // ----------------------------
       // ✅ SETUP OPENCLAW
       // ----------------------------
@@ -156,8 +276,41 @@ function App() {
       // ----------------------------
       // ✅ LINKEDIN LOGIN
       // ----------------------------
-      else if (lowerMsg === "linkedin login") {
-        response = await invoke("linkedin_login");
+      // ✅ LINKEDIN SESSION / LOGIN [account label]
+      else if (lowerMsg === "linkedin session" || lowerMsg.startsWith("linkedin session ")) {
+        const account = userMessage.slice(16).trim();
+        response = await invoke("check_linkedin_session", { account: account || null });
+      }
+      else if (lowerMsg === "linkedin login" || lowerMsg.startsWith("linkedin login ")) {
+        const account = userMessage.slice(14).trim();
+        response = await invoke("linkedin_login", { account: account || null });
+      }
+
+      // ✅ ACCOUNTS: accounts | account add <platform> <label> | account remove <platform> <label>
+      //    | agent account <agent name>: <label|default>  (LinkedIn)
+      else if (lowerMsg === "accounts") {
+        response = await invoke("list_accounts");
+      }
+      else if (/^account (add|remove) /.test(lowerMsg)) {
+        const [action, platform, ...label] = userMessage.trim().split(/\s+/).slice(1);
+        response = await invoke(action.toLowerCase() === "add" ? "add_account" : "remove_account", {
+          platform: platform || "",
+          label: label.join(" "),
+        });
+      }
+      else if (lowerMsg.startsWith("agent account ")) {
+        const rest = userMessage.slice(14);
+        const idx = rest.lastIndexOf(":");
+        if (idx === -1) {
+          response = "❌ Format:\nagent account <agent name>: <account label|default>";
+        } else {
+          const label = rest.slice(idx + 1).trim();
+          response = await invoke("set_agent_account", {
+            agentName: rest.slice(0, idx).trim(),
+            platform: "linkedin",
+            label: label.toLowerCase() === "default" ? null : label,
+          });
+        }
       }
 
       // ----------------------------
@@ -165,7 +318,11 @@ function App() {
       // ----------------------------
       else if (lowerMsg.startsWith("linkedin post:")) {
         const text = userMessage.replace(/^linkedin post:/i, "").trim();
-        response = await invoke("linkedin_post", { text });
+        response = await invoke("linkedin_post", { text, dryRun: null });
+      }
+      else if (lowerMsg.startsWith("linkedin dry post:")) {
+        const text = userMessage.replace(/^linkedin dry post:/i, "").trim();
+        response = await invoke("linkedin_post", { text, dryRun: true });
       }
 
       // ----------------------------
@@ -186,11 +343,142 @@ function App() {
         response = await invoke("list_pending_approvals");
       }
 
-      // ✅ APPROVE <id>
+      // ✅ APPROVAL PROMPT <id> (audit: what the LLM was asked)
+      else if (lowerMsg.startsWith("approval prompt ")) {
+        const id = userMessage.slice(16).trim();
+        response = await invoke("get_approval_prompt", { id });
+      }
+
+      // ✅ STYLE GUIDE
+      else if (lowerMsg === "style guide") {
+        response = await invoke("show_style_guide");
+      }
+      else if (lowerMsg.startsWith("set style guide:")) {
+        const styleGuide = userMessage.replace(/^set style guide:/i, "").trim();
+        response = await invoke("set_style_guide", { styleGuide: styleGuide || null });
+      }
+
+      // ✅ LOCAL MODELS (Ollama)
+      else if (lowerMsg === "ollama status") {
+        response = await invoke("ollama_status");
+      }
+      else if (lowerMsg === "local models") {
+        response = await invoke("list_local_models");
+      }
+      else if (lowerMsg.startsWith("pull model ")) {
+        const model = userMessage.slice(11).trim();
+        setMessages((prev) => [...prev, { role: "assistant", content: `⬇️ Pulling ${model}...`, pullFor: model }]);
+        const unlisten = await listen("ollama-pull-progress", (event) => {
+          const p = event.payload;
+          if (p.model !== model) return;
+          const pct = p.percent != null ? ` ${p.percent}%` : "";
+          setMessages((prev) =>
+            prev.map((m) => (m.pullFor === model ? { ...m, content: `⬇️ ${model}: ${p.status}${pct}` } : m))
+          );
+        });
+        try {
+          response = await invoke("pull_local_model", { model });
+        } finally {
+          unlisten();
+        }
+      }
+      else if (lowerMsg.startsWith("delete model ")) {
+        const model = userMessage.slice(13).trim();
+        response = await invoke("delete_local_model", { model });
+      }
+      else if (lowerMsg.startsWith("use local model ")) {
+        const model = userMessage.slice(16).trim();
+        response = await invoke("set_local_model", { model });
+      }
+      else if (lowerMsg.startsWith("ollama url")) {
+        const url = userMessage.slice(10).trim();
+        response = await invoke("set_ollama_url", { url: url || null });
+      }
+
+      // ✅ AGENT LOOP: run agent <name>[: task]
+      else if (lowerMsg.startsWith("run agent ")) {
+        const rest = userMessage.slice(10);
+        const idx = rest.indexOf(":");
+        const agentName = (idx === -1 ? rest : rest.slice(0, idx)).trim();
+        const task = idx === -1 ? null : rest.slice(idx + 1).trim();
+        response = await invoke("run_agent", { agentName, task });
+      }
+      else if (lowerMsg === "agent runs") {
+        response = await invoke("list_agent_runs");
+      }
+      else if (lowerMsg.startsWith("agent run ")) {
+        const id = userMessage.slice(10).trim();
+        response = await invoke("show_agent_run", { id });
+      }
+
+      // ✅ STRUCTURED OUTPUT: json <prompt> :: <JSON schema>
+      else if (lowerMsg.startsWith("json ")) {
+        const rest = userMessage.slice(5);
+        const idx = rest.indexOf("::");
+        if (idx === -1) {
+          response = "❌ Format:\njson <prompt> :: {\"type\":\"object\",\"properties\":{...},\"required\":[...]}";
+        } else {
+          response = await invoke("llm_structured", {
+            prompt: rest.slice(0, idx).trim(),
+            schema: rest.slice(idx + 2).trim(),
+          });
+        }
+      }
+
+      // ✅ PROMPT TEMPLATES
+      else if (lowerMsg === "templates") {
+        response = await invoke("list_prompt_templates");
+      }
+      else if (lowerMsg.startsWith("template ")) {
+        const name = userMessage.slice(9).trim();
+        response = await invoke("show_prompt_template", { name });
+      }
+      // save template <name> [{"topic":"text"}]: <body>
+      else if (lowerMsg.startsWith("save template ")) {
+        const rest = userMessage.slice(14);
+        const idx = rest.indexOf(":");
+        if (idx === -1) {
+          response = "❌ Format:\nsave template <name> [{\"var\":\"text|url|list|number\"}]: <body>";
+        } else {
+          const head = rest.slice(0, idx).trim();
+          const body = rest.slice(idx + 1).trim();
+          const braceIdx = head.indexOf("{");
+          const name = braceIdx === -1 ? head : head.slice(0, braceIdx).trim();
+          const variables = braceIdx === -1 ? null : head.slice(braceIdx);
+          response = await invoke("save_prompt_template", { name, body, variables });
+        }
+      }
+      // preview template <name> {"topic":"AI agents"}
+      else if (lowerMsg.startsWith("preview template ")) {
+        const rest = userMessage.slice(17).trim();
+        const braceIdx = rest.indexOf("{");
+        const name = braceIdx === -1 ? rest : rest.slice(0, braceIdx).trim();
+        const variables = braceIdx === -1 ? null : rest.slice(braceIdx);
+        response = await invoke("render_prompt_template", { name, variables });
+      }
+      // agent template <agent name>: <template|default>
+      else if (lowerMsg.startsWith("agent template ")) {
+        const rest = userMessage.slice(15);
+        const idx = rest.lastIndexOf(":");
+        if (idx === -1) {
+          response = "❌ Format:\nagent template <agent name>: <template name|default>";
+        } else {
+          const agentName = rest.slice(0, idx).trim();
+          const template = rest.slice(idx + 1).trim();
+          response = await invoke("set_agent_template", {
+            agentName,
+            template: template.toLowerCase() === "default" ? null : template,
+          });
+        }
+      }
+
+      // ✅ APPROVE <id> [dry]
       else if (lowerMsg.startsWith("approve ")) {
-        const id = userMessage.slice(8).trim();
-        if (!id) response = "❌ Usage: approve <id>";
-        else response = await invoke("approve_action", { id });
+        const parts = userMessage.slice(8).trim().split(/\s+/);
+        const id = parts[0] || "";
+        const dry = (parts[1] || "").toLowerCase() === "dry";
+        if (!id) response = "❌ Usage: approve <id> [dry]";
+        else response = await invoke("approve_action", { id, dryRun: dry ? true : null });
       }
 
       // ----------------------------
@@ -226,9 +514,221 @@ else if (lowerMsg === "run demo2") {
 else if (lowerMsg === "scheduler tick") {
   response = await invoke("scheduler_tick_now");
 }
+else if (lowerMsg.startsWith("set repo url ")) {
+  response = await invoke("set_repo_url", { url: userMessage.trim().slice(13).trim() });
+}
+
+      // ----------------------------
+      // ✅ CONVERSATIONS
+      // ----------------------------
+      else if (lowerMsg === "new chat") {
+        const id = await invoke("new_conversation", { title: null });
+        setConversationId(id);
+        response = `✅ Started a new chat (id: ${id})`;
+      }
+      else if (lowerMsg === "chats" || lowerMsg === "list chats") {
+        response = await invoke("list_conversations");
+      }
+      else if (lowerMsg.startsWith("open chat ")) {
+        const id = userMessage.slice(10).trim();
+        setConversationId(id);
+        response = `✅ Continuing chat ${id}`;
+      }
+      else if (lowerMsg.startsWith("rename chat ")) {
+        const title = userMessage.slice(12).trim();
+        if (!conversationId) response = "❌ No active chat. Type: new chat";
+        else response = await invoke("rename_conversation", { id: conversationId, title });
+      }
+      else if (lowerMsg.startsWith("delete chat ")) {
+        const id = userMessage.slice(12).trim();
+        response = await invoke("delete_conversation", { id });
+        if (id === conversationId) setConversationId(null);
+      }
+
+      // ✅ RESPONSE CACHE: cache on <minutes> | cache off | clear cache
+      else if (lowerMsg.startsWith("cache on")) {
+        const minutes = parseInt(userMessage.slice(8).trim(), 10);
+        response = await invoke("set_llm_cache", { ttlMinutes: Number.isNaN(minutes) ? 60 : minutes });
+      }
+      else if (lowerMsg === "cache off") {
+        response = await invoke("set_llm_cache", { ttlMinutes: null });
+      }
+      else if (lowerMsg === "clear cache") {
+        response = await invoke("clear_llm_cache");
+      }
+
+      // ✅ AGENT MEMORY: agent memory <name> | forget memory <name> | memory days <n> | memory similarity <0.5-1>
+      else if (lowerMsg.startsWith("agent memory ")) {
+        response = await invoke("agent_memory", { agentName: userMessage.slice(13).trim() });
+      }
+      else if (lowerMsg.startsWith("forget memory ")) {
+        response = await invoke("forget_agent_memory", { agentName: userMessage.slice(14).trim() });
+      }
+      else if (lowerMsg.startsWith("memory days ")) {
+        const days = parseInt(userMessage.slice(12).trim(), 10);
+        response = Number.isNaN(days)
+          ? "❌ Format:\nmemory days <n>   (0 = off)"
+          : await invoke("set_memory_settings", { days, similarity: null });
+      }
+      else if (lowerMsg.startsWith("memory similarity ")) {
+        const similarity = parseFloat(userMessage.slice(18).trim());
+        response = Number.isNaN(similarity)
+          ? "❌ Format:\nmemory similarity <0.5-1.0>"
+          : await invoke("set_memory_settings", { days: null, similarity });
+      }
+
+      // ✅ DRAFT EVAL: eval drafts [agent name]
+      else if (lowerMsg === "eval drafts" || lowerMsg.startsWith("eval drafts ")) {
+        const agentName = userMessage.slice(11).trim();
+        response = await invoke("run_draft_eval", { agentName: agentName || null });
+      }
+
+      // ✅ AUTOMATIONS: automations | scripts dir <path|default>
+      else if (lowerMsg === "automations") {
+        response = await invoke("list_automations");
+      }
+      else if (lowerMsg.startsWith("scripts dir")) {
+        const path = userMessage.slice(11).trim();
+        response = await invoke("set_scripts_dir", { path: path.toLowerCase() === "default" ? null : path });
+      }
+
+      // ✅ DRY RUN: dry run on|off | sandbox <agent> on|off
+      else if (lowerMsg === "dry run on" || lowerMsg === "dry run off") {
+        response = await invoke("set_dry_run", { enabled: lowerMsg.endsWith("on") });
+      }
+      else if (lowerMsg.startsWith("sandbox ")) {
+        const m = userMessage.slice(8).trim().match(/^(.+)\s+(on|off)$/i);
+        if (!m) response = "❌ Usage: sandbox <agent name> on|off";
+        else response = await invoke("set_agent_sandbox", { agentName: m[1].trim(), enabled: m[2].toLowerCase() === "on" });
+      }
+
+      // ✅ JOB QUEUE: jobs | max browsers <n>
+      else if (lowerMsg === "jobs") {
+        response = await invoke("list_jobs");
+      }
+      else if (lowerMsg.startsWith("max browsers ")) {
+        const max = parseInt(userMessage.slice(13).trim(), 10);
+        response = Number.isNaN(max) ? "❌ Usage: max browsers <1-8>" : await invoke("set_max_browsers", { max });
+      }
+
+      // ✅ AUTOMATION RUNS: runs | stop runs | cancel run <id> | attach <id>
+      else if (lowerMsg === "runs") {
+        response = await invoke("list_runs", { limit: 10 });
+      }
+      else if (lowerMsg === "stop runs") {
+        response = await invoke("cancel_run", { runId: null });
+      }
+      else if (lowerMsg.startsWith("cancel run ")) {
+        response = await invoke("cancel_run", { runId: userMessage.slice(11).trim() });
+      }
+      else if (lowerMsg.startsWith("attach ")) {
+        const runId = userMessage.slice(7).trim();
+        const transcript = String(await invoke("run_output", { runId }));
+        const shown = Number(transcript.match(/, (\d+) lines/)?.[1] ?? 0);
+        setMessages((prev) => [
+          ...prev.map((m) => (m.runFor === runId ? { ...m, runFor: null } : m)),
+          { role: "assistant", content: transcript, runFor: runId, lastSeq: shown },
+        ]);
+        return;
+      }
+
+      // ✅ BROWSER: browser | browser engine <chromium|firefox|webkit> | browser mode <auto|headless|headed> | browser viewport <WxH|default>
+      else if (lowerMsg === "browser") {
+        response = await invoke("browser_settings");
+      }
+      else if (/^browser (engine|mode|viewport) /.test(lowerMsg)) {
+        const [, key, value] = userMessage.trim().match(/^browser (\w+) (.+)$/i);
+        response = await invoke("set_browser_settings", {
+          engine: key.toLowerCase() === "engine" ? value : null,
+          mode: key.toLowerCase() === "mode" ? value : null,
+          viewport: key.toLowerCase() === "viewport" ? value : null,
+        });
+      }
+
+      // ✅ RUN ARTIFACTS: artifacts <run id> | open artifacts <run id> [file] | artifact retention <days>
+      else if (lowerMsg.startsWith("artifacts ")) {
+        response = await invoke("list_artifacts", { runId: userMessage.slice(10).trim() });
+      }
+      else if (lowerMsg.startsWith("open artifacts ")) {
+        const [runId, file] = userMessage.slice(15).trim().split(/\s+/);
+        response = await invoke("open_artifact", { runId: runId || "", file: file || null });
+      }
+      else if (lowerMsg.startsWith("artifact retention ")) {
+        const days = parseInt(userMessage.slice(19).trim(), 10);
+        response = Number.isNaN(days)
+          ? "❌ Usage: artifact retention <days>"
+          : await invoke("set_artifact_retention", { days });
+      }
+
+      // ✅ CANCEL: stop (current chat reply) | cancel all | cancel <run id | pull:model>
+      else if (lowerMsg === "stop") {
+        response = chatRequestId.current
+          ? await invoke("cancel_llm", { requestId: chatRequestId.current })
+          : "ℹ️ Nothing to stop.";
+      }
+      else if (lowerMsg === "cancel all") {
+        response = await invoke("cancel_llm", { requestId: null });
+      }
+      else if (lowerMsg.startsWith("cancel ")) {
+        response = await invoke("cancel_llm", { requestId: userMessage.slice(7).trim() });
+      }
+
+      // ✅ HTTP: http settings | http timeout <connect> <read> | proxy <url|none>
+      else if (lowerMsg === "http settings") {
+        response = await invoke("show_http_settings");
+      }
+      else if (lowerMsg.startsWith("http timeout ")) {
+        const [connect, read] = userMessage.slice(13).trim().split(/\s+/).map((n) => parseInt(n, 10));
+        response = Number.isNaN(connect)
+          ? "❌ Format:\nhttp timeout <connect seconds> [read seconds]"
+          : await invoke("set_http_settings", {
+              connectTimeoutSecs: connect,
+              readTimeoutSecs: Number.isNaN(read) || read === undefined ? null : read,
+              proxy: null,
+            });
+      }
+      else if (lowerMsg.startsWith("proxy ")) {
+        response = await invoke("set_http_settings", {
+          connectTimeoutSecs: null,
+          readTimeoutSecs: null,
+          proxy: userMessage.slice(6).trim(),
+        });
+      }
+
+      // ✅ CONTENT GUARD: guard settings | guard set <key> <value> | check content: <text>
+      else if (lowerMsg === "guard settings" || lowerMsg === "guard") {
+        response = await invoke("show_guard_settings");
+      }
+      else if (lowerMsg.startsWith("guard set ")) {
+        const rest = userMessage.slice(10).trim();
+        const space = rest.indexOf(" ");
+        const key = space === -1 ? rest : rest.slice(0, space);
+        const value = space === -1 ? "" : rest.slice(space + 1).trim();
+        response = await invoke("set_guard_setting", { key, value });
+      }
+      else if (lowerMsg.startsWith("check content:")) {
+        const text = userMessage.slice(userMessage.indexOf(":") + 1).trim();
+        response = await invoke("check_content", { text });
+      }
 
       else {
-        response = await invoke("llm_reply", { prompt: userMessage });
+        // "fresh: <prompt>" skips the response cache for this message
+        const fresh = /^fresh:/i.test(userMessage);
+        const prompt = fresh ? userMessage.replace(/^fresh:/i, "").trim() : userMessage;
+
+        // keep follow-ups in one conversation (created on first message)
+        let cid = conversationId;
+        if (!cid) {
+          cid = await invoke("new_conversation", { title: null });
+          setConversationId(cid);
+        }
+        const requestId = crypto.randomUUID();
+        chatRequestId.current = requestId;
+        try {
+          response = await invoke("llm_reply", { prompt, conversationId: cid, noCache: fresh, requestId });
+        } finally {
+          if (chatRequestId.current === requestId) chatRequestId.current = null;
+        }
       }
 
       const text = String(response);